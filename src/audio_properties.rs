@@ -0,0 +1,61 @@
+// std library imports
+use std::fmt;
+use std::time::Duration;
+
+// taglib-sys imports
+use taglib_sys::*;
+
+/* How precise the length reported by taglib is */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DurationPrecision {
+    Seconds,
+    Milliseconds,
+}
+
+/* The audio properties (length etc) of a file */
+pub struct AudioProperties {
+    properties: *const TagLib_AudioProperties,
+}
+
+impl fmt::Debug for AudioProperties {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AudioProperties").finish()
+    }
+}
+
+impl AudioProperties {
+    /// # Safety
+    /// The pointer must stay valid for as long as the properties are used.
+    pub unsafe fn from_ptr(ptr: *const TagLib_AudioProperties) -> AudioProperties {
+        AudioProperties { properties: ptr }
+    }
+
+    // the length in milliseconds, if the linked taglib can tell us that
+    fn length_ms(self: &Self) -> Option<u64> {
+        unsafe {
+            match taglib_ext_audioproperties_length_ms(self.properties) {
+                ms if ms < 0 => None,
+                ms => Some(ms as u64),
+            }
+        }
+    }
+
+    /* The length of the file. If only whole seconds are available, then the
+     * duration is rounded to the second - check precision() to find out */
+    pub fn duration(self: &Self) -> Duration {
+        match self.length_ms() {
+            Some(ms) => Duration::from_millis(ms),
+            None => unsafe {
+                let seconds = taglib_audioproperties_length(self.properties);
+                Duration::from_secs(seconds.max(0) as u64)
+            },
+        }
+    }
+
+    pub fn precision(self: &Self) -> DurationPrecision {
+        match self.length_ms() {
+            Some(_) => DurationPrecision::Milliseconds,
+            None => DurationPrecision::Seconds,
+        }
+    }
+}
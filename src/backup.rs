@@ -0,0 +1,93 @@
+// std library imports
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/* Where saving with SaveOptions::backup copies the original file to, before changing
+ * it. Each mode holds a suffix (like ".bak") which is added to the whole file name, so
+ * "song.mp3" is backed up to "song.mp3.bak" */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum BackupMode {
+    // back up to the suffixed path, unless there's already a backup there, which is kept
+    // (so it holds the file as it was before the first save)
+    Suffix(String),
+    // back up to the suffixed path, replacing any backup that's already there
+    Overwrite(String),
+    // back up to the suffixed path with the first free number added: ".bak.1", ".bak.2"...
+    Numbered(String),
+}
+
+impl BackupMode {
+    fn suffix(self: &Self) -> &str {
+        match *self {
+            BackupMode::Suffix(ref suffix) => suffix,
+            BackupMode::Overwrite(ref suffix) => suffix,
+            BackupMode::Numbered(ref suffix) => suffix,
+        }
+    }
+
+    // the path with the suffix (and a number, if there is one) added
+    fn backup_path(self: &Self, path: &Path, number: Option<u32>) -> PathBuf {
+        let mut name = OsString::from(path.file_name().unwrap_or_default());
+        name.push(self.suffix());
+        if let Some(number) = number {
+            name.push(format!(".{}", number));
+        }
+        path.with_file_name(name)
+    }
+
+    // the numbered backups that exist, in order, stopping at the first gap
+    fn numbered_backups(self: &Self, path: &Path) -> Vec<PathBuf> {
+        (1..)
+            .map(|number| self.backup_path(path, Some(number)))
+            .take_while(|backup| backup.exists())
+            .collect()
+    }
+}
+
+/* Back up path as mode says, returning the backup that was made (if any). The copy is
+ * made under a temporary name and then renamed, so that a failed copy (if the disk is
+ * full, say) never leaves a partial backup, or damages one that's already there */
+pub fn backup(path: &Path, mode: &BackupMode) -> Result<Option<PathBuf>, (PathBuf, io::Error)> {
+    let backup = match *mode {
+        BackupMode::Suffix(_) => {
+            let backup = mode.backup_path(path, None);
+            if backup.exists() {
+                return Ok(None);
+            }
+            backup
+        }
+        BackupMode::Overwrite(_) => mode.backup_path(path, None),
+        BackupMode::Numbered(_) => {
+            let next = mode.numbered_backups(path).len() as u32 + 1;
+            mode.backup_path(path, Some(next))
+        }
+    };
+    let mut temp_name = OsString::from(backup.as_os_str());
+    temp_name.push(".tmp");
+    let temp = PathBuf::from(temp_name);
+    let copied = fs::copy(path, &temp).and_then(|_| fs::rename(&temp, &backup));
+    match copied {
+        Ok(()) => Ok(Some(backup)),
+        Err(err) => {
+            let _ = fs::remove_file(&temp);
+            Err((backup, err))
+        }
+    }
+}
+
+/* Undo saves made with a backup, by copying the most recent backup of path (as made by
+ * mode) back over it. The backup is left in place. Fails with an error of kind NotFound
+ * if there's no backup */
+pub fn restore_from_backup<P: AsRef<Path>>(path: P, mode: &BackupMode) -> io::Result<()> {
+    let path = path.as_ref();
+    let backup = match *mode {
+        BackupMode::Numbered(_) => mode.numbered_backups(path).pop(),
+        _ => Some(mode.backup_path(path, None)).filter(|backup| backup.exists()),
+    };
+    match backup {
+        Some(backup) => fs::copy(backup, path).map(|_| ()),
+        None => Err(io::Error::new(io::ErrorKind::NotFound, "no backup to restore from")),
+    }
+}
@@ -0,0 +1,176 @@
+// std library imports
+use std::convert::TryFrom;
+use std::ffi::CString;
+use std::os::raw::c_int;
+use std::str::Utf8Error;
+
+// taglib-sys imports
+use taglib_sys::*;
+
+use fields::{format_number_pair, parse_number_pair};
+use {FieldWriteError, OptionalStringReadError, PropertyError, SetError, TagField, TagLibFile, TagLibTag};
+
+// the keys for formats without frames or atoms of their own, as Picard writes them
+const WORK: &str = "WORK";
+const MOVEMENT_NAME: &str = "MOVEMENTNAME";
+const MOVEMENT: &str = "MOVEMENT";
+const MOVEMENT_TOTAL: &str = "MOVEMENTTOTAL";
+
+/* The fields for classical music that Apple Music and Roon show: the work, and the
+ * movement of it that the track is. In MP4 these are the ©wrk, ©mvn, ©mvi and ©mvc
+ * atoms, in ID3v2 the MVNM and MVIN ("3/4") frames, and in Vorbis comments WORK,
+ * MOVEMENTNAME, MOVEMENT and MOVEMENTTOTAL. */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ClassicalInfo {
+    pub work: Option<String>,
+    pub movement_name: Option<String>,
+    pub movement_number: Option<u32>,
+    pub movement_total: Option<u32>,
+}
+
+impl TagLibTag {
+    fn mp4_int_item(self: &Self, name: &str) -> Option<u32> {
+        let cs_name = CString::new(name).expect("atom names don't contain nul bytes");
+        let value = unsafe { taglib_ext_mp4_int_item(self.file, cs_name.as_ptr()) };
+        u32::try_from(value).ok().filter(|&n| n != 0)
+    }
+
+    fn set_mp4_int_item(self: &mut Self, field: TagField, name: &str, value: Option<u32>) -> FieldWriteError {
+        let value = match value {
+            Some(value) => c_int::try_from(value).map_err(|_| SetError::malformed(field, &value.to_string()))?,
+            None => -1,
+        };
+        let cs_name = CString::new(name).expect("atom names don't contain nul bytes");
+        self.modified = true;
+        if unsafe { taglib_ext_mp4_set_int_item(self.file, cs_name.as_ptr(), value) } == 0 {
+            return Err(SetError::unsupported(field));
+        }
+        Ok(())
+    }
+
+    /* The work, e.g. "Symphony No. 9 in D minor, Op. 125". In ID3v2, taglib 1.12 and
+     * later keep this in TIT1, and earlier versions in a TXXX frame described as WORK,
+     * so that's read too, where TIT1 is empty */
+    pub fn work(self: &Self) -> OptionalStringReadError {
+        if self.is_mp4() {
+            return self.mp4_item("©wrk");
+        }
+        match self.field_property(WORK)? {
+            None if self.has_id3v2_properties() => match self.user_text(WORK) {
+                Err(PropertyError::InvalidValue(err)) => Err(err),
+                work => Ok(work.unwrap_or(None)),
+            },
+            work => Ok(work),
+        }
+    }
+
+    // None removes the field, including any TXXX frame described as WORK
+    pub fn set_work<'a, V: Into<Option<&'a str>>>(self: &mut Self, work: V) -> FieldWriteError {
+        let work = work.into();
+        if self.is_mp4() {
+            return self.set_mapped_field(TagField::Work, WORK, "", "©wrk", work);
+        }
+        self.set_field_property(TagField::Work, WORK, work)?;
+        match work {
+            None | Some("") if self.has_id3v2_properties() => {
+                self.remove_user_text(WORK).map_err(|_| SetError::unsupported(TagField::Work))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // e.g. "II. Molto vivace"
+    pub fn movement_name(self: &Self) -> OptionalStringReadError {
+        self.mapped_field(MOVEMENT_NAME, "MVNM", "©mvn")
+    }
+
+    pub fn set_movement_name<'a, V: Into<Option<&'a str>>>(self: &mut Self, movement_name: V) -> FieldWriteError {
+        self.set_mapped_field(TagField::MovementName, MOVEMENT_NAME, "MVNM", "©mvn", movement_name.into())
+    }
+
+    /* The movement number, and the number of movements in the work, if it's there */
+    pub fn movement(self: &Self) -> Option<(u32, Option<u32>)> {
+        if self.is_mp4() {
+            return Some((self.mp4_int_item("©mvi")?, self.mp4_int_item("©mvc")));
+        }
+        if self.has_id3v2_properties() {
+            return parse_number_pair(&self.mapped_field(MOVEMENT, "MVIN", "").ok()??);
+        }
+        self.number_pair_property(MOVEMENT, MOVEMENT_TOTAL)
+    }
+
+    /* Set the movement number, and optionally the number of movements. None (or 0) for
+     * the number removes both. */
+    pub fn set_movement<N: Into<Option<u32>>, T: Into<Option<u32>>>(self: &mut Self, number: N, total: T) -> FieldWriteError {
+        let number = number.into().filter(|&n| n != 0);
+        let total = number.and(total.into()).filter(|&n| n != 0);
+        if self.is_mp4() {
+            self.set_mp4_int_item(TagField::Movement, "©mvi", number)?;
+            return self.set_mp4_int_item(TagField::Movement, "©mvc", total);
+        }
+        if self.has_id3v2_properties() {
+            let value = number.map(|n| format_number_pair(n, total));
+            return self.set_mapped_field(TagField::Movement, MOVEMENT, "MVIN", "", value.as_deref());
+        }
+        self.set_field_property(TagField::Movement, MOVEMENT, number.map(|n| n.to_string()).as_deref())?;
+        self.set_field_property(TagField::Movement, MOVEMENT_TOTAL, total.map(|n| n.to_string()).as_deref())
+    }
+
+    pub fn classical_info(self: &Self) -> Result<ClassicalInfo, Utf8Error> {
+        let movement = self.movement();
+        Ok(ClassicalInfo {
+            work: self.work()?,
+            movement_name: self.movement_name()?,
+            movement_number: movement.map(|(number, _)| number),
+            movement_total: movement.and_then(|(_, total)| total),
+        })
+    }
+
+    /* Replace all of the fields, removing those that are None. Both strings are checked
+     * before anything is written. */
+    pub fn set_classical_info(self: &mut Self, info: &ClassicalInfo) -> FieldWriteError {
+        if let Some(ref work) = info.work {
+            self.to_c_string(TagField::Work, work)?;
+        }
+        if let Some(ref movement_name) = info.movement_name {
+            self.to_c_string(TagField::MovementName, movement_name)?;
+        }
+        self.set_work(info.work.as_deref())?;
+        self.set_movement_name(info.movement_name.as_deref())?;
+        self.set_movement(info.movement_number, info.movement_total)
+    }
+}
+
+impl TagLibFile {
+    pub fn work(self: &Self) -> OptionalStringReadError {
+        self.tag.work()
+    }
+
+    pub fn set_work<'a, V: Into<Option<&'a str>>>(self: &mut Self, work: V) -> FieldWriteError {
+        self.tag.set_work(work)
+    }
+
+    pub fn movement_name(self: &Self) -> OptionalStringReadError {
+        self.tag.movement_name()
+    }
+
+    pub fn set_movement_name<'a, V: Into<Option<&'a str>>>(self: &mut Self, movement_name: V) -> FieldWriteError {
+        self.tag.set_movement_name(movement_name)
+    }
+
+    pub fn movement(self: &Self) -> Option<(u32, Option<u32>)> {
+        self.tag.movement()
+    }
+
+    pub fn set_movement<N: Into<Option<u32>>, T: Into<Option<u32>>>(self: &mut Self, number: N, total: T) -> FieldWriteError {
+        self.tag.set_movement(number, total)
+    }
+
+    pub fn classical_info(self: &Self) -> Result<ClassicalInfo, Utf8Error> {
+        self.tag.classical_info()
+    }
+
+    pub fn set_classical_info(self: &mut Self, info: &ClassicalInfo) -> FieldWriteError {
+        self.tag.set_classical_info(info)
+    }
+}
@@ -0,0 +1,150 @@
+// std library imports
+use std::error::Error;
+use std::fmt;
+
+use {FieldReadError, PictureError, SetError, TagField, TagLibFile, TagLibTag};
+
+// taglib-sys imports
+use taglib_sys::*;
+
+/* Errors from copying the tags of one file to another */
+#[derive(Debug)]
+pub enum CopyError {
+    // a field of the source couldn't be read
+    Read(FieldReadError),
+    // a field couldn't be written to the destination
+    Write(SetError),
+    // the pictures couldn't be read, or written to the destination
+    Pictures(PictureError),
+    // everything that could be copied was, but the destination format couldn't store
+    // these fields/properties (or couldn't store them exactly)
+    Unrepresentable {
+        fields: Vec<TagField>,
+        properties: Vec<String>,
+    },
+}
+
+impl fmt::Display for CopyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CopyError::Read(err) => err.fmt(f),
+            CopyError::Write(err) => err.fmt(f),
+            CopyError::Pictures(err) => err.fmt(f),
+            CopyError::Unrepresentable { fields, properties } => write!(
+                f,
+                "destination format can't store the fields {:?} or the properties {:?}",
+                fields, properties
+            ),
+        }
+    }
+}
+
+impl Error for CopyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CopyError::Read(err) => Some(err),
+            CopyError::Write(err) => Some(err),
+            CopyError::Pictures(err) => Some(err),
+            CopyError::Unrepresentable { .. } => None,
+        }
+    }
+}
+
+impl TagLibFile {
+    /* Copy all of the tags of this file to dest: the standard fields, the podcast and
+     * classical fields, the rating, the pictures, and any other properties that both
+     * formats support. Anything already in dest is replaced. Fields that the
+     * destination can't hold exactly are reported, rather than silently truncated.
+     * dest isn't saved. */
+    pub fn copy_tags_to(self: &Self, dest: &mut TagLibFile) -> Result<(), CopyError> {
+        let (data, mut errors) = self.tag().to_tag_data();
+        if let Some(error) = errors.pop() {
+            return Err(CopyError::Read(error));
+        }
+
+        // copy the property map first, as it holds the extended properties, but not bpm
+        let properties = unsafe {
+            let unsupported = taglib_ext_properties_copy(self.file_handle, dest.file_handle);
+            TagLibTag::read_string_list(unsupported).unwrap_or_default()
+        };
+        dest.tag.modified = true;
+        // the fields that are kept in the property map have been copied with it, and any
+        // that dest couldn't store are already in properties, so leave them as they are
+        let mut data = data;
+        data.set_property_fields(&dest.tag().to_tag_data().0);
+        dest.tag_mut().apply_strict(&data).map_err(CopyError::Write)?;
+
+        // ID3v2 ratings are POPM frames, which aren't in the property map either. Any
+        // that can't be written is reported below
+        let rating = self.rating();
+        if dest.rating() != rating {
+            let _ = dest.set_rating(rating);
+        }
+
+        // nor are the podcast and classical fields, for every format and version of
+        // taglib, so they're copied as the frames or atoms themselves
+        let read = |field: TagField| move |error| CopyError::Read(FieldReadError { field, error });
+        let podcast = self.podcast_info().map_err(read(TagField::Podcast))?;
+        if dest.podcast_info().ok().as_ref() != Some(&podcast) {
+            let _ = dest.set_podcast_info(&podcast);
+        }
+        let classical = self.classical_info().map_err(read(TagField::Work))?;
+        if dest.classical_info().ok().as_ref() != Some(&classical) {
+            let _ = dest.set_classical_info(&classical);
+        }
+
+        // taglib keeps pictures apart from the property map, so they're copied on their own
+        match self.tag().pictures() {
+            Ok(pictures) => match dest.tag_mut().set_pictures(&pictures) {
+                // nothing is lost when the destination can't hold pictures, if there are none
+                Err(PictureError::Unsupported) if pictures.is_empty() => {}
+                copied => copied.map_err(CopyError::Pictures)?,
+            },
+            Err(PictureError::Unsupported) => {}
+            Err(err) => return Err(CopyError::Pictures(err)),
+        }
+
+        // then check what actually ended up in the destination
+        let (copied, _) = dest.tag().to_tag_data();
+        let mut fields = Vec::new();
+        if copied.title != data.title {
+            fields.push(TagField::Title);
+        }
+        if copied.artist != data.artist {
+            fields.push(TagField::Artist);
+        }
+        if copied.album != data.album {
+            fields.push(TagField::Album);
+        }
+        if copied.comment != data.comment {
+            fields.push(TagField::Comment);
+        }
+        if copied.genre != data.genre {
+            fields.push(TagField::Genre);
+        }
+        if copied.year != data.year {
+            fields.push(TagField::Year);
+        }
+        if copied.track != data.track {
+            fields.push(TagField::Track);
+        }
+        if copied.bpm != data.bpm {
+            fields.push(TagField::Bpm);
+        }
+        if dest.rating() != rating {
+            fields.push(TagField::Rating);
+        }
+        if dest.podcast_info().ok().as_ref() != Some(&podcast) {
+            fields.push(TagField::Podcast);
+        }
+        if dest.classical_info().ok().as_ref() != Some(&classical) {
+            fields.push(TagField::Work);
+        }
+
+        if fields.is_empty() && properties.is_empty() {
+            Ok(())
+        } else {
+            Err(CopyError::Unrepresentable { fields, properties })
+        }
+    }
+}
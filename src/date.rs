@@ -0,0 +1,112 @@
+// std library imports
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+// split the date part of "YYYY", "YYYY-MM" or "YYYY-MM-DD" into its numbers, or None if
+// it isn't in one of those forms
+pub(crate) fn parse_date(value: &str) -> Option<(u32, Option<u32>, Option<u32>)> {
+    let mut parts = value.trim().split('-');
+    let number = |part: Option<&str>, digits: usize| -> Option<u32> {
+        let part = part?;
+        if part.len() == digits && part.bytes().all(|b| b.is_ascii_digit()) {
+            part.parse().ok()
+        } else {
+            None
+        }
+    };
+    let year = number(parts.next(), 4)?;
+    let month = match parts.next() {
+        Some(month) => Some(number(Some(month), 2).filter(|m| (1..=12).contains(m))?),
+        None => None,
+    };
+    let day = match parts.next() {
+        Some(day) => Some(number(Some(day), 2).filter(|d| (1..=31).contains(d))?),
+        None => None,
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((year, month, day))
+}
+
+/* A date that may only be known to the year or month, as tags store them, e.g. 2009,
+ * 2009-09 or 2009-09-26 */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PartialDate {
+    year: u32,
+    month: Option<u32>,
+    day: Option<u32>,
+}
+
+impl PartialDate {
+    /* None if the year isn't four digits, the month isn't from 1 to 12, the day isn't
+     * from 1 to 31, or there's a day without a month */
+    pub fn new(year: u32, month: Option<u32>, day: Option<u32>) -> Option<PartialDate> {
+        let valid_month = match month {
+            Some(month) => (1..=12).contains(&month),
+            None => true,
+        };
+        let valid_day = match day {
+            Some(day) => (1..=31).contains(&day) && month.is_some(),
+            None => true,
+        };
+        if year <= 9999 && valid_month && valid_day {
+            Some(PartialDate { year, month, day })
+        } else {
+            None
+        }
+    }
+
+    pub fn year(self: &Self) -> u32 {
+        self.year
+    }
+
+    pub fn month(self: &Self) -> Option<u32> {
+        self.month
+    }
+
+    pub fn day(self: &Self) -> Option<u32> {
+        self.day
+    }
+}
+
+/* Displayed as precisely as it's known, e.g. "2009-09" */
+impl fmt::Display for PartialDate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}", self.year)?;
+        if let Some(month) = self.month {
+            write!(f, "-{:02}", month)?;
+            if let Some(day) = self.day {
+                write!(f, "-{:02}", day)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/* The error from parsing a PartialDate, holding the string that wasn't recognised */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDateError(pub String);
+
+impl fmt::Display for ParseDateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid date {:?}", self.0)
+    }
+}
+
+impl Error for ParseDateError {}
+
+/* Dates are parsed from "YYYY", "YYYY-MM" or "YYYY-MM-DD", or from a timestamp such as
+ * "2009-09-26T07:00:00Z" (as MP4 files often have), ignoring the time */
+impl FromStr for PartialDate {
+    type Err = ParseDateError;
+
+    fn from_str(s: &str) -> Result<PartialDate, ParseDateError> {
+        let trimmed = s.trim();
+        let date = trimmed.split(['T', ' ']).next().unwrap_or(trimmed);
+        parse_date(date)
+            .and_then(|(year, month, day)| PartialDate::new(year, month, day))
+            .ok_or_else(|| ParseDateError(s.to_owned()))
+    }
+}
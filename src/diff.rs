@@ -0,0 +1,116 @@
+use {TagData, TagField, TagLibTag};
+
+/* The value of a field, for reporting changes */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FieldValue {
+    Text(String),
+    Number(u32),
+}
+
+/* A field that would change, going from current to desired. None means unset */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: TagField,
+    pub current: Option<FieldValue>,
+    pub desired: Option<FieldValue>,
+}
+
+/* How strictly text fields are compared when diffing */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiffOptions {
+    // compare text case insensitively
+    pub ignore_case: bool,
+    // ignore leading and trailing whitespace, and treat runs of whitespace as one space
+    pub ignore_whitespace: bool,
+}
+
+impl DiffOptions {
+    fn normalise(self: &Self, text: &str) -> String {
+        let text = if self.ignore_whitespace {
+            text.split_whitespace().collect::<Vec<_>>().join(" ")
+        } else {
+            text.to_owned()
+        };
+        if self.ignore_case {
+            text.to_lowercase()
+        } else {
+            text
+        }
+    }
+}
+
+impl TagData {
+    /* The changes needed to go from this data to desired, following the same rules as
+     * TagLibTag::apply: a field that is None in desired is left alone, so it's never a
+     * change. Writing an empty string clears a field, so Some("") is the same as unset. */
+    pub fn diff(self: &Self, desired: &TagData, options: DiffOptions) -> Vec<FieldChange> {
+        let mut changes = Vec::new();
+        {
+            let mut text = |field: TagField, current: &Option<String>, desired: &Option<String>| {
+                let desired = match *desired {
+                    Some(ref desired) => desired,
+                    None => return,
+                };
+                let current = current.as_ref().filter(|s| !s.is_empty());
+                let unchanged = match current {
+                    Some(current) => options.normalise(current) == options.normalise(desired),
+                    None => desired.is_empty(),
+                };
+                if !unchanged {
+                    changes.push(FieldChange {
+                        field,
+                        current: current.map(|s| FieldValue::Text(s.clone())),
+                        desired: Some(desired.clone())
+                            .filter(|s| !s.is_empty())
+                            .map(FieldValue::Text),
+                    });
+                }
+            };
+            text(TagField::Title, &self.title, &desired.title);
+            text(TagField::Artist, &self.artist, &desired.artist);
+            text(TagField::Album, &self.album, &desired.album);
+            text(TagField::Comment, &self.comment, &desired.comment);
+            text(TagField::Genre, &self.genre, &desired.genre);
+            text(TagField::Composer, &self.composer, &desired.composer);
+            text(TagField::Conductor, &self.conductor, &desired.conductor);
+            text(TagField::Remixer, &self.remixer, &desired.remixer);
+            text(TagField::ArtistSort, &self.artist_sort, &desired.artist_sort);
+            text(TagField::AlbumArtistSort, &self.album_artist_sort, &desired.album_artist_sort);
+            text(TagField::AlbumSort, &self.album_sort, &desired.album_sort);
+            text(TagField::TitleSort, &self.title_sort, &desired.title_sort);
+            text(TagField::Label, &self.label, &desired.label);
+        }
+        {
+            let mut number = |field: TagField, current: Option<u32>, desired: Option<u32>| {
+                // 0 is the same as unset, as for the numeric setters
+                let desired = match desired {
+                    Some(desired) => Some(desired).filter(|&n| n != 0),
+                    None => return,
+                };
+                if current != desired {
+                    changes.push(FieldChange {
+                        field,
+                        current: current.map(FieldValue::Number),
+                        desired: desired.map(FieldValue::Number),
+                    });
+                }
+            };
+            number(TagField::Year, self.year, desired.year);
+            number(TagField::Track, self.track, desired.track);
+            number(TagField::Bpm, self.bpm, desired.bpm);
+        }
+        changes
+    }
+}
+
+impl TagLibTag {
+    /* The changes that apply(desired) would make, compared exactly */
+    pub fn diff(self: &Self, desired: &TagData) -> Vec<FieldChange> {
+        self.diff_with(desired, DiffOptions::default())
+    }
+
+    pub fn diff_with(self: &Self, desired: &TagData, options: DiffOptions) -> Vec<FieldChange> {
+        let (current, _) = self.to_tag_data();
+        current.diff(desired, options)
+    }
+}
@@ -0,0 +1,99 @@
+// std library imports
+use std::error::Error;
+use std::ffi::NulError;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use {CopyError, SaveError};
+
+/* Various kinds of errors that we can get from using a file. Each one carries the
+ * path of the file that it happened to */
+#[derive(Debug)]
+pub enum FileError {
+    // the file couldn't be accessed at all (it doesn't exist, we can't read it, etc)
+    Io(PathBuf, io::Error),
+    // taglib couldn't open the file, usually because it doesn't know the format
+    OpenFailure(PathBuf),
+    // taglib couldn't save the file, with the cause if it could be found
+    SaveFailure(SaveError),
+    // the path isn't unicode, on platforms where taglib needs it to be (never on unix or windows)
+    PathAsString(PathBuf),
+    NullPathString(PathBuf, NulError),
+    // taglib opened the file, but couldn't make sense of it
+    InvalidTagFile(PathBuf),
+    // the file was opened read only, so wasn't saved
+    ReadOnly(PathBuf),
+    // the path is a directory (or a symlink to one)
+    IsADirectory(PathBuf),
+    // the path is something else that isn't a file, like a fifo, socket or device
+    NotARegularFile(PathBuf),
+    // the unsaved changes couldn't be copied to the file being saved to
+    CopyFailure(PathBuf, CopyError),
+    // the file's format doesn't support what was asked of it
+    Unsupported(PathBuf),
+}
+
+impl FileError {
+    // the path of the file that the error happened to
+    pub fn path(self: &Self) -> &Path {
+        match *self {
+            FileError::Io(ref path, _) => path,
+            FileError::OpenFailure(ref path) => path,
+            FileError::SaveFailure(ref err) => &err.path,
+            FileError::PathAsString(ref path) => path,
+            FileError::NullPathString(ref path, _) => path,
+            FileError::InvalidTagFile(ref path) => path,
+            FileError::ReadOnly(ref path) => path,
+            FileError::IsADirectory(ref path) => path,
+            FileError::NotARegularFile(ref path) => path,
+            FileError::CopyFailure(ref path, _) => path,
+            FileError::Unsupported(ref path) => path,
+        }
+    }
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FileError::Io(ref path, ref err) => write!(f, "couldn't access {}: {}", path.display(), err),
+            FileError::OpenFailure(ref path) => {
+                write!(f, "couldn't open {}, the format may not be supported", path.display())
+            }
+            FileError::SaveFailure(ref err) => write!(f, "{}", err),
+            FileError::PathAsString(ref path) => write!(f, "{} isn't valid unicode", path.display()),
+            FileError::NullPathString(ref path, _) => {
+                write!(f, "{} contains a nul byte", path.display())
+            }
+            FileError::InvalidTagFile(ref path) => {
+                write!(f, "{} isn't a valid file of its format", path.display())
+            }
+            FileError::ReadOnly(ref path) => {
+                write!(f, "{} was opened read only, so can't be saved", path.display())
+            }
+            FileError::IsADirectory(ref path) => write!(f, "{} is a directory", path.display()),
+            FileError::NotARegularFile(ref path) => {
+                write!(f, "{} isn't a regular file", path.display())
+            }
+            FileError::CopyFailure(ref path, ref err) => {
+                write!(f, "couldn't copy the changes to {}: {}", path.display(), err)
+            }
+            FileError::Unsupported(ref path) => {
+                write!(f, "{} is in a format that doesn't support this", path.display())
+            }
+        }
+    }
+}
+
+impl Error for FileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            FileError::Io(_, ref err) => Some(err),
+            FileError::NullPathString(_, ref err) => Some(err),
+            FileError::SaveFailure(ref err) => Some(err),
+            FileError::CopyFailure(_, ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
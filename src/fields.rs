@@ -0,0 +1,1005 @@
+// std library imports
+use std::ffi::CString;
+use std::os::raw::c_int;
+use std::ptr;
+use std::str::Utf8Error;
+
+// taglib-sys imports
+use taglib_sys::*;
+
+use date::{parse_date, PartialDate};
+use {iso_639_2_code, FieldWriteError, FileType, OptionalStringReadError, SetError, TagField, TagLibFile, TagLibTag};
+
+// parse "n" or "n/m", as used for disc and track numbers, where 0 is the same as unset
+pub(crate) fn parse_number_pair(value: &str) -> Option<(u32, Option<u32>)> {
+    let (number, total) = match value.split_once('/') {
+        Some((number, total)) => (number, Some(total.trim().parse().ok()?)),
+        None => (value, None),
+    };
+    let number = number.trim().parse().ok().filter(|&n| n != 0)?;
+    Some((number, total.filter(|&n| n != 0)))
+}
+
+// format a number and total as "n/m", or just "n" without a total
+pub(crate) fn format_number_pair(number: u32, total: Option<u32>) -> String {
+    match total {
+        Some(total) => format!("{}/{}", number, total),
+        None => number.to_string(),
+    }
+}
+
+// parse a BPM like "128", "127.96" or "127,96", where 0 is the same as unset
+fn parse_bpm(value: &str) -> Option<f64> {
+    let bpm: f64 = value.trim().replace(',', ".").parse().ok()?;
+    Some(bpm).filter(|&bpm| bpm.is_finite() && bpm > 0.0)
+}
+
+// format a BPM to two decimal places, without trailing zeros, e.g. "127.96" or "128"
+fn format_bpm(bpm: f64) -> String {
+    let formatted = format!("{:.2}", bpm);
+    formatted.trim_end_matches('0').trim_end_matches('.').to_owned()
+}
+
+// normalise an ISRC to its 12 character form, e.g. "us-s1z-99-00001" to "USS1Z9900001",
+// or None if it isn't one: a country code, a registrant, a year and a designation code
+fn normalise_isrc(value: &str) -> Option<String> {
+    let isrc: String = value.trim().chars().filter(|&c| c != '-').collect::<String>().to_ascii_uppercase();
+    let bytes = isrc.as_bytes();
+    let valid = bytes.len() == 12
+        && bytes[..2].iter().all(|b| b.is_ascii_uppercase())
+        && bytes[2..5].iter().all(|b| b.is_ascii_alphanumeric())
+        && bytes[5..].iter().all(|b| b.is_ascii_digit());
+    if valid {
+        Some(isrc)
+    } else {
+        None
+    }
+}
+
+// what joins artists together in tags without multiple values, in lower case
+const ARTIST_SEPARATORS: [&str; 8] = [" (feat. ", " (ft. ", " (featuring ", " feat. ", " ft. ", " featuring ", "/", ";"];
+
+// split e.g. "A feat. B", "A (feat. B)" or "A/B" into ["A", "B"]
+fn split_joined_artists(joined: &str) -> Vec<String> {
+    // ASCII lower case keeps every byte where it was, so indices into it work for joined
+    let lower = joined.to_ascii_lowercase();
+    let mut artists = Vec::new();
+    let mut start = 0;
+    let mut bracketed = false;
+    loop {
+        let next = ARTIST_SEPARATORS
+            .iter()
+            .filter_map(|separator| lower[start..].find(separator).map(|i| (start + i, *separator)))
+            .min_by_key(|&(i, _)| i);
+        let mut artist = joined[start..next.map_or(joined.len(), |(i, _)| i)].trim();
+        if bracketed {
+            if let Some(inner) = artist.strip_suffix(')') {
+                artist = inner.trim_end();
+                bracketed = false;
+            }
+        }
+        if !artist.is_empty() {
+            artists.push(artist.to_owned());
+        }
+        match next {
+            Some((i, separator)) => {
+                start = i + separator.len();
+                bracketed |= separator.contains('(');
+            }
+            None => return artists,
+        }
+    }
+}
+
+/* Fields that taglib's C API has no getters or setters for, which are kept in the
+ * file's property map instead. taglib maps each key to the right frame, atom or
+ * comment for the format, e.g. ALBUMARTIST is TPE2 in ID3v2, aART in MP4 and
+ * ALBUMARTIST in Vorbis comments. */
+impl TagLibTag {
+    // a tag without a file has no property map, so none of these fields are present
+    pub(crate) fn field_property(self: &Self, key: &str) -> OptionalStringReadError {
+        if self.file.is_null() {
+            return Ok(None);
+        }
+        let cs_key = CString::new(key).expect("property keys don't contain nul bytes");
+        unsafe {
+            let value_ptr = taglib_ext_property_get(self.file, cs_key.as_ptr());
+            if value_ptr.is_null() {
+                Ok(None)
+            } else {
+                Self::read_and_parse(value_ptr).map(Some)
+            }
+        }
+    }
+
+    // write (or with None, remove) a field, with the same nul handling and encoding
+    // policy as the standard fields. As with those, an empty string removes the field.
+    pub(crate) fn set_field_property(self: &mut Self, field: TagField, key: &str, value: Option<&str>) -> FieldWriteError {
+        let cs_key = CString::new(key).expect("property keys don't contain nul bytes");
+        let cs_value = match value {
+            Some(value) if !value.is_empty() => Some(self.to_c_string(field, value)?),
+            _ => None,
+        };
+        if self.file.is_null() {
+            // there's nothing to remove from a tag without a file
+            return match cs_value {
+                Some(_) => Err(SetError::unsupported(field)),
+                None => Ok(()),
+            };
+        }
+        // as with the standard fields, setting what's already there isn't a change
+        let wanted: Vec<&str> = value.filter(|value| !value.is_empty()).into_iter().collect();
+        if self.field_property_values(key).is_ok_and(|current| current == wanted) {
+            return Ok(());
+        }
+        let value_ptr = cs_value.as_ref().map_or(ptr::null(), |v| v.as_ptr());
+        unsafe {
+            if taglib_ext_property_set(self.file, cs_key.as_ptr(), value_ptr, 0) == 0 {
+                return Err(SetError::unsupported(field));
+            }
+        }
+        self.modified = true;
+        Ok(())
+    }
+
+    // every value of a field, in order
+    pub(crate) fn field_property_values(self: &Self, key: &str) -> Result<Vec<String>, Utf8Error> {
+        if self.file.is_null() {
+            return Ok(Vec::new());
+        }
+        let cs_key = CString::new(key).expect("property keys don't contain nul bytes");
+        unsafe { Self::read_string_list(taglib_ext_property_values(self.file, cs_key.as_ptr())) }
+    }
+
+    // replace every value of a field, checking them all before any are written. Empty
+    // strings are skipped, and no values at all removes the field
+    pub(crate) fn set_field_property_values(self: &mut Self, field: TagField, key: &str, values: &[&str]) -> FieldWriteError {
+        let wanted: Vec<&str> = values.iter().cloned().filter(|value| !value.is_empty()).collect();
+        let values = wanted
+            .iter()
+            .map(|value| self.to_c_string(field, value))
+            .collect::<Result<Vec<CString>, SetError>>()?;
+        if values.is_empty() {
+            return self.set_field_property(field, key, None);
+        }
+        if self.file.is_null() {
+            return Err(SetError::unsupported(field));
+        }
+        // as with set_field_property, setting what's already there isn't a change
+        if self.field_property_values(key).is_ok_and(|current| current == wanted) {
+            return Ok(());
+        }
+        let cs_key = CString::new(key).expect("property keys don't contain nul bytes");
+        for (i, value) in values.iter().enumerate() {
+            unsafe {
+                if taglib_ext_property_set(self.file, cs_key.as_ptr(), value.as_ptr(), (i > 0) as c_int) == 0 {
+                    return Err(SetError::unsupported(field));
+                }
+            }
+            self.modified = true;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn is_mp4(self: &Self) -> bool {
+        matches!(self.file_type(), Some(FileType::Mp4))
+    }
+
+    // the first value of an MP4 atom, by name, e.g. "catg"
+    pub(crate) fn mp4_item(self: &Self, name: &str) -> OptionalStringReadError {
+        let cs_name = CString::new(name).expect("atom names don't contain nul bytes");
+        unsafe {
+            let value_ptr = taglib_ext_mp4_item(self.file, cs_name.as_ptr());
+            if value_ptr.is_null() {
+                Ok(None)
+            } else {
+                Self::read_and_parse(value_ptr).map(Some)
+            }
+        }
+    }
+
+    /* For fields that not every version of taglib maps to a property key: the ID3v2
+     * text frame or the MP4 atom itself, and the property for every other format */
+    pub(crate) fn mapped_field(self: &Self, key: &str, id3v2_frame: &str, mp4_atom: &str) -> OptionalStringReadError {
+        if self.is_mp4() {
+            return self.mp4_item(mp4_atom);
+        }
+        if !self.has_id3v2_properties() {
+            return self.field_property(key);
+        }
+        let cs_id = CString::new(id3v2_frame).expect("frame ids don't contain nul bytes");
+        let value_ptr = unsafe { taglib_ext_id3v2_text_frame(self.file, cs_id.as_ptr()) };
+        if value_ptr.is_null() {
+            Ok(None)
+        } else {
+            Self::read_and_parse(value_ptr).map(Some)
+        }
+    }
+
+    // as set_field_property, for the fields that mapped_field reads
+    pub(crate) fn set_mapped_field(
+        self: &mut Self,
+        field: TagField,
+        key: &str,
+        id3v2_frame: &str,
+        mp4_atom: &str,
+        value: Option<&str>,
+    ) -> FieldWriteError {
+        let id3v2 = self.has_id3v2_properties();
+        if !id3v2 && !self.is_mp4() {
+            return self.set_field_property(field, key, value);
+        }
+        let cs_value = match value {
+            Some(value) if !value.is_empty() => Some(self.to_c_string(field, value)?),
+            _ => None,
+        };
+        // as with set_field_property, setting what's already there isn't a change
+        let wanted = value.filter(|value| !value.is_empty());
+        if self.mapped_field(key, id3v2_frame, mp4_atom).is_ok_and(|current| current.as_deref() == wanted) {
+            return Ok(());
+        }
+        let value_ptr = cs_value.as_ref().map_or(ptr::null(), |v| v.as_ptr());
+        let stored = if id3v2 {
+            let cs_id = CString::new(id3v2_frame).expect("frame ids don't contain nul bytes");
+            unsafe { taglib_ext_id3v2_set_text_frame(self.file, cs_id.as_ptr(), value_ptr) }
+        } else {
+            let cs_name = CString::new(mp4_atom).expect("atom names don't contain nul bytes");
+            unsafe { taglib_ext_mp4_set_item(self.file, cs_name.as_ptr(), value_ptr) }
+        };
+        if stored == 0 {
+            return Err(SetError::unsupported(field));
+        }
+        self.modified = true;
+        Ok(())
+    }
+
+    // Vorbis comments keep totals in a field of their own, where other formats use "n/m"
+    fn has_vorbis_comments(self: &Self) -> bool {
+        matches!(
+            self.file_type(),
+            Some(FileType::Flac) | Some(FileType::OggVorbis) | Some(FileType::OggFlac) | Some(FileType::Speex) | Some(FileType::Opus)
+        )
+    }
+
+    // formats whose properties come from an APE tag
+    fn has_ape_tag(self: &Self) -> bool {
+        matches!(self.file_type(), Some(FileType::Mpc) | Some(FileType::WavPack))
+    }
+
+    // formats whose properties come from an ID3v2 tag
+    pub(crate) fn has_id3v2_properties(self: &Self) -> bool {
+        matches!(
+            self.file_type(),
+            Some(FileType::Mpeg) | Some(FileType::Wav) | Some(FileType::Aiff) | Some(FileType::TrueAudio)
+        )
+    }
+
+    // read a number and total, from "n/m" in key, or n in key and m in total_key
+    pub(crate) fn number_pair_property(self: &Self, key: &str, total_key: &str) -> Option<(u32, Option<u32>)> {
+        let (number, total) = parse_number_pair(&self.field_property(key).ok()??)?;
+        let total = total.or_else(|| {
+            let total = self.field_property(total_key).ok()??;
+            total.trim().parse().ok().filter(|&n| n != 0)
+        });
+        Some((number, total))
+    }
+
+    // write a number and total in whichever way the format expects
+    fn set_number_pair_property(self: &mut Self, field: TagField, key: &str, total_key: &str, number: Option<u32>, total: Option<u32>) -> FieldWriteError {
+        let number = number.filter(|&n| n != 0);
+        let total = number.and(total).filter(|&n| n != 0);
+        if self.has_vorbis_comments() {
+            self.set_field_property(field, key, number.map(|n| n.to_string()).as_deref())?;
+            self.set_field_property(field, total_key, total.map(|n| n.to_string()).as_deref())
+        } else {
+            let value = number.map(|n| format_number_pair(n, total));
+            self.set_field_property(field, key, value.as_deref())?;
+            // a total in a field of its own would be a TXXX frame or similar, which other
+            // tools may have left behind, and which would disagree with the new value
+            self.set_field_property(field, total_key, None)
+        }
+    }
+
+    pub fn album_artist(self: &Self) -> OptionalStringReadError {
+        self.field_property("ALBUMARTIST")
+    }
+
+    // None removes the field
+    pub fn set_album_artist<'a, V: Into<Option<&'a str>>>(self: &mut Self, album_artist: V) -> FieldWriteError {
+        self.set_field_property(TagField::AlbumArtist, "ALBUMARTIST", album_artist.into())
+    }
+
+    /* The first composer. Use property_values("COMPOSER") to read all of them, and
+     * append_property to add more than one */
+    pub fn composer(self: &Self) -> OptionalStringReadError {
+        self.field_property("COMPOSER")
+    }
+
+    // None removes the field, along with any other composers
+    pub fn set_composer<'a, V: Into<Option<&'a str>>>(self: &mut Self, composer: V) -> FieldWriteError {
+        self.set_field_property(TagField::Composer, "COMPOSER", composer.into())
+    }
+
+    // the conductor (TPE3 in ID3v2)
+    pub fn conductor(self: &Self) -> OptionalStringReadError {
+        self.field_property("CONDUCTOR")
+    }
+
+    pub fn set_conductor<'a, V: Into<Option<&'a str>>>(self: &mut Self, conductor: V) -> FieldWriteError {
+        self.set_field_property(TagField::Conductor, "CONDUCTOR", conductor.into())
+    }
+
+    /* The remixer (TPE4 in ID3v2). APE tags call it MixArtist, which older versions of
+     * taglib don't map to REMIXER, so that's read too on formats with APE tags */
+    pub fn remixer(self: &Self) -> OptionalStringReadError {
+        match self.field_property("REMIXER")? {
+            None if self.has_ape_tag() => self.field_property("MIXARTIST"),
+            remixer => Ok(remixer),
+        }
+    }
+
+    // None removes the field, including any MixArtist
+    pub fn set_remixer<'a, V: Into<Option<&'a str>>>(self: &mut Self, remixer: V) -> FieldWriteError {
+        let remixer = remixer.into();
+        self.set_field_property(TagField::Remixer, "REMIXER", remixer)?;
+        match remixer {
+            Some(remixer) if !remixer.is_empty() => Ok(()),
+            _ if self.has_ape_tag() => self.set_field_property(TagField::Remixer, "MIXARTIST", None),
+            _ => Ok(()),
+        }
+    }
+
+    /* The sort order fields hold the name that a field is sorted by, e.g. "Beatles, The"
+     * (TSOP, TSO2, TSOA and TSOT in ID3v2, and soar, soaa, soal and sonm in MP4) */
+    pub fn artist_sort(self: &Self) -> OptionalStringReadError {
+        self.field_property("ARTISTSORT")
+    }
+
+    pub fn set_artist_sort<'a, V: Into<Option<&'a str>>>(self: &mut Self, artist_sort: V) -> FieldWriteError {
+        self.set_field_property(TagField::ArtistSort, "ARTISTSORT", artist_sort.into())
+    }
+
+    pub fn album_artist_sort(self: &Self) -> OptionalStringReadError {
+        self.field_property("ALBUMARTISTSORT")
+    }
+
+    pub fn set_album_artist_sort<'a, V: Into<Option<&'a str>>>(self: &mut Self, album_artist_sort: V) -> FieldWriteError {
+        self.set_field_property(TagField::AlbumArtistSort, "ALBUMARTISTSORT", album_artist_sort.into())
+    }
+
+    pub fn album_sort(self: &Self) -> OptionalStringReadError {
+        self.field_property("ALBUMSORT")
+    }
+
+    pub fn set_album_sort<'a, V: Into<Option<&'a str>>>(self: &mut Self, album_sort: V) -> FieldWriteError {
+        self.set_field_property(TagField::AlbumSort, "ALBUMSORT", album_sort.into())
+    }
+
+    pub fn title_sort(self: &Self) -> OptionalStringReadError {
+        self.field_property("TITLESORT")
+    }
+
+    pub fn set_title_sort<'a, V: Into<Option<&'a str>>>(self: &mut Self, title_sort: V) -> FieldWriteError {
+        self.set_field_property(TagField::TitleSort, "TITLESORT", title_sort.into())
+    }
+
+    /* The grouping, which often holds the name of a work, where the title is the
+     * movement (TIT1 or GRP1 in ID3v2, ©grp in MP4). Older versions of taglib call
+     * this CONTENTGROUP, so both keys are read. */
+    pub fn grouping(self: &Self) -> OptionalStringReadError {
+        match self.field_property("GROUPING")? {
+            Some(grouping) => Ok(Some(grouping)),
+            None => self.field_property("CONTENTGROUP"),
+        }
+    }
+
+    /* Written under whichever key the linked taglib maps to a frame of its own, rather
+     * than to a TXXX frame. None removes the field. */
+    pub fn set_grouping<'a, V: Into<Option<&'a str>>>(self: &mut Self, grouping: V) -> FieldWriteError {
+        // taglib 1.x has no frame for GROUPING, and maps TIT1 to CONTENTGROUP instead
+        let cs_grouping = CString::new("GROUPING").expect("property keys don't contain nul bytes");
+        let content_group = self.has_id3v2_properties() && unsafe { taglib_ext_id3v2_has_frame_for_key(cs_grouping.as_ptr()) == 0 };
+        let (key, other_key) = if content_group { ("CONTENTGROUP", "GROUPING") } else { ("GROUPING", "CONTENTGROUP") };
+        self.set_field_property(TagField::Grouping, key, grouping.into())?;
+        self.set_field_property(TagField::Grouping, other_key, None)
+    }
+
+    /* The key that the track starts in (TKEY in ID3v2), as the file has it. Parse it as
+     * a MusicalKey to convert between standard and Camelot notation. */
+    pub fn initial_key(self: &Self) -> OptionalStringReadError {
+        self.field_property("INITIALKEY")
+    }
+
+    // None removes the field
+    pub fn set_initial_key<'a, V: Into<Option<&'a str>>>(self: &mut Self, initial_key: V) -> FieldWriteError {
+        self.set_field_property(TagField::InitialKey, "INITIALKEY", initial_key.into())
+    }
+
+    /* The record label (TPUB in ID3v2). Vorbis comments have it as LABEL, or, from older
+     * tools, ORGANIZATION, so that is read too, when there's no LABEL. */
+    pub fn label(self: &Self) -> OptionalStringReadError {
+        Ok(self.label_with_key()?.map(|(_, label)| label))
+    }
+
+    // the label, along with the key that it was read from, LABEL or ORGANIZATION
+    pub fn label_with_key(self: &Self) -> Result<Option<(&'static str, String)>, Utf8Error> {
+        for &key in ["LABEL", "ORGANIZATION"].iter() {
+            if let Some(label) = self.field_property(key)? {
+                return Ok(Some((key, label)));
+            }
+        }
+        Ok(None)
+    }
+
+    /* The label is always written as LABEL, leaving any ORGANIZATION as it was, as LABEL
+     * takes precedence. None removes both. */
+    pub fn set_label<'a, V: Into<Option<&'a str>>>(self: &mut Self, label: V) -> FieldWriteError {
+        let label = label.into();
+        self.set_field_property(TagField::Label, "LABEL", label)?;
+        match label {
+            Some(label) if !label.is_empty() => Ok(()),
+            _ => self.set_field_property(TagField::Label, "ORGANIZATION", None),
+        }
+    }
+
+    /* The copyright statement (TCOP in ID3v2, cprt in MP4). As with every field, the
+     * string encoding policy applies: © is in Latin-1, so it's always accepted, but
+     * under the Latin1 policy a character outside it, like ℗, is a NotLatin1 error,
+     * rather than being replaced. */
+    pub fn copyright(self: &Self) -> OptionalStringReadError {
+        self.field_property("COPYRIGHT")
+    }
+
+    // None removes the field
+    pub fn set_copyright<'a, V: Into<Option<&'a str>>>(self: &mut Self, copyright: V) -> FieldWriteError {
+        self.set_field_property(TagField::Copyright, "COPYRIGHT", copyright.into())
+    }
+
+    /* The person or organisation that encoded the file (TENC in ID3v2) */
+    pub fn encoded_by(self: &Self) -> OptionalStringReadError {
+        self.field_property("ENCODEDBY")
+    }
+
+    // None removes the field
+    pub fn set_encoded_by<'a, V: Into<Option<&'a str>>>(self: &mut Self, encoded_by: V) -> FieldWriteError {
+        self.set_field_property(TagField::EncodedBy, "ENCODEDBY", encoded_by.into())
+    }
+
+    /* The software and settings that the file was encoded with, e.g. "Lavf58.29.100"
+     * (TSSE in ID3v2). taglib calls this ENCODING, but some tools write Vorbis comments
+     * as ENCODERSETTINGS, which is read when there's no ENCODING. */
+    pub fn encoder_settings(self: &Self) -> OptionalStringReadError {
+        match self.field_property("ENCODING")? {
+            Some(settings) => Ok(Some(settings)),
+            None => self.field_property("ENCODERSETTINGS"),
+        }
+    }
+
+    // written as ENCODING, replacing any ENCODERSETTINGS. None removes both
+    pub fn set_encoder_settings<'a, V: Into<Option<&'a str>>>(self: &mut Self, encoder_settings: V) -> FieldWriteError {
+        self.set_field_property(TagField::EncoderSettings, "ENCODING", encoder_settings.into())?;
+        self.set_field_property(TagField::EncoderSettings, "ENCODERSETTINGS", None)
+    }
+
+    /* The language of the content (TLAN in ID3v2), as the file has it, which may be a
+     * code, or a name like "English" */
+    pub fn language(self: &Self) -> OptionalStringReadError {
+        self.field_property("LANGUAGE")
+    }
+
+    // the language as an ISO 639-2 code, where it's one that iso_639_2_code recognises
+    pub fn language_code(self: &Self) -> Option<&'static str> {
+        self.language().ok()?.and_then(|language| iso_639_2_code(&language))
+    }
+
+    // None removes the field
+    pub fn set_language<'a, V: Into<Option<&'a str>>>(self: &mut Self, language: V) -> FieldWriteError {
+        self.set_field_property(TagField::Language, "LANGUAGE", language.into())
+    }
+
+    /* Set the language as an ISO 639-2 code, normalised with iso_639_2_code, so "en"
+     * and "English" are written as "eng". A language that isn't recognised is Malformed. */
+    pub fn set_language_code(self: &mut Self, language: &str) -> FieldWriteError {
+        match iso_639_2_code(language) {
+            Some(code) => self.set_language(code),
+            None => Err(SetError::malformed(TagField::Language, language)),
+        }
+    }
+
+    /* The date of the original release, for remasters and reissues, as "YYYY",
+     * "YYYY-MM" or "YYYY-MM-DD" (TDOR, or TORY in ID3v2.3). Older tools write only the
+     * year, as ORIGINALYEAR, which is read when there's no ORIGINALDATE. */
+    pub fn original_date(self: &Self) -> OptionalStringReadError {
+        match self.field_property("ORIGINALDATE")? {
+            Some(date) => Ok(Some(date)),
+            None => self.field_property("ORIGINALYEAR"),
+        }
+    }
+
+    // the year of the original release, or None if there's no date, or it can't be parsed
+    pub fn original_year(self: &Self) -> Option<u32> {
+        let date = self.original_date().ok()??;
+        parse_date(&date).map(|(year, _, _)| year)
+    }
+
+    /* Set the original release date, which must be "YYYY", "YYYY-MM" or "YYYY-MM-DD",
+     * or it's Malformed. It's written as ORIGINALDATE, replacing any ORIGINALYEAR. None
+     * removes both. */
+    pub fn set_original_date<'a, V: Into<Option<&'a str>>>(self: &mut Self, date: V) -> FieldWriteError {
+        let date = date.into();
+        if let Some(date) = date {
+            if !date.is_empty() && parse_date(date).is_none() {
+                return Err(SetError::malformed(TagField::OriginalDate, date));
+            }
+        }
+        self.set_field_property(TagField::OriginalDate, "ORIGINALDATE", date.map(str::trim))?;
+        self.set_field_property(TagField::OriginalDate, "ORIGINALYEAR", None)
+    }
+
+    // set the original release date to just a year. None (or 0) removes it
+    pub fn set_original_year<V: Into<Option<u32>>>(self: &mut Self, year: V) -> FieldWriteError {
+        let year = year.into().filter(|&y| y != 0).map(|y| format!("{:04}", y));
+        self.set_original_date(year.as_deref())
+    }
+
+    // the DATE property, if it's there and parses
+    pub(crate) fn full_date(self: &Self) -> Option<PartialDate> {
+        self.field_property("DATE").ok()??.parse().ok()
+    }
+
+    /* The release date, as precisely as the tag has it (TDRC in ID3v2.4, which taglib
+     * converts from TYER and TDAT in ID3v2.3, ©day in MP4, and DATE in Vorbis comments).
+     * Timestamps are read as their date. Where there's no date that parses, this is just
+     * the year, if there is one. */
+    pub fn date(self: &Self) -> Option<PartialDate> {
+        self.full_date().or_else(|| self.year().and_then(|year| PartialDate::new(year, None, None)))
+    }
+
+    // written as precisely as date is known, e.g. "2009-09". None removes the date
+    pub fn set_date<V: Into<Option<PartialDate>>>(self: &mut Self, date: V) -> FieldWriteError {
+        let date = date.into().map(|date| date.to_string());
+        self.set_field_property(TagField::Year, "DATE", date.as_deref())
+    }
+
+    /* The mood, e.g. "mellow". In ID3v2.4 this is TMOO, which doesn't exist in ID3v2.3,
+     * where tools use a TXXX frame described as MOOD instead. taglib reads both as MOOD,
+     * so both are read (TMOO first), and it's written as TMOO. */
+    pub fn mood(self: &Self) -> OptionalStringReadError {
+        self.field_property("MOOD")
+    }
+
+    // every mood, in order
+    pub fn moods(self: &Self) -> Result<Vec<String>, Utf8Error> {
+        self.field_property_values("MOOD")
+    }
+
+    // None removes every mood
+    pub fn set_mood<'a, V: Into<Option<&'a str>>>(self: &mut Self, mood: V) -> FieldWriteError {
+        self.set_field_property(TagField::Mood, "MOOD", mood.into())
+    }
+
+    // an empty slice removes every mood
+    pub fn set_moods(self: &mut Self, moods: &[&str]) -> FieldWriteError {
+        self.set_field_property_values(TagField::Mood, "MOOD", moods)
+    }
+
+    /* Every genre, in order, e.g. ["Jazz", "Bossa Nova"], where ID3v2.4 and Vorbis
+     * comments store several. Old tags often join them into one string instead, like
+     * "Jazz;Bossa Nova" or "Jazz/Bossa Nova", which genres_with(true) splits apart */
+    pub fn genres(self: &Self) -> Result<Vec<String>, Utf8Error> {
+        self.genres_with(false)
+    }
+
+    pub fn genres_with(self: &Self, split_joined: bool) -> Result<Vec<String>, Utf8Error> {
+        let genres = self.field_property_values("GENRE")?;
+        if !split_joined {
+            return Ok(genres);
+        }
+        Ok(genres
+            .iter()
+            .flat_map(|genre| genre.split([';', '/']))
+            .map(str::trim)
+            .filter(|genre| !genre.is_empty())
+            .map(str::to_owned)
+            .collect())
+    }
+
+    // an empty slice removes every genre
+    pub fn set_genres(self: &mut Self, genres: &[&str]) -> FieldWriteError {
+        self.set_field_property_values(TagField::Genre, "GENRE", genres)
+    }
+
+    /* Every artist, in order, with the primary artist first. Tags without multiple values,
+     * like ID3v2.3, often join them instead: artists_with(true) splits on "/", ";", "feat.",
+     * "ft." and "featuring", but never on "," or "&", as in "Earth, Wind & Fire" */
+    pub fn artists(self: &Self) -> Result<Vec<String>, Utf8Error> {
+        self.artists_with(false)
+    }
+
+    pub fn artists_with(self: &Self, split_joined: bool) -> Result<Vec<String>, Utf8Error> {
+        let artists = self.field_property_values("ARTIST")?;
+        if !split_joined {
+            return Ok(artists);
+        }
+        Ok(artists.iter().flat_map(|artist| split_joined_artists(artist)).collect())
+    }
+
+    // an empty slice removes every artist
+    pub fn set_artists(self: &mut Self, artists: &[&str]) -> FieldWriteError {
+        self.set_field_property_values(TagField::Artist, "ARTIST", artists)
+    }
+
+    /* The International Standard Recording Code (TSRC in ID3v2) */
+    pub fn isrc(self: &Self) -> OptionalStringReadError {
+        self.field_property("ISRC")
+    }
+
+    /* Set the ISRC, which is checked and written in its 12 character, upper case form,
+     * without hyphens. Anything else is Malformed. None (or an empty or blank value)
+     * removes the field. */
+    pub fn set_isrc<'a, V: Into<Option<&'a str>>>(self: &mut Self, isrc: V) -> FieldWriteError {
+        match isrc.into() {
+            Some(isrc) if isrc.trim().is_empty() => self.set_isrc_unchecked(None),
+            Some(isrc) => match normalise_isrc(isrc) {
+                Some(normalised) => self.set_isrc_unchecked(normalised.as_str()),
+                None => Err(SetError::malformed(TagField::Isrc, isrc)),
+            },
+            None => self.set_isrc_unchecked(None),
+        }
+    }
+
+    // as set_isrc, but writing the value as it is, for legacy codes
+    pub fn set_isrc_unchecked<'a, V: Into<Option<&'a str>>>(self: &mut Self, isrc: V) -> FieldWriteError {
+        self.set_field_property(TagField::Isrc, "ISRC", isrc.into())
+    }
+
+    /* The disc number, and the number of discs if it's known. Values that can't be
+     * parsed, like "A" or "3/", are None. The total is read from "1/2" style values, or
+     * from a DISCTOTAL field of its own, as Vorbis comments usually have it. */
+    pub fn disc(self: &Self) -> Option<(u32, Option<u32>)> {
+        self.number_pair_property("DISCNUMBER", "DISCTOTAL")
+    }
+
+    /* Set the disc number, and optionally the number of discs. The total is written as
+     * DISCTOTAL in Vorbis comments, and as "1/2" everywhere else. None (or 0) for the
+     * number removes both. */
+    pub fn set_disc<N: Into<Option<u32>>, T: Into<Option<u32>>>(self: &mut Self, number: N, total: T) -> FieldWriteError {
+        self.set_number_pair_property(TagField::Disc, "DISCNUMBER", "DISCTOTAL", number.into(), total.into())
+    }
+
+    /* The number of tracks, from "5/12" style track numbers, or from a TRACKTOTAL field
+     * of its own, as Vorbis comments usually have it */
+    pub fn track_total(self: &Self) -> Option<u32> {
+        self.number_pair_property("TRACKNUMBER", "TRACKTOTAL").and_then(|(_, total)| total)
+    }
+
+    /* Set the track number along with the number of tracks, where None for the total
+     * removes it. The total is written as TRACKTOTAL in Vorbis comments, and as "5/12"
+     * everywhere else. None (or 0) for the number removes both. */
+    pub fn set_track_with_total<N: Into<Option<u32>>>(self: &mut Self, number: N, total: Option<u32>) -> FieldWriteError {
+        self.set_number_pair_property(TagField::Track, "TRACKNUMBER", "TRACKTOTAL", number.into(), total)
+    }
+
+    /* Whether the file is marked as part of a compilation (TCMP in ID3v2, cpil in MP4,
+     * COMPILATION in Vorbis comments). "1", "0", "true" and "false" are understood, in
+     * any case, and anything else is None, as is a file without the flag. */
+    pub fn is_compilation(self: &Self) -> Option<bool> {
+        let value = self.field_property("COMPILATION").ok()??;
+        match value.trim().to_ascii_lowercase().as_str() {
+            "1" | "true" => Some(true),
+            "0" | "false" => Some(false),
+            _ => None,
+        }
+    }
+
+    /* Mark the file as part of a compilation, which is written as "1" in every format.
+     * Players treat a missing flag as false, so false removes the flag, rather than
+     * writing "0". */
+    pub fn set_compilation(self: &mut Self, compilation: bool) -> FieldWriteError {
+        let value = if compilation { Some("1") } else { None };
+        self.set_field_property(TagField::Compilation, "COMPILATION", value)
+    }
+
+    /* The BPM with its fraction, e.g. 127.96, where bpm() would round it down to 127.
+     * A comma as the decimal separator is accepted too. MP4 stores the BPM as an
+     * integer, so there's never a fraction there. */
+    pub fn bpm_f64(self: &Self) -> Option<f64> {
+        parse_bpm(&self.field_property("BPM").ok()??)
+    }
+
+    /* Set the BPM to two decimal places, always with a "." as the decimal separator.
+     * None (or anything under 0.005, which would be written as 0) removes it, and a
+     * negative, infinite or NaN BPM is Malformed. */
+    pub fn set_bpm_f64<V: Into<Option<f64>>>(self: &mut Self, bpm: V) -> FieldWriteError {
+        let bpm = bpm.into();
+        if let Some(bpm) = bpm {
+            if !bpm.is_finite() || bpm < 0.0 {
+                return Err(SetError::malformed(TagField::Bpm, &bpm.to_string()));
+            }
+        }
+        let bpm = bpm.filter(|&bpm| bpm >= 0.005);
+        self.set_field_property(TagField::Bpm, "BPM", bpm.map(format_bpm).as_deref())
+    }
+
+    /* The unsynchronised lyrics (USLT in ID3v2, ©lyr in MP4, and LYRICS, or failing that
+     * UNSYNCEDLYRICS, in Vorbis comments). Lines are separated as the file has them. */
+    pub fn lyrics(self: &Self) -> OptionalStringReadError {
+        match self.field_property("LYRICS")? {
+            Some(lyrics) => Ok(Some(lyrics)),
+            None => self.field_property("UNSYNCEDLYRICS"),
+        }
+    }
+
+    /* The ISO 639-2 language code of the lyrics, e.g. "eng", where the format records
+     * one, which only ID3v2 does */
+    pub fn lyrics_language(self: &Self) -> Option<String> {
+        if self.file.is_null() {
+            return None;
+        }
+        let language = unsafe { taglib_ext_id3v2_lyrics_language(self.file) };
+        if language.is_null() {
+            return None;
+        }
+        Self::read_and_parse(language).ok().filter(|l| !l.trim_matches('\0').is_empty())
+    }
+
+    // None removes the lyrics, including any under UNSYNCEDLYRICS
+    pub fn set_lyrics<'a, V: Into<Option<&'a str>>>(self: &mut Self, lyrics: V) -> FieldWriteError {
+        self.set_field_property(TagField::Lyrics, "LYRICS", lyrics.into())?;
+        self.set_field_property(TagField::Lyrics, "UNSYNCEDLYRICS", None)
+    }
+}
+
+impl TagLibFile {
+    pub fn album_artist(self: &Self) -> OptionalStringReadError {
+        self.tag.album_artist()
+    }
+
+    pub fn set_album_artist<'a, V: Into<Option<&'a str>>>(self: &mut Self, album_artist: V) -> FieldWriteError {
+        self.tag.set_album_artist(album_artist)
+    }
+
+    pub fn composer(self: &Self) -> OptionalStringReadError {
+        self.tag.composer()
+    }
+
+    pub fn set_composer<'a, V: Into<Option<&'a str>>>(self: &mut Self, composer: V) -> FieldWriteError {
+        self.tag.set_composer(composer)
+    }
+
+    pub fn conductor(self: &Self) -> OptionalStringReadError {
+        self.tag.conductor()
+    }
+
+    pub fn set_conductor<'a, V: Into<Option<&'a str>>>(self: &mut Self, conductor: V) -> FieldWriteError {
+        self.tag.set_conductor(conductor)
+    }
+
+    pub fn remixer(self: &Self) -> OptionalStringReadError {
+        self.tag.remixer()
+    }
+
+    pub fn set_remixer<'a, V: Into<Option<&'a str>>>(self: &mut Self, remixer: V) -> FieldWriteError {
+        self.tag.set_remixer(remixer)
+    }
+
+    pub fn artist_sort(self: &Self) -> OptionalStringReadError {
+        self.tag.artist_sort()
+    }
+
+    pub fn set_artist_sort<'a, V: Into<Option<&'a str>>>(self: &mut Self, artist_sort: V) -> FieldWriteError {
+        self.tag.set_artist_sort(artist_sort)
+    }
+
+    pub fn album_artist_sort(self: &Self) -> OptionalStringReadError {
+        self.tag.album_artist_sort()
+    }
+
+    pub fn set_album_artist_sort<'a, V: Into<Option<&'a str>>>(self: &mut Self, album_artist_sort: V) -> FieldWriteError {
+        self.tag.set_album_artist_sort(album_artist_sort)
+    }
+
+    pub fn album_sort(self: &Self) -> OptionalStringReadError {
+        self.tag.album_sort()
+    }
+
+    pub fn set_album_sort<'a, V: Into<Option<&'a str>>>(self: &mut Self, album_sort: V) -> FieldWriteError {
+        self.tag.set_album_sort(album_sort)
+    }
+
+    pub fn title_sort(self: &Self) -> OptionalStringReadError {
+        self.tag.title_sort()
+    }
+
+    pub fn set_title_sort<'a, V: Into<Option<&'a str>>>(self: &mut Self, title_sort: V) -> FieldWriteError {
+        self.tag.set_title_sort(title_sort)
+    }
+
+    pub fn grouping(self: &Self) -> OptionalStringReadError {
+        self.tag.grouping()
+    }
+
+    pub fn set_grouping<'a, V: Into<Option<&'a str>>>(self: &mut Self, grouping: V) -> FieldWriteError {
+        self.tag.set_grouping(grouping)
+    }
+
+    pub fn initial_key(self: &Self) -> OptionalStringReadError {
+        self.tag.initial_key()
+    }
+
+    pub fn set_initial_key<'a, V: Into<Option<&'a str>>>(self: &mut Self, initial_key: V) -> FieldWriteError {
+        self.tag.set_initial_key(initial_key)
+    }
+
+    pub fn label(self: &Self) -> OptionalStringReadError {
+        self.tag.label()
+    }
+
+    pub fn label_with_key(self: &Self) -> Result<Option<(&'static str, String)>, Utf8Error> {
+        self.tag.label_with_key()
+    }
+
+    pub fn set_label<'a, V: Into<Option<&'a str>>>(self: &mut Self, label: V) -> FieldWriteError {
+        self.tag.set_label(label)
+    }
+
+    pub fn copyright(self: &Self) -> OptionalStringReadError {
+        self.tag.copyright()
+    }
+
+    pub fn set_copyright<'a, V: Into<Option<&'a str>>>(self: &mut Self, copyright: V) -> FieldWriteError {
+        self.tag.set_copyright(copyright)
+    }
+
+    pub fn encoded_by(self: &Self) -> OptionalStringReadError {
+        self.tag.encoded_by()
+    }
+
+    pub fn set_encoded_by<'a, V: Into<Option<&'a str>>>(self: &mut Self, encoded_by: V) -> FieldWriteError {
+        self.tag.set_encoded_by(encoded_by)
+    }
+
+    pub fn encoder_settings(self: &Self) -> OptionalStringReadError {
+        self.tag.encoder_settings()
+    }
+
+    pub fn set_encoder_settings<'a, V: Into<Option<&'a str>>>(self: &mut Self, encoder_settings: V) -> FieldWriteError {
+        self.tag.set_encoder_settings(encoder_settings)
+    }
+
+    pub fn language(self: &Self) -> OptionalStringReadError {
+        self.tag.language()
+    }
+
+    pub fn language_code(self: &Self) -> Option<&'static str> {
+        self.tag.language_code()
+    }
+
+    pub fn set_language<'a, V: Into<Option<&'a str>>>(self: &mut Self, language: V) -> FieldWriteError {
+        self.tag.set_language(language)
+    }
+
+    pub fn set_language_code(self: &mut Self, language: &str) -> FieldWriteError {
+        self.tag.set_language_code(language)
+    }
+
+    pub fn original_date(self: &Self) -> OptionalStringReadError {
+        self.tag.original_date()
+    }
+
+    pub fn original_year(self: &Self) -> Option<u32> {
+        self.tag.original_year()
+    }
+
+    pub fn set_original_date<'a, V: Into<Option<&'a str>>>(self: &mut Self, date: V) -> FieldWriteError {
+        self.tag.set_original_date(date)
+    }
+
+    pub fn set_original_year<V: Into<Option<u32>>>(self: &mut Self, year: V) -> FieldWriteError {
+        self.tag.set_original_year(year)
+    }
+
+    pub fn date(self: &Self) -> Option<PartialDate> {
+        self.tag.date()
+    }
+
+    pub fn set_date<V: Into<Option<PartialDate>>>(self: &mut Self, date: V) -> FieldWriteError {
+        self.tag.set_date(date)
+    }
+
+    pub fn mood(self: &Self) -> OptionalStringReadError {
+        self.tag.mood()
+    }
+
+    pub fn moods(self: &Self) -> Result<Vec<String>, Utf8Error> {
+        self.tag.moods()
+    }
+
+    pub fn set_mood<'a, V: Into<Option<&'a str>>>(self: &mut Self, mood: V) -> FieldWriteError {
+        self.tag.set_mood(mood)
+    }
+
+    pub fn set_moods(self: &mut Self, moods: &[&str]) -> FieldWriteError {
+        self.tag.set_moods(moods)
+    }
+
+    pub fn artists(self: &Self) -> Result<Vec<String>, Utf8Error> {
+        self.tag.artists()
+    }
+
+    pub fn artists_with(self: &Self, split_joined: bool) -> Result<Vec<String>, Utf8Error> {
+        self.tag.artists_with(split_joined)
+    }
+
+    pub fn set_artists(self: &mut Self, artists: &[&str]) -> FieldWriteError {
+        self.tag.set_artists(artists)
+    }
+
+    pub fn genres(self: &Self) -> Result<Vec<String>, Utf8Error> {
+        self.tag.genres()
+    }
+
+    pub fn genres_with(self: &Self, split_joined: bool) -> Result<Vec<String>, Utf8Error> {
+        self.tag.genres_with(split_joined)
+    }
+
+    pub fn set_genres(self: &mut Self, genres: &[&str]) -> FieldWriteError {
+        self.tag.set_genres(genres)
+    }
+
+    pub fn isrc(self: &Self) -> OptionalStringReadError {
+        self.tag.isrc()
+    }
+
+    pub fn set_isrc<'a, V: Into<Option<&'a str>>>(self: &mut Self, isrc: V) -> FieldWriteError {
+        self.tag.set_isrc(isrc)
+    }
+
+    pub fn set_isrc_unchecked<'a, V: Into<Option<&'a str>>>(self: &mut Self, isrc: V) -> FieldWriteError {
+        self.tag.set_isrc_unchecked(isrc)
+    }
+
+    pub fn disc(self: &Self) -> Option<(u32, Option<u32>)> {
+        self.tag.disc()
+    }
+
+    pub fn set_disc<N: Into<Option<u32>>, T: Into<Option<u32>>>(self: &mut Self, number: N, total: T) -> FieldWriteError {
+        self.tag.set_disc(number, total)
+    }
+
+    pub fn track_total(self: &Self) -> Option<u32> {
+        self.tag.track_total()
+    }
+
+    pub fn set_track_with_total<N: Into<Option<u32>>>(self: &mut Self, number: N, total: Option<u32>) -> FieldWriteError {
+        self.tag.set_track_with_total(number, total)
+    }
+
+    pub fn is_compilation(self: &Self) -> Option<bool> {
+        self.tag.is_compilation()
+    }
+
+    pub fn set_compilation(self: &mut Self, compilation: bool) -> FieldWriteError {
+        self.tag.set_compilation(compilation)
+    }
+
+    pub fn bpm_f64(self: &Self) -> Option<f64> {
+        self.tag.bpm_f64()
+    }
+
+    pub fn set_bpm_f64<V: Into<Option<f64>>>(self: &mut Self, bpm: V) -> FieldWriteError {
+        self.tag.set_bpm_f64(bpm)
+    }
+
+    pub fn lyrics(self: &Self) -> OptionalStringReadError {
+        self.tag.lyrics()
+    }
+
+    pub fn lyrics_language(self: &Self) -> Option<String> {
+        self.tag.lyrics_language()
+    }
+
+    pub fn set_lyrics<'a, V: Into<Option<&'a str>>>(self: &mut Self, lyrics: V) -> FieldWriteError {
+        self.tag.set_lyrics(lyrics)
+    }
+}
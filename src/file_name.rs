@@ -0,0 +1,85 @@
+// std library imports
+use std::ffi::CString;
+use std::os::raw::c_int;
+#[cfg(unix)]
+use std::os::unix::ffi::OsStrExt;
+#[cfg(windows)]
+use std::os::windows::ffi::OsStrExt;
+use std::path::Path;
+
+// taglib-sys imports
+use taglib_sys::*;
+
+use file_type::TypeCode;
+use {FileError, OpenOptions};
+
+/* A path, in the form that taglib's constructors take it. On windows this is a wide
+ * string, as taglib would otherwise go through the ANSI code page */
+#[cfg(not(windows))]
+pub struct FileName(CString);
+
+#[cfg(windows)]
+pub struct FileName(Vec<u16>);
+
+impl FileName {
+    // paths on unix are arbitrary bytes, which taglib passes straight on to fopen
+    #[cfg(unix)]
+    pub fn new(path: &Path) -> Result<FileName, FileError> {
+        CString::new(path.as_os_str().as_bytes())
+            .map(FileName)
+            .map_err(|err| FileError::NullPathString(path.to_owned(), err))
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    pub fn new(path: &Path) -> Result<FileName, FileError> {
+        // get the filename as a string, then a c string
+        match path.to_str() {
+            Some(filename) => CString::new(filename)
+                .map(FileName)
+                .map_err(|err| FileError::NullPathString(path.to_owned(), err)),
+            None => Err(FileError::PathAsString(path.to_owned())),
+        }
+    }
+
+    #[cfg(windows)]
+    pub fn new(path: &Path) -> Result<FileName, FileError> {
+        let mut wide: Vec<u16> = path.as_os_str().encode_wide().collect();
+        if wide.contains(&0) {
+            // get the error (with the position of the nul) in the same form as elsewhere
+            let err = CString::new(path.to_string_lossy().into_owned()).unwrap_err();
+            return Err(FileError::NullPathString(path.to_owned(), err));
+        }
+        wide.push(0);
+        Ok(FileName(wide))
+    }
+
+    /* Open the file through the ffi, either guessing its type from its extension, or as
+     * the type given in the options */
+    #[cfg(not(windows))]
+    pub unsafe fn open(self: &Self, options: &OpenOptions) -> *mut TagLib_File {
+        let filename = self.0.as_ptr();
+        let read_properties = options.audio_properties.is_some() as c_int;
+        let style = options.audio_properties.unwrap_or_default().to_taglib();
+        match options.file_type.map(|file_type| file_type.type_code()) {
+            None => taglib_ext_file_new(filename, read_properties, style),
+            Some(TypeCode::TagC(code)) => taglib_ext_file_new_type(filename, code, read_properties, style),
+            Some(TypeCode::Ext(code)) => taglib_ext_file_new_ext_type(filename, code, read_properties, style),
+        }
+    }
+
+    #[cfg(windows)]
+    pub unsafe fn open(self: &Self, options: &OpenOptions) -> *mut TagLib_File {
+        let filename = self.0.as_ptr();
+        let read_properties = options.audio_properties.is_some() as c_int;
+        let style = options.audio_properties.unwrap_or_default().to_taglib();
+        match options.file_type.map(|file_type| file_type.type_code()) {
+            None => taglib_ext_file_new_wchar(filename, read_properties, style),
+            Some(TypeCode::TagC(code)) => {
+                taglib_ext_file_new_type_wchar(filename, code, read_properties, style)
+            }
+            Some(TypeCode::Ext(code)) => {
+                taglib_ext_file_new_ext_type_wchar(filename, code, read_properties, style)
+            }
+        }
+    }
+}
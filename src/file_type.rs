@@ -0,0 +1,149 @@
+// std library imports
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+// taglib-sys imports
+use taglib_sys::*;
+
+// some types are in tag_c's list, and others we construct through the extensions
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TypeCode {
+    TagC(TagLib_File_Type),
+    Ext(TagLib_Ext_File_Type),
+}
+
+/* The formats that taglib can be told to open a file as */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileType {
+    Mpeg,
+    OggVorbis,
+    Flac,
+    Mpc,
+    OggFlac,
+    WavPack,
+    Speex,
+    TrueAudio,
+    Mp4,
+    Asf,
+    Opus,
+    Wav,
+    Aiff,
+}
+
+// the names that file types are displayed as, and parsed from
+const NAMES: [(FileType, &str); 13] = [
+    (FileType::Mpeg, "mpeg"),
+    (FileType::OggVorbis, "oggvorbis"),
+    (FileType::Flac, "flac"),
+    (FileType::Mpc, "mpc"),
+    (FileType::OggFlac, "oggflac"),
+    (FileType::WavPack, "wavpack"),
+    (FileType::Speex, "speex"),
+    (FileType::TrueAudio, "trueaudio"),
+    (FileType::Mp4, "mp4"),
+    (FileType::Asf, "asf"),
+    (FileType::Opus, "opus"),
+    (FileType::Wav, "wav"),
+    (FileType::Aiff, "aiff"),
+];
+
+// file extensions (in lower case) and the types that they usually mean
+const EXTENSIONS: [(&str, FileType); 24] = [
+    ("mp3", FileType::Mpeg),
+    ("mp2", FileType::Mpeg),
+    ("mpga", FileType::Mpeg),
+    ("ogg", FileType::OggVorbis),
+    ("oga", FileType::OggVorbis),
+    ("flac", FileType::Flac),
+    ("mpc", FileType::Mpc),
+    ("wv", FileType::WavPack),
+    ("spx", FileType::Speex),
+    ("tta", FileType::TrueAudio),
+    ("m4a", FileType::Mp4),
+    ("m4b", FileType::Mp4),
+    ("m4p", FileType::Mp4),
+    ("m4r", FileType::Mp4),
+    ("mp4", FileType::Mp4),
+    ("3g2", FileType::Mp4),
+    ("wma", FileType::Asf),
+    ("asf", FileType::Asf),
+    ("opus", FileType::Opus),
+    ("wav", FileType::Wav),
+    ("aif", FileType::Aiff),
+    ("aiff", FileType::Aiff),
+    ("aifc", FileType::Aiff),
+    ("afc", FileType::Aiff),
+];
+
+impl FileType {
+    /* Guess the type of a file from its extension (ignoring case). Unknown extensions,
+     * and files without one, give None */
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<FileType> {
+        let extension = path.as_ref().extension()?.to_str()?;
+        EXTENSIONS
+            .iter()
+            .find(|&&(known, _)| known.eq_ignore_ascii_case(extension))
+            .map(|&(_, file_type)| file_type)
+    }
+
+    // the type that taglib identifies as code
+    pub(crate) fn from_type_code(code: TypeCode) -> Option<FileType> {
+        NAMES
+            .iter()
+            .map(|&(file_type, _)| file_type)
+            .find(|file_type| file_type.type_code() == code)
+    }
+
+    // how taglib identifies this type
+    pub(crate) fn type_code(self: Self) -> TypeCode {
+        match self {
+            FileType::Mpeg => TypeCode::TagC(TagLib_File_Type_TagLib_File_MPEG),
+            FileType::OggVorbis => TypeCode::TagC(TagLib_File_Type_TagLib_File_OggVorbis),
+            FileType::Flac => TypeCode::TagC(TagLib_File_Type_TagLib_File_FLAC),
+            FileType::Mpc => TypeCode::TagC(TagLib_File_Type_TagLib_File_MPC),
+            FileType::OggFlac => TypeCode::TagC(TagLib_File_Type_TagLib_File_OggFlac),
+            FileType::WavPack => TypeCode::TagC(TagLib_File_Type_TagLib_File_WavPack),
+            FileType::Speex => TypeCode::TagC(TagLib_File_Type_TagLib_File_Speex),
+            FileType::TrueAudio => TypeCode::TagC(TagLib_File_Type_TagLib_File_TrueAudio),
+            FileType::Mp4 => TypeCode::TagC(TagLib_File_Type_TagLib_File_MP4),
+            FileType::Asf => TypeCode::TagC(TagLib_File_Type_TagLib_File_ASF),
+            FileType::Opus => TypeCode::Ext(TagLib_Ext_File_Type_TagLib_Ext_File_Opus),
+            FileType::Wav => TypeCode::Ext(TagLib_Ext_File_Type_TagLib_Ext_File_WAV),
+            FileType::Aiff => TypeCode::Ext(TagLib_Ext_File_Type_TagLib_Ext_File_AIFF),
+        }
+    }
+}
+
+impl fmt::Display for FileType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let &(_, name) = NAMES.iter().find(|&&(file_type, _)| file_type == *self).unwrap();
+        f.write_str(name)
+    }
+}
+
+/* The error from parsing a FileType, holding the string that wasn't recognised */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseFileTypeError(pub String);
+
+impl fmt::Display for ParseFileTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown file type {:?}", self.0)
+    }
+}
+
+impl Error for ParseFileTypeError {}
+
+/* File types are parsed from the names that they're displayed as, ignoring case */
+impl FromStr for FileType {
+    type Err = ParseFileTypeError;
+
+    fn from_str(s: &str) -> Result<FileType, ParseFileTypeError> {
+        NAMES
+            .iter()
+            .find(|&&(_, name)| name.eq_ignore_ascii_case(s))
+            .map(|&(file_type, _)| file_type)
+            .ok_or_else(|| ParseFileTypeError(s.to_owned()))
+    }
+}
@@ -0,0 +1,101 @@
+// std library imports
+use std::error::Error;
+use std::ffi::CString;
+use std::fmt;
+
+// taglib-sys imports
+use taglib_sys::*;
+
+use {PropertyError, TagLibFile, TagLibTag};
+
+const ITUNSMPB: &str = "iTunSMPB";
+
+/* The encoder delay and padding that iTunes stores in the iTunSMPB field, so that the
+ * silence that MP3 and AAC encoders add at each end can be cut for gapless playback */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GaplessInfo {
+    // samples of silence at the start
+    pub encoder_delay: u32,
+    // samples of silence at the end
+    pub encoder_padding: u32,
+    // samples of audio between them
+    pub original_length: u64,
+}
+
+/* Errors from reading gapless playback data */
+#[derive(Debug)]
+pub enum GaplessInfoError {
+    // the iTunSMPB field couldn't be read
+    Read(PropertyError),
+    // the iTunSMPB value isn't in the expected form, with the value
+    Parse(String),
+}
+
+impl fmt::Display for GaplessInfoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GaplessInfoError::Read(err) => err.fmt(f),
+            GaplessInfoError::Parse(value) => write!(f, "couldn't parse iTunSMPB value {:?}", value),
+        }
+    }
+}
+
+impl Error for GaplessInfoError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GaplessInfoError::Read(err) => Some(err),
+            GaplessInfoError::Parse(_) => None,
+        }
+    }
+}
+
+// parse e.g. " 00000000 00000840 000001CA 00000000003F31F6 00000000 ...", where after
+// a reserved field come the delay, the padding and the length, in hex
+fn parse_itunsmpb(value: &str) -> Option<GaplessInfo> {
+    let fields: Vec<&str> = value.split_whitespace().collect();
+    if fields.len() < 4 {
+        return None;
+    }
+    Some(GaplessInfo {
+        encoder_delay: u32::from_str_radix(fields[1], 16).ok()?,
+        encoder_padding: u32::from_str_radix(fields[2], 16).ok()?,
+        original_length: u64::from_str_radix(fields[3], 16).ok()?,
+    })
+}
+
+impl TagLibTag {
+    /* The gapless playback data, from a comment (COMM) described as iTunSMPB in ID3v2,
+     * as iTunes writes it, or else from a user text field of that name, e.g. a TXXX
+     * frame, or the ----:com.apple.iTunes:iTunSMPB atom in MP4. None if there's no
+     * iTunSMPB field. Writing it is left to encoders. */
+    pub fn gapless_info(self: &Self) -> Result<Option<GaplessInfo>, GaplessInfoError> {
+        if self.file.is_null() {
+            return Ok(None);
+        }
+        let mut value = None;
+        if self.has_id3v2_properties() {
+            let cs_description = CString::new(ITUNSMPB).expect("descriptions don't contain nul bytes");
+            let value_ptr = unsafe { taglib_ext_id3v2_comment(self.file, cs_description.as_ptr()) };
+            if !value_ptr.is_null() {
+                let comment = Self::read_and_parse(value_ptr).map_err(PropertyError::InvalidValue);
+                value = Some(comment.map_err(GaplessInfoError::Read)?);
+            }
+        }
+        if value.is_none() {
+            value = self.user_text(ITUNSMPB).map_err(GaplessInfoError::Read)?;
+        }
+        match value {
+            None => Ok(None),
+            Some(value) => match parse_itunsmpb(&value) {
+                Some(info) => Ok(Some(info)),
+                None => Err(GaplessInfoError::Parse(value)),
+            },
+        }
+    }
+}
+
+impl TagLibFile {
+    pub fn gapless_info(self: &Self) -> Result<Option<GaplessInfo>, GaplessInfoError> {
+        self.tag.gapless_info()
+    }
+}
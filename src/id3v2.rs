@@ -0,0 +1,91 @@
+// std library imports
+use std::os::raw::c_int;
+
+// taglib-sys imports
+use taglib_sys::*;
+
+/* The text encodings that ID3v2 frames can use.
+ *
+ * This is separate from StringEncodingPolicy, which decides which characters the
+ * setters accept. The encoding decides how the frames store them: Latin1 frames are
+ * moved to a unicode encoding by taglib if their text doesn't fit, so pair Latin1
+ * with StringEncodingPolicy::Latin1 to be sure that every frame stays Latin-1. Utf8
+ * and Utf16Be only exist in ID3v2.4, and taglib writes them as Utf16 in ID3v2.3 */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Id3v2Encoding {
+    Latin1,
+    Utf16,
+    Utf16Be,
+    Utf8,
+}
+
+/* How much padding to leave after the frames of an ID3v2 tag when saving. Padding lets
+ * later edits be written in place, rather than moving all of the audio along.
+ *
+ * Taglib won't write more than 1% of the file's size, or 1 KiB for files smaller than
+ * 100 KiB, and never more than 1 MiB. It reduces a larger reservation to 1 KiB. It also
+ * writes 1 KiB when asked for none at all, so the least padding that it can be made to
+ * leave is a single byte */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PaddingPolicy {
+    // keep the padding that the tag already has (if it's not too large), as taglib does
+    #[default]
+    Default,
+    // drop any existing padding, leaving the least that taglib allows (one byte)
+    Minimal,
+    // leave this many bytes of padding
+    Reserve(u32),
+}
+
+/* The version of ID3v2 that MPEG files' tags are saved as. Much older hardware and
+ * software only reads ID3v2.3, which lacks some of ID3v2.4's frames, so when saving
+ * it:
+ *
+ * - the date (TDRC) is written as TYER, TDAT and TIME, the original date (TDOR) as
+ *   TORY, and the involved people and musicians (TIPL and TMCL) as IPLS
+ * - the mood, sort orders, disc subtitle and release date are written as TXXX frames
+ *   under their property keys (e.g. TXXX:MOOD for TMOO), which taglib reads back as
+ *   the same properties
+ * - frames with several values have them joined with "/"
+ * - the rest of ID3v2.4's frames (e.g. RVA2 and TPRO) are dropped
+ *
+ * The frames are changed in the open file as well, so they stay that way if it's
+ * saved again as ID3v2.4. Other formats are always saved as ID3v2.4 */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Id3v2Version {
+    V3,
+    #[default]
+    V4,
+}
+
+impl Id3v2Version {
+    // the version's number, as the extensions take it
+    pub(crate) fn number(self: Self) -> c_int {
+        match self {
+            Id3v2Version::V3 => 3,
+            Id3v2Version::V4 => 4,
+        }
+    }
+}
+
+impl PaddingPolicy {
+    // the padding to ask the extensions for, if any
+    pub(crate) fn bytes(self: Self) -> Option<u32> {
+        match self {
+            PaddingPolicy::Default => None,
+            PaddingPolicy::Minimal => Some(1),
+            PaddingPolicy::Reserve(bytes) => Some(bytes),
+        }
+    }
+}
+
+impl Id3v2Encoding {
+    pub(crate) fn to_taglib(self: Self) -> TagLib_ID3v2_Encoding {
+        match self {
+            Id3v2Encoding::Latin1 => TagLib_ID3v2_Encoding_TagLib_ID3v2_Latin1,
+            Id3v2Encoding::Utf16 => TagLib_ID3v2_Encoding_TagLib_ID3v2_UTF16,
+            Id3v2Encoding::Utf16Be => TagLib_ID3v2_Encoding_TagLib_ID3v2_UTF16BE,
+            Id3v2Encoding::Utf8 => TagLib_ID3v2_Encoding_TagLib_ID3v2_UTF8,
+        }
+    }
+}
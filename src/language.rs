@@ -0,0 +1,75 @@
+// the languages that language codes are normalised for: the English name, the ISO 639-1
+// code, and the ISO 639-2 codes, bibliographic first, then terminological, where they differ
+const LANGUAGES: [(&str, &str, &str, &str); 49] = [
+    ("English", "en", "eng", "eng"),
+    ("French", "fr", "fre", "fra"),
+    ("German", "de", "ger", "deu"),
+    ("Spanish", "es", "spa", "spa"),
+    ("Italian", "it", "ita", "ita"),
+    ("Portuguese", "pt", "por", "por"),
+    ("Dutch", "nl", "dut", "nld"),
+    ("Swedish", "sv", "swe", "swe"),
+    ("Norwegian", "no", "nor", "nor"),
+    ("Danish", "da", "dan", "dan"),
+    ("Finnish", "fi", "fin", "fin"),
+    ("Icelandic", "is", "ice", "isl"),
+    ("Polish", "pl", "pol", "pol"),
+    ("Czech", "cs", "cze", "ces"),
+    ("Slovak", "sk", "slo", "slk"),
+    ("Hungarian", "hu", "hun", "hun"),
+    ("Romanian", "ro", "rum", "ron"),
+    ("Greek", "el", "gre", "ell"),
+    ("Turkish", "tr", "tur", "tur"),
+    ("Russian", "ru", "rus", "rus"),
+    ("Ukrainian", "uk", "ukr", "ukr"),
+    ("Bulgarian", "bg", "bul", "bul"),
+    ("Serbian", "sr", "srp", "srp"),
+    ("Croatian", "hr", "hrv", "hrv"),
+    ("Slovenian", "sl", "slv", "slv"),
+    ("Estonian", "et", "est", "est"),
+    ("Latvian", "lv", "lav", "lav"),
+    ("Lithuanian", "lt", "lit", "lit"),
+    ("Irish", "ga", "gle", "gle"),
+    ("Welsh", "cy", "wel", "cym"),
+    ("Catalan", "ca", "cat", "cat"),
+    ("Basque", "eu", "baq", "eus"),
+    ("Hebrew", "he", "heb", "heb"),
+    ("Arabic", "ar", "ara", "ara"),
+    ("Persian", "fa", "per", "fas"),
+    ("Hindi", "hi", "hin", "hin"),
+    ("Bengali", "bn", "ben", "ben"),
+    ("Urdu", "ur", "urd", "urd"),
+    ("Chinese", "zh", "chi", "zho"),
+    ("Japanese", "ja", "jpn", "jpn"),
+    ("Korean", "ko", "kor", "kor"),
+    ("Vietnamese", "vi", "vie", "vie"),
+    ("Thai", "th", "tha", "tha"),
+    ("Indonesian", "id", "ind", "ind"),
+    ("Malay", "ms", "may", "msa"),
+    ("Tagalog", "tl", "tgl", "tgl"),
+    ("Swahili", "sw", "swa", "swa"),
+    ("Latin", "la", "lat", "lat"),
+    ("Esperanto", "eo", "epo", "epo"),
+];
+
+/* Normalise a language, written as an ISO 639-1 or 639-2 code, or as its English name,
+ * to the ISO 639-2 (bibliographic) code that ID3v2 uses, e.g. "en", "en-GB" and
+ * "English" are all "eng". "und" (undetermined) and "mul" (multiple languages) are
+ * kept as they are. Anything else is None. */
+pub fn iso_639_2_code(language: &str) -> Option<&'static str> {
+    let language = language.trim();
+    // ignore the region of tags like "en-GB" or "pt_BR"
+    let language = language.split(['-', '_']).next().unwrap_or(language);
+    if language.eq_ignore_ascii_case("und") {
+        return Some("und");
+    }
+    if language.eq_ignore_ascii_case("mul") {
+        return Some("mul");
+    }
+    LANGUAGES
+        .iter()
+        .find(|&&(name, alpha2, bibliographic, terminological)| {
+            [name, alpha2, bibliographic, terminological].iter().any(|code| code.eq_ignore_ascii_case(language))
+        })
+        .map(|&(_, _, bibliographic, _)| bibliographic)
+}
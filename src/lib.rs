@@ -4,86 +4,587 @@ extern crate taglib_sys;
 // std library imports
 use std::ffi::CStr;
 use std::ffi::CString;
-use std::ffi::NulError;
+use std::error::Error;
+use std::fmt;
 use std::os::raw::c_void;
 use std::os::raw::c_char;
+use std::os::raw::c_int;
+use std::os::raw::c_uint;
 use std::str::Utf8Error;
+use std::fs;
+use std::io;
+use std::path::Path;
 use std::path::PathBuf;
 
 // taglib-sys imports
 use taglib_sys::*;
 
+mod error;
+pub use error::FileError;
+
+mod version;
+pub use version::{version, Version};
+
+mod file_name;
+use file_name::FileName;
+
+mod sniff;
+pub use sniff::sniff_file_type;
+
+mod stream;
+use stream::Stream;
+
+mod file_type;
+use file_type::TypeCode;
+pub use file_type::{FileType, ParseFileTypeError};
+
+mod open_options;
+pub use open_options::{OpenOptions, ReadStyle};
+
+mod audio_properties;
+pub use audio_properties::{AudioProperties, DurationPrecision};
+
+mod tag_data;
+pub use tag_data::{FieldReadError, SetError, SetErrorKind, TagData, TagField};
+
+mod id3v2;
+pub use id3v2::{Id3v2Encoding, Id3v2Version, PaddingPolicy};
+
+mod nul_handling;
+pub use nul_handling::NulHandling;
+
+mod string_encoding;
+pub use string_encoding::StringEncodingPolicy;
+
+mod tag_str;
+pub use tag_str::TagStr;
+
+mod diff;
+pub use diff::{DiffOptions, FieldChange, FieldValue};
+
+mod copy;
+pub use copy::CopyError;
+
+mod save_error;
+pub use save_error::{SaveError, SaveErrorKind};
+
+mod backup;
+pub use backup::{restore_from_backup, BackupMode};
+
+mod save;
+pub use save::{OtherTags, SaveOptions, SaveWarning};
+
+mod tag_edit;
+pub use tag_edit::{EditError, TagEdit};
+
+mod property;
+pub use property::{PropertyError, PropertyParseError};
+
+mod fields;
+
+mod musicbrainz;
+pub use musicbrainz::MusicBrainzIds;
+
+mod replay_gain;
+pub use replay_gain::{ReplayGain, ReplayGainError};
+
+mod musical_key;
+pub use musical_key::{MusicalKey, ParseMusicalKeyError};
+
+mod language;
+pub use language::iso_639_2_code;
+
+mod date;
+pub use date::{ParseDateError, PartialDate};
+
+mod rating;
+pub use rating::RatingConvention;
+
+mod podcast;
+pub use podcast::PodcastInfo;
+
+mod gapless;
+pub use gapless::{GaplessInfo, GaplessInfoError};
+
+mod classical;
+pub use classical::ClassicalInfo;
+
+mod picture;
+pub use picture::{image_mime_type, Picture, PictureDimensions, PictureError, PictureType};
+
+mod picture_constraints;
+pub use picture_constraints::{image_dimensions, ConstraintViolation, PictureConstraints};
+
+mod tag_container;
+pub use tag_container::TagContainer;
+
 /* Define a file interface */
-#[derive(Debug)]
 pub struct TagLibFile {
+    // the path that the file was opened with, kept so that it can be reopened. Empty
+    // for files opened from memory, or from an open file
+    path: PathBuf,
+    // the options that the file was opened with, kept so that it can be reopened
+    options: OpenOptions,
     file_handle: *mut TagLib_File,
     tag: TagLibTag,
-}
-
-/* Various kinds of errors that we can get from using a file */
-#[derive(Debug)]
-pub enum FileError {
-    OpenFailure,
-    SaveFailure,
-    PathAsString,
-    NullPathString(NulError),
-    InvalidTagFile
+    audio_properties: Option<AudioProperties>,
+    // the stream that the file is read from, if it wasn't opened by path. Dropped after
+    // the file handle is freed
+    stream: Option<Stream>,
 }
 
 impl TagLibFile {
 
     /* Open a file with tag information */
     pub fn new<P: Into<PathBuf>>(filename: P) -> Result<TagLibFile, FileError> {
-        // get the filename as a string, then a c string
-        let cs_filename = filename
-            .into()
-            .to_str()
-            .ok_or(FileError::PathAsString)
-            .and_then(|filename| {
-                CString::new(filename).map_err(|err| FileError::NullPathString(err))
-            })?;
-
-        unsafe {
-            // start off by setting the string management options 
-            // this does mean that we need to manually free all the strings that get returned to us, however.
-            taglib_set_string_management_enabled(false as i32);
+        OpenOptions::new().open(filename)
+    }
+
+    /* Open a file as a particular type, rather than guessing the type from its extension */
+    pub fn new_with_type<P: Into<PathBuf>>(filename: P, file_type: FileType) -> Result<TagLibFile, FileError> {
+        OpenOptions::new().file_type(file_type).open(filename)
+    }
+
+    /* Open a file that must never be written to. The tag can still be changed in memory,
+     * but save() will always fail with FileError::ReadOnly, without touching the file */
+    pub fn open_read_only<P: Into<PathBuf>>(filename: P) -> Result<TagLibFile, FileError> {
+        OpenOptions::new().read_only(true).open(filename)
+    }
+
+    /* Open a file from a copy of its contents in memory, rather than from disk. As
+     * there's no extension to guess the type from, it has to be given. Files from memory
+     * are read only, as there would be nowhere to save them to */
+    pub fn from_bytes(data: &[u8], file_type: FileType) -> Result<TagLibFile, FileError> {
+        OpenOptions::new().file_type(file_type).open_bytes(data)
+    }
+
+    fn open_bytes(data: &[u8], mut options: OpenOptions) -> Result<TagLibFile, FileError> {
+        options.read_only = true;
+        Self::open_stream(Stream::from_bytes(data), options)
+    }
+
+    /* Open a file that's already open, such as one whose descriptor was passed from
+     * another process, and which can't be reopened by path. As there's no extension to
+     * guess the type from, it has to be given. The file is consumed, and closed when the
+     * TagLibFile is dropped. Saving writes through the file, so needs it to have been
+     * opened for writing */
+    #[cfg(unix)]
+    pub fn from_file(file: fs::File, file_type: FileType) -> Result<TagLibFile, FileError> {
+        OpenOptions::new().file_type(file_type).open_file(file)
+    }
+
+    #[cfg(unix)]
+    fn open_file(file: fs::File, options: OpenOptions) -> Result<TagLibFile, FileError> {
+        let stream = Stream::from_file(file, options.read_only)
+            .map_err(|err| FileError::Io(PathBuf::new(), err))?;
+        Self::open_stream(stream, options)
+    }
+
+    fn open_stream(stream: Stream, options: OpenOptions) -> Result<TagLibFile, FileError> {
+        unsafe {
+            let file_ptr = stream.open(&options);
+            Self::from_handle(PathBuf::new(), options, file_ptr, Some(stream))
+        }
+    }
+
+    fn open(path: PathBuf, options: OpenOptions) -> Result<TagLibFile, FileError> {
+        let file_name = FileName::new(&path)?;
+
+        // taglib can't tell us why it couldn't open a file, so check that we can get at it
+        // first, to be able to tell a missing file from one in a format taglib doesn't know.
+        // This follows symlinks, and is done before opening, which would block on a fifo
+        match fs::metadata(&path) {
+            Err(err) => return Err(FileError::Io(path, err)),
+            Ok(ref metadata) if metadata.is_dir() => return Err(FileError::IsADirectory(path)),
+            Ok(ref metadata) if !metadata.is_file() => return Err(FileError::NotARegularFile(path)),
+            Ok(_) => (),
+        }
+        if let Err(err) = fs::File::open(&path) {
+            return Err(FileError::Io(path, err));
+        }
+
+        unsafe {
             // try to open the file using the ffi
-            let file_ptr = taglib_file_new(cs_filename.as_ptr());
-            // Todo: Should the struct member be a reference instead?
-            if file_ptr.is_null() {
-                return Err(FileError::OpenFailure);
+            let file_ptr = file_name.open(&options);
+            Self::from_handle(path, options, file_ptr, None)
+        }
+    }
+
+    // wrap a handle that taglib has just opened, checking that it's usable
+    unsafe fn from_handle(
+        path: PathBuf,
+        options: OpenOptions,
+        file_ptr: *mut TagLib_File,
+        stream: Option<Stream>,
+    ) -> Result<TagLibFile, FileError> {
+        // start off by setting the string management options 
+        // this does mean that we need to manually free all the strings that get returned to us, however.
+        taglib_set_string_management_enabled(false as i32);
+        // rust strings are UTF-8, so that's how taglib needs to read and write them
+        taglib_set_strings_unicode(true as i32);
+        // Todo: Should the struct member be a reference instead?
+        if file_ptr.is_null() {
+            return Err(FileError::OpenFailure(path));
+        } else {
+            // Check to see if the tag file is valid (true/false as int)
+            if taglib_file_is_valid(file_ptr) == 0 { 
+                taglib_file_free(file_ptr);
+                return Err(FileError::InvalidTagFile(path))
+            }
+            // pub fn taglib_file_is_valid(file: *const TagLib_File) -> ::std::os::raw::c_int;
+            // Get the tag. We want to do this here, so that any references to it only live as long as the file (which is dropped through the drop trait)
+            let tag = TagLibTag::from_file(file_ptr, taglib_file_tag(file_ptr), options.string_encoding);
+            // not every file has audio properties (they can be skipped with OpenOptions)
+            let properties_ptr = taglib_file_audioproperties(file_ptr);
+            let audio_properties = if properties_ptr.is_null() {
+                None
             } else {
-                // Check to see if the tag file is valid (true/false as int)
-                if taglib_file_is_valid(file_ptr) == 0 { 
-                    return Err(FileError::InvalidTagFile)
-                }
-                // pub fn taglib_file_is_valid(file: *const TagLib_File) -> ::std::os::raw::c_int;
-                // Get the tag. We want to do this here, so that any references to it only live as long as the file (which is dropped through the drop trait)
-                let tag_ptr = taglib_file_tag(file_ptr);
-                return Ok(TagLibFile {
-                    file_handle: file_ptr,
-                    tag: TagLibTag::from_ptr(tag_ptr),
-                });
+                Some(AudioProperties::from_ptr(properties_ptr))
+            };
+            return Ok(TagLibFile {
+                path,
+                options,
+                file_handle: file_ptr,
+                tag,
+                audio_properties,
+                stream,
+            });
+        }
+    }
+
+    /* Open a second, independent handle to the same file. The handles don't see each
+     * other's unsaved changes, and if both are saved then the last save wins */
+    pub fn try_clone(self: &Self) -> Result<TagLibFile, FileError> {
+        let mut clone = self.reopen()?;
+        clone.tag.nul_handling = self.tag.nul_handling;
+        Ok(clone)
+    }
+
+    /* Re-read the file from disk, picking up any changes made to it since it was opened,
+     * and discarding any unsaved changes made through this handle. If the file can no
+     * longer be opened, then the error is returned and this handle is left as it was */
+    pub fn reload(self: &mut Self) -> Result<(), FileError> {
+        let mut reloaded = self.reopen()?;
+        // keep any settings, which aren't part of the file
+        reloaded.tag.nul_handling = self.tag.nul_handling;
+        // replacing self drops (and so frees) the old taglib file
+        *self = reloaded;
+        Ok(())
+    }
+
+    // open the file again, from wherever it came from
+    fn reopen(self: &Self) -> Result<TagLibFile, FileError> {
+        match self.stream {
+            Some(ref stream) => {
+                let stream = stream
+                    .try_clone(self.options.read_only)
+                    .map_err(|err| FileError::Io(self.path.clone(), err))?;
+                Self::open_stream(stream, self.options.clone())
             }
+            None => Self::open(self.path.clone(), self.options.clone()),
         }
     }
 
-    pub fn save(self: &Self) -> Result<(), FileError> { 
+    /* Save any changes to the file. If nothing has been changed, then the file isn't
+     * touched at all (so its modification time stays the same). Files opened read only
+     * can't be saved, whether or not they've been changed */
+    pub fn save(self: &mut Self) -> Result<(), FileError> { 
+        self.save_in_place(&SaveOptions::new())
+    }
+
+    // save, giving any ID3v2 tag the padding asked for, and MPEG files the tags and ID3v2
+    // version asked for
+    fn save_in_place(self: &mut Self, options: &SaveOptions) -> Result<(), FileError> {
+        if self.options.read_only {
+            return Err(FileError::ReadOnly(self.path.clone()));
+        }
+        if !self.is_modified() {
+            return Ok(());
+        }
         unsafe {
-            let status_code = taglib_file_save(self.file_handle);
+            if let Some(encoding) = self.options.id3v2_encoding {
+                taglib_ext_id3v2_set_text_encoding(self.file_handle, encoding.to_taglib());
+            }
+            if let Some(bytes) = options.id3v2_padding.bytes() {
+                taglib_ext_id3v2_set_padding(self.file_handle, bytes);
+            }
+            let is_mpeg = self.tag.file_type() == Some(FileType::Mpeg);
+            let status_code = if is_mpeg && (options.mpeg_tags.is_some() || options.id3v2_version != Id3v2Version::V4) {
+                // taglib's own save writes every kind of tag that has something in it
+                let (mask, strip) = match options.mpeg_tags {
+                    Some((ref tags, others)) => (TagContainer::to_mask(tags), others == OtherTags::Strip),
+                    None => (TagContainer::to_mask(&[TagContainer::Id3v1, TagContainer::Id3v2, TagContainer::Ape]), true),
+                };
+                taglib_ext_mpeg_save(self.file_handle, mask, strip as c_int, options.id3v2_version.number())
+            } else {
+                taglib_file_save(self.file_handle)
+            };
             // status code returns true on success, so compare with 0/non-zero
             if status_code == 0 { 
-                Err(FileError::SaveFailure)
+                Err(FileError::SaveFailure(SaveError::probe(&self.path)))
             } else { 
+                self.tag.modified = false;
                 Ok(())
             }
         }
     }
 
+    // the path that the file was opened with, exactly as it was given
+    pub fn path(self: &Self) -> &Path {
+        &self.path
+    }
+
+    // the path, with symlinks and relative components resolved
+    pub fn canonical_path(self: &Self) -> io::Result<PathBuf> {
+        self.path.canonicalize()
+    }
+
+    // how the string setters deal with nul bytes, see NulHandling
+    pub fn set_nul_handling(self: &mut Self, nul_handling: NulHandling) -> () {
+        self.tag.set_nul_handling(nul_handling)
+    }
+
+    // whether the file was opened read only, see open_read_only
+    pub fn is_read_only(self: &Self) -> bool {
+        self.options.read_only
+    }
+
+    /* The format that taglib opened the file as, which (unless it was given when opening)
+     * comes from the extension, or for formats like mpeg, the contents. None for formats
+     * that taglib supports, but FileType doesn't, like tracker modules */
+    pub fn file_type(self: &Self) -> Option<FileType> {
+        self.tag.file_type()
+    }
+
+    // the options that the file was opened with
+    pub fn options(self: &Self) -> &OpenOptions {
+        &self.options
+    }
+
+    // whether there are changes that haven't been saved yet
+    pub fn is_modified(self: &Self) -> bool {
+        self.tag.is_modified()
+    }
+
+    // whether the file's tag matches a snapshot, using the same equality as TagData
+    pub fn tags_equal(self: &Self, other: &TagData) -> Result<bool, FieldReadError> {
+        let (data, mut errors) = self.tag.to_tag_data();
+        match errors.pop() {
+            Some(error) => Err(error),
+            None => Ok(data == *other),
+        }
+    }
+
     // return a reference to the tag that only lives as long as the file
     pub fn tag(self: &Self) -> &TagLibTag { 
         &self.tag
     }
+
+    // return a mutable reference to the tag, for calling setters on
+    pub fn tag_mut(self: &mut Self) -> &mut TagLibTag {
+        &mut self.tag
+    }
+
+    // start a batch of changes to the tag, which are written when the edit is saved
+    pub fn edit(self: &mut Self) -> TagEdit<'_> {
+        TagEdit::new(self)
+    }
+
+    // return a reference to the audio properties, again only living as long as the file
+    pub fn audio_properties(self: &Self) -> Option<&AudioProperties> {
+        self.audio_properties.as_ref()
+    }
+
+    /* Forwarding accessors, so that simple uses don't need to go through tag() */
+
+    pub fn title(self: &Self) -> StringReadError {
+        self.tag.title()
+    }
+
+    pub fn artist(self: &Self) -> StringReadError {
+        self.tag.artist()
+    }
+
+    pub fn album(self: &Self) -> StringReadError {
+        self.tag.album()
+    }
+
+    pub fn comment(self: &Self) -> StringReadError {
+        self.tag.comment()
+    }
+
+    pub fn genre(self: &Self) -> StringReadError {
+        self.tag.genre()
+    }
+
+    pub fn title_opt(self: &Self) -> OptionalStringReadError {
+        self.tag.title_opt()
+    }
+
+    pub fn artist_opt(self: &Self) -> OptionalStringReadError {
+        self.tag.artist_opt()
+    }
+
+    pub fn album_opt(self: &Self) -> OptionalStringReadError {
+        self.tag.album_opt()
+    }
+
+    pub fn comment_opt(self: &Self) -> OptionalStringReadError {
+        self.tag.comment_opt()
+    }
+
+    pub fn genre_opt(self: &Self) -> OptionalStringReadError {
+        self.tag.genre_opt()
+    }
+
+    pub fn title_lossy(self: &Self) -> String {
+        self.tag.title_lossy()
+    }
+
+    pub fn artist_lossy(self: &Self) -> String {
+        self.tag.artist_lossy()
+    }
+
+    pub fn album_lossy(self: &Self) -> String {
+        self.tag.album_lossy()
+    }
+
+    pub fn comment_lossy(self: &Self) -> String {
+        self.tag.comment_lossy()
+    }
+
+    pub fn genre_lossy(self: &Self) -> String {
+        self.tag.genre_lossy()
+    }
+
+    pub fn title_bytes(self: &Self) -> Vec<u8> {
+        self.tag.title_bytes()
+    }
+
+    pub fn artist_bytes(self: &Self) -> Vec<u8> {
+        self.tag.artist_bytes()
+    }
+
+    pub fn album_bytes(self: &Self) -> Vec<u8> {
+        self.tag.album_bytes()
+    }
+
+    pub fn comment_bytes(self: &Self) -> Vec<u8> {
+        self.tag.comment_bytes()
+    }
+
+    pub fn genre_bytes(self: &Self) -> Vec<u8> {
+        self.tag.genre_bytes()
+    }
+
+    pub fn year(self: &Self) -> Option<u32> {
+        self.tag.year()
+    }
+
+    pub fn track(self: &Self) -> Option<u32> {
+        self.tag.track()
+    }
+
+    pub fn bpm(self: &Self) -> Option<u32> {
+        self.tag.bpm()
+    }
+
+    pub fn set_title(self: &mut Self, title: &str) -> FieldWriteError {
+        self.tag.set_title(title)
+    }
+
+    pub fn set_artist(self: &mut Self, artist: &str) -> FieldWriteError {
+        self.tag.set_artist(artist)
+    }
+
+    pub fn set_album(self: &mut Self, album: &str) -> FieldWriteError {
+        self.tag.set_album(album)
+    }
+
+    pub fn set_comment(self: &mut Self, comment: &str) -> FieldWriteError {
+        self.tag.set_comment(comment)
+    }
+
+    pub fn set_genre(self: &mut Self, genre: &str) -> FieldWriteError {
+        self.tag.set_genre(genre)
+    }
+
+    pub fn set_year<V: Into<Option<u32>>>(self: &mut Self, year: V) -> FieldWriteError {
+        self.tag.set_year(year)
+    }
+
+    pub fn set_track<V: Into<Option<u32>>>(self: &mut Self, track: V) -> FieldWriteError {
+        self.tag.set_track(track)
+    }
+
+    pub fn set_bpm<V: Into<Option<u32>>>(self: &mut Self, bpm: V) -> FieldWriteError {
+        self.tag.set_bpm(bpm)
+    }
+
+    pub fn clear(self: &mut Self) -> FieldWriteError {
+        self.tag.clear()
+    }
+}
+
+/* Open a file, snapshot its standard fields, and close it again before returning, so
+ * that the file is held open for as short a time as possible. Fields that aren't
+ * valid UTF-8 are left as None - use TagLibTag::to_tag_data to find out about them.
+ * The duration isn't a tag, so TagData doesn't have it - open a TagLibFile and use
+ * its audio_properties for that */
+pub fn read_tags<P: Into<PathBuf>>(filename: P) -> Result<TagData, FileError> {
+    let file = TagLibFile::new(filename)?;
+    let (data, _) = file.tag().to_tag_data();
+    Ok(data)
+}
+
+/* Errors from update_tags: either the file couldn't be opened/saved, or the edit failed */
+#[derive(Debug)]
+pub enum UpdateError<E> {
+    File(FileError),
+    Edit(E),
+}
+
+impl<E: fmt::Display> fmt::Display for UpdateError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UpdateError::File(err) => err.fmt(f),
+            UpdateError::Edit(err) => err.fmt(f),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for UpdateError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            UpdateError::File(err) => Some(err),
+            UpdateError::Edit(err) => Some(err),
+        }
+    }
+}
+
+/* Open a file, run edit on its tag, and save it only if edit succeeded. The file is
+ * closed before returning, including when edit fails or panics. */
+pub fn update_tags<P, F, E>(filename: P, edit: F) -> Result<(), UpdateError<E>>
+where
+    P: Into<PathBuf>,
+    F: FnOnce(&mut TagLibTag) -> Result<(), E>,
+{
+    let mut file = TagLibFile::new(filename).map_err(UpdateError::File)?;
+    edit(file.tag_mut()).map_err(UpdateError::Edit)?;
+    file.save().map_err(UpdateError::File)
+}
+
+// show what the file is, rather than where taglib's handles happen to live in memory
+impl fmt::Debug for TagLibFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TagLibFile")
+            .field("path", &self.path)
+            .field("file_type", &self.file_type())
+            .field("valid", &(unsafe { taglib_file_is_valid(self.file_handle) } != 0))
+            .field("modified", &self.is_modified())
+            .field("read_only", &self.options.read_only)
+            .finish()
+    }
 }
 
 impl Drop for TagLibFile {
@@ -97,17 +598,100 @@ impl Drop for TagLibFile {
 
 type StringReadError = Result<String, Utf8Error>;
 
-type StringWriteError = Result<(), NulError>; 
+type OptionalStringReadError = Result<Option<String>, Utf8Error>;
 
-#[derive(Debug)]
+type FieldWriteError = Result<(), SetError>;
+
+/// A file's tag. This can only be borrowed from the TagLibFile that owns it, so it
+/// can't outlive the file, and building one from a raw pointer is unsafe:
+///
+/// ```compile_fail
+/// let tag = taglib::TagLibTag::from_ptr(std::ptr::null_mut());
+/// ```
+///
+/// as is building a file's audio properties from one:
+///
+/// ```compile_fail
+/// let properties = taglib::AudioProperties::from_ptr(std::ptr::null());
+/// ```
 pub struct TagLibTag {
-    tag: *mut TagLib_Tag
+    tag: *mut TagLib_Tag,
+    // the file that the tag came from, used to look in its property map. May be null.
+    file: *mut TagLib_File,
+    // whether any setters have been called since the tag was read (or last saved)
+    modified: bool,
+    // what the string setters do with nul bytes
+    nul_handling: NulHandling,
+    // which characters the string setters accept
+    string_encoding: StringEncodingPolicy,
+}
+
+impl fmt::Debug for TagLibTag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TagLibTag")
+            .field("modified", &self.modified)
+            .finish()
+    }
 }
 
 // Todo: should this be merged with taglib file?
 impl TagLibTag { 
-    pub fn from_ptr(ptr: *mut TagLib_Tag) -> TagLibTag { 
-        TagLibTag { tag: ptr }
+    /// # Safety
+    /// The pointer must stay valid for as long as the tag is used.
+    pub unsafe fn from_ptr(ptr: *mut TagLib_Tag) -> TagLibTag { 
+        TagLibTag {
+            tag: ptr,
+            file: std::ptr::null_mut(),
+            modified: false,
+            nul_handling: NulHandling::default(),
+            string_encoding: StringEncodingPolicy::default(),
+        }
+    }
+
+    fn from_file(
+        file_ptr: *mut TagLib_File,
+        tag_ptr: *mut TagLib_Tag,
+        string_encoding: StringEncodingPolicy,
+    ) -> TagLibTag {
+        TagLibTag {
+            tag: tag_ptr,
+            file: file_ptr,
+            modified: false,
+            nul_handling: NulHandling::default(),
+            string_encoding,
+        }
+    }
+
+    /* Whether any setters have been called since the tag was read, or last saved */
+    pub fn is_modified(self: &Self) -> bool {
+        self.modified
+    }
+
+    /* How the string setters deal with nul bytes, which taglib can't store */
+    pub fn nul_handling(self: &Self) -> NulHandling {
+        self.nul_handling
+    }
+
+    pub fn set_nul_handling(self: &mut Self, nul_handling: NulHandling) -> () {
+        self.nul_handling = nul_handling;
+    }
+
+    /* Which characters the string setters accept */
+    pub fn string_encoding(self: &Self) -> StringEncodingPolicy {
+        self.string_encoding
+    }
+
+    pub fn set_string_encoding(self: &mut Self, policy: StringEncodingPolicy) -> () {
+        self.string_encoding = policy;
+    }
+
+    // convert a value for field into a c string for taglib, applying the nul handling
+    // and the string encoding policy
+    fn to_c_string(self: &Self, field: TagField, value: &str) -> Result<CString, SetError> {
+        let cstr = self.nul_handling.to_c_string(field, value)?;
+        // nul handling only ever removes bytes, so the string is still valid UTF-8
+        self.string_encoding.check(field, cstr.to_str().unwrap_or(value))?;
+        Ok(cstr)
     }
 
     fn read_and_parse(c_string_pointer: *mut c_char) -> StringReadError {
@@ -122,13 +706,60 @@ impl TagLibTag {
         }
     }
 
+    // read a null terminated list of strings from the extensions, freeing the list
+    fn read_string_list(list: *mut *mut c_char) -> Result<Vec<String>, Utf8Error> {
+        if list.is_null() {
+            return Ok(Vec::new());
+        }
+        unsafe {
+            let mut strings = Vec::new();
+            let mut it = list;
+            while !(*it).is_null() {
+                strings.push(CStr::from_ptr(*it).to_str().map(|s| s.to_owned()));
+                it = it.offset(1);
+            }
+            taglib_ext_free_string_list(list);
+            strings.into_iter().collect()
+        }
+    }
+
+    // copy the bytes of a c string, exactly as taglib returned them
+    fn read_bytes(c_string_pointer: *mut c_char) -> Vec<u8> {
+        if c_string_pointer.is_null() {
+            return Vec::new();
+        }
+        unsafe {
+            let bytes = CStr::from_ptr(c_string_pointer).to_bytes().to_vec();
+            taglib_free(c_string_pointer as *mut c_void);
+            bytes
+        }
+    }
+
+    // as read_and_parse, but replacing invalid UTF-8 rather than failing
+    fn read_and_parse_lossy(c_string_pointer: *mut c_char) -> String {
+        unsafe {
+            let string = CStr::from_ptr(c_string_pointer).to_string_lossy().into_owned();
+            taglib_free(c_string_pointer as *mut c_void);
+            string
+        }
+    }
+
     pub fn title(self: &Self) -> StringReadError {
         unsafe {
             Self::read_and_parse(taglib_tag_title(self.tag))
         }
     }
 
+    /* The first artist, where there are several: see genre */
     pub fn artist(self: &Self) -> StringReadError {
+        if self.file.is_null() {
+            return self.joined_artist();
+        }
+        self.artist_opt().map(|artist| artist.unwrap_or_default())
+    }
+
+    // every artist, joined as taglib does
+    fn joined_artist(self: &Self) -> StringReadError {
         unsafe {
             Self::read_and_parse(taglib_tag_artist(self.tag))
         }
@@ -146,13 +777,176 @@ impl TagLibTag {
         }
     }
 
+    /* The first genre, where there are several. taglib itself joins them with spaces,
+     * which is only used when there's no file to read the genres from */
     pub fn genre(self: &Self) -> StringReadError {
+        if self.file.is_null() {
+            return self.joined_genre();
+        }
+        self.genre_opt().map(|genre| genre.unwrap_or_default())
+    }
+
+    // every genre, joined as taglib does
+    fn joined_genre(self: &Self) -> StringReadError {
         unsafe {
             Self::read_and_parse(taglib_tag_genre(self.tag))
         }
     }
 
+    /* The _lossy getters never fail: any invalid UTF-8 is replaced with U+FFFD */
+    pub fn title_lossy(self: &Self) -> String {
+        unsafe {
+            Self::read_and_parse_lossy(taglib_tag_title(self.tag))
+        }
+    }
+
+    pub fn artist_lossy(self: &Self) -> String {
+        self.read_property_lossy("ARTIST", taglib_tag_artist)
+    }
+
+    pub fn album_lossy(self: &Self) -> String {
+        unsafe {
+            Self::read_and_parse_lossy(taglib_tag_album(self.tag))
+        }
+    }
+
+    pub fn comment_lossy(self: &Self) -> String {
+        unsafe {
+            Self::read_and_parse_lossy(taglib_tag_comment(self.tag))
+        }
+    }
+
+    pub fn genre_lossy(self: &Self) -> String {
+        self.read_property_lossy("GENRE", taglib_tag_genre)
+    }
+
+    // the first value of a property, falling back to taglib's getter without the file
+    fn read_property_lossy(
+        self: &Self,
+        key: &str,
+        fallback: unsafe extern "C" fn(*const TagLib_Tag) -> *mut c_char,
+    ) -> String {
+        Self::read_and_parse_lossy(self.first_value_ptr(key, fallback))
+    }
+
+    // as read_property_lossy, but giving the string itself, which the caller frees. It's
+    // never null, as the getter gives an empty string when the property isn't there
+    pub(crate) fn first_value_ptr(
+        self: &Self,
+        key: &str,
+        fallback: unsafe extern "C" fn(*const TagLib_Tag) -> *mut c_char,
+    ) -> *mut c_char {
+        if !self.file.is_null() {
+            let cs_key = CString::new(key).expect("property keys don't contain nul bytes");
+            let value_ptr = unsafe { taglib_ext_property_get(self.file, cs_key.as_ptr()) };
+            if !value_ptr.is_null() {
+                return value_ptr;
+            }
+        }
+        unsafe { fallback(self.tag) }
+    }
+
+    /* The _bytes getters return the raw bytes that taglib gives us (without the trailing
+     * nul), for doing your own charset detection */
+    pub fn title_bytes(self: &Self) -> Vec<u8> {
+        unsafe {
+            Self::read_bytes(taglib_tag_title(self.tag))
+        }
+    }
+
+    // the first artist, as with artist
+    pub fn artist_bytes(self: &Self) -> Vec<u8> {
+        Self::read_bytes(self.first_value_ptr("ARTIST", taglib_tag_artist))
+    }
+
+    pub fn album_bytes(self: &Self) -> Vec<u8> {
+        unsafe {
+            Self::read_bytes(taglib_tag_album(self.tag))
+        }
+    }
+
+    pub fn comment_bytes(self: &Self) -> Vec<u8> {
+        unsafe {
+            Self::read_bytes(taglib_tag_comment(self.tag))
+        }
+    }
+
+    // the first genre, as with genre
+    pub fn genre_bytes(self: &Self) -> Vec<u8> {
+        Self::read_bytes(self.first_value_ptr("GENRE", taglib_tag_genre))
+    }
+
+    // the format of the file that the tag came from, if it has one
+    fn file_type(self: &Self) -> Option<FileType> {
+        if self.file.is_null() {
+            return None;
+        }
+        let mut type_code = 0;
+        let mut ext_type_code = 0;
+        let code = unsafe { taglib_ext_file_type(self.file, &mut type_code, &mut ext_type_code) };
+        match code {
+            1 => FileType::from_type_code(TypeCode::TagC(type_code)),
+            2 => FileType::from_type_code(TypeCode::Ext(ext_type_code)),
+            _ => None,
+        }
+    }
+
+    /* Whether the tag has nothing in it: none of the standard fields (including bpm) are
+     * set, and the file has no other properties either */
+    pub fn is_empty(self: &Self) -> bool {
+        unsafe {
+            if taglib_ext_tag_is_empty(self.tag) == 0 || self.bpm().is_some() {
+                return false;
+            }
+            self.file.is_null() || taglib_ext_properties_is_empty(self.file) != 0
+        }
+    }
+
+    // read a property from the file's property map, where a null pointer means that it isn't there
+    fn read_property(self: &Self, key: &str, fallback: fn(&Self) -> StringReadError) -> OptionalStringReadError {
+        if self.file.is_null() {
+            // without the file, the best that we can do is to treat empty as absent
+            return fallback(self).map(|s| if s.is_empty() { None } else { Some(s) });
+        }
+        let cs_key = CString::new(key).expect("property keys don't contain nul bytes");
+        unsafe {
+            let value_ptr = taglib_ext_property_get(self.file, cs_key.as_ptr());
+            if value_ptr.is_null() {
+                Ok(None)
+            } else {
+                Self::read_and_parse(value_ptr).map(Some)
+            }
+        }
+    }
+
+    /* The _opt getters distinguish between a field that isn't present at all (None)
+     * and one that is stored, but empty (Some("")), where the format allows that */
+    pub fn title_opt(self: &Self) -> OptionalStringReadError {
+        self.read_property("TITLE", Self::title)
+    }
+
+    pub fn artist_opt(self: &Self) -> OptionalStringReadError {
+        self.read_property("ARTIST", Self::joined_artist)
+    }
+
+    pub fn album_opt(self: &Self) -> OptionalStringReadError {
+        self.read_property("ALBUM", Self::album)
+    }
+
+    pub fn comment_opt(self: &Self) -> OptionalStringReadError {
+        self.read_property("COMMENT", Self::comment)
+    }
+
+    pub fn genre_opt(self: &Self) -> OptionalStringReadError {
+        self.read_property("GENRE", Self::joined_genre)
+    }
+
+    /* The year of the full date, where it has one (see date), so that the two agree,
+     * and otherwise taglib's year */
     pub fn year(self: &Self) -> Option<u32> {
+        if let Some(date) = self.full_date() {
+            return Some(date.year());
+        }
         unsafe {
             match taglib_tag_year(self.tag) {
                 0 => None,
@@ -179,60 +973,103 @@ impl TagLibTag {
         }
     }
 
-    pub fn set_title(self: &Self, title: &str) -> StringWriteError {
+    // write a string field, unless it already holds that value, in which case the tag
+    // is left untouched (and unmodified)
+    fn write_string(
+        self: &mut Self,
+        field: TagField,
+        value: &str,
+        getter: fn(&Self) -> StringReadError,
+        setter: unsafe extern "C" fn(*mut TagLib_Tag, *const c_char),
+    ) -> FieldWriteError {
+        let cstr = self.to_c_string(field, value)?;
+        if getter(self).ok().as_deref() == cstr.to_str().ok() {
+            return Ok(());
+        }
         unsafe {
-            CString::new(title).map(|cstr| {
-                let title_ptr = cstr.as_ptr();
-                taglib_tag_set_title(self.tag, title_ptr);
-            })
+            setter(self.tag, cstr.as_ptr());
         }
+        self.modified = true;
+        Ok(())
     }
 
-    pub fn set_artist(self: &Self, artist: &str) -> StringWriteError {
-        unsafe {
-            CString::new(artist).map(|cstr| {
-                let artist_ptr = cstr.as_ptr();
-                taglib_tag_set_artist(self.tag, artist_ptr);
-            })
-        }
+    pub fn set_title(self: &mut Self, title: &str) -> FieldWriteError {
+        self.write_string(TagField::Title, title, Self::title, taglib_tag_set_title)
     }
 
-    pub fn set_album(self: &Self, album: &str) -> StringWriteError {
-        unsafe {
-            CString::new(album).map(|cstr| {
-                let album_ptr = cstr.as_ptr();
-                taglib_tag_set_album(self.tag, album_ptr);
-            })
-        }
+    // replaces every artist with this one
+    pub fn set_artist(self: &mut Self, artist: &str) -> FieldWriteError {
+        self.write_string(TagField::Artist, artist, Self::joined_artist, taglib_tag_set_artist)
     }
 
-    pub fn set_comment(self: &Self, comment: &str) -> StringWriteError {
-        unsafe {
-            CString::new(comment).map(|cstr| {
-                let comment_ptr = cstr.as_ptr();
-                taglib_tag_set_comment(self.tag, comment_ptr);
-            })
-        }
+    pub fn set_album(self: &mut Self, album: &str) -> FieldWriteError {
+        self.write_string(TagField::Album, album, Self::album, taglib_tag_set_album)
     }
 
-    pub fn set_genre(self: &Self, genre: &str) -> StringWriteError {
-        unsafe {
-            CString::new(genre).map(|cstr| {
-                let genre_ptr = cstr.as_ptr();
-                taglib_tag_set_genre(self.tag, genre_ptr);
-            })
-        }
+    pub fn set_comment(self: &mut Self, comment: &str) -> FieldWriteError {
+        self.write_string(TagField::Comment, comment, Self::comment, taglib_tag_set_comment)
+    }
+
+    // replaces every genre with this one
+    pub fn set_genre(self: &mut Self, genre: &str) -> FieldWriteError {
+        self.write_string(TagField::Genre, genre, Self::joined_genre, taglib_tag_set_genre)
     }
 
-    pub fn set_year(self: &Self, year: u32) -> () { 
+    // taglib uses 0 to mean "unset" for numeric fields, so None is written as 0, and clears
+    // the field. Some(0) can't be told apart from that, so it also clears the field.
+    fn numeric_value(value: Option<u32>) -> u32 {
+        value.unwrap_or(0)
+    }
+
+    // as write_string, for numeric fields. These can't currently fail, but return a
+    // result so that all of the setters look the same
+    fn write_number(
+        self: &mut Self,
+        value: Option<u32>,
+        getter: fn(&Self) -> Option<u32>,
+        setter: unsafe extern "C" fn(*mut TagLib_Tag, c_uint),
+    ) -> FieldWriteError {
+        let value = Self::numeric_value(value);
+        if getter(self).unwrap_or(0) == value {
+            return Ok(());
+        }
         unsafe {
-            taglib_tag_set_year(self.tag, year);
+            setter(self.tag, value);
         }
+        self.modified = true;
+        Ok(())
     }
 
-    pub fn set_track(self: &Self, track: u32) -> () { 
-        unsafe {
-            taglib_tag_set_track(self.tag, track);
+    /* The numeric setters accept either a plain value, or an Option, where None clears the field */
+    pub fn set_year<V: Into<Option<u32>>>(self: &mut Self, year: V) -> FieldWriteError { 
+        self.write_number(year.into(), Self::year, taglib_tag_set_year)
+    }
+
+    /* Setting the track keeps the track total, if there is one, where taglib on its own
+     * would replace "5/12" with just "6". Clearing the track clears the total too. */
+    pub fn set_track<V: Into<Option<u32>>>(self: &mut Self, track: V) -> FieldWriteError { 
+        let track = track.into();
+        match self.track_total() {
+            Some(total) if Self::numeric_value(track) != 0 => self.set_track_with_total(track, Some(total)),
+            _ => self.write_number(track, Self::track, taglib_tag_set_track),
         }
     }
+
+    pub fn set_bpm<V: Into<Option<u32>>>(self: &mut Self, bpm: V) -> FieldWriteError { 
+        self.write_number(bpm.into(), Self::bpm, taglib_tag_set_bpm)
+    }
+
+    /* Clear all of the standard fields. Taglib removes a field (rather than storing an
+     * empty value) when it's set to an empty string or 0, so after saving the fields
+     * are gone from the file. Stops at the first field that can't be cleared */
+    pub fn clear(self: &mut Self) -> FieldWriteError {
+        self.set_title("")?;
+        self.set_artist("")?;
+        self.set_album("")?;
+        self.set_comment("")?;
+        self.set_genre("")?;
+        self.set_year(None)?;
+        self.set_track(None)?;
+        self.set_bpm(None)
+    }
 }
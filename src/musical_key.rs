@@ -0,0 +1,121 @@
+// std library imports
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+// the standard names of the keys at each position of the Camelot wheel, from 1 to 12
+const MAJOR_NAMES: [&str; 12] = ["B", "F#", "Db", "Ab", "Eb", "Bb", "F", "C", "G", "D", "A", "E"];
+const MINOR_NAMES: [&str; 12] = ["Abm", "Ebm", "Bbm", "Fm", "Cm", "Gm", "Dm", "Am", "Em", "Bm", "F#m", "Dbm"];
+
+/* A musical key, as DJ software writes to the initial key field, either in standard
+ * notation ("Am", "F#") or in Camelot notation ("8A", "2B"). Enharmonic keys are the
+ * same key, so "G#m" and "Abm" are equal. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MusicalKey {
+    // the position on the Camelot wheel, from 1 to 12
+    camelot: u8,
+    minor: bool,
+}
+
+impl MusicalKey {
+    /* The key at a position of the Camelot wheel, e.g. (8, true) is 8A, or A minor */
+    pub fn from_camelot(number: u8, minor: bool) -> Option<MusicalKey> {
+        if (1..=12).contains(&number) {
+            Some(MusicalKey { camelot: number, minor })
+        } else {
+            None
+        }
+    }
+
+    // the key with the given root, in semitones above C
+    fn from_pitch_class(pitch_class: u8, minor: bool) -> MusicalKey {
+        // a minor key shares its position with its relative major, three semitones up,
+        // and each step around the wheel is a fifth (7 semitones), with C major at 8
+        let major = if minor { (pitch_class + 3) % 12 } else { pitch_class };
+        MusicalKey { camelot: (major * 7 + 7) % 12 + 1, minor }
+    }
+
+    pub fn is_minor(self: &Self) -> bool {
+        self.minor
+    }
+
+    /* In Camelot notation, e.g. "8A" */
+    pub fn camelot(self: &Self) -> String {
+        format!("{}{}", self.camelot, if self.minor { 'A' } else { 'B' })
+    }
+
+    /* In standard notation, e.g. "Am", using the names of the Camelot wheel */
+    pub fn standard(self: &Self) -> &'static str {
+        let names = if self.minor { &MINOR_NAMES } else { &MAJOR_NAMES };
+        names[usize::from(self.camelot - 1)]
+    }
+}
+
+/* Displayed in standard notation */
+impl fmt::Display for MusicalKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.standard())
+    }
+}
+
+/* The error from parsing a MusicalKey, holding the string that wasn't recognised */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseMusicalKeyError(pub String);
+
+impl fmt::Display for ParseMusicalKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown musical key {:?}", self.0)
+    }
+}
+
+impl Error for ParseMusicalKeyError {}
+
+// parse "8A" or "12b"
+fn parse_camelot(s: &str) -> Option<MusicalKey> {
+    let (number, letter) = s.split_at(s.len().checked_sub(1)?);
+    let minor = match letter {
+        "A" | "a" => true,
+        "B" | "b" => false,
+        _ => return None,
+    };
+    MusicalKey::from_camelot(number.parse().ok()?, minor)
+}
+
+// parse "A", "F#m", "Bbmin", "E♭ major" and the like
+fn parse_standard(s: &str) -> Option<MusicalKey> {
+    let mut chars = s.chars();
+    let root = match chars.next()?.to_ascii_uppercase() {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    let rest = chars.as_str();
+    let (pitch_class, rest) = match rest.chars().next() {
+        Some('#') | Some('♯') => (root + 1, &rest[rest.chars().next()?.len_utf8()..]),
+        Some('b') | Some('♭') => (root + 11, &rest[rest.chars().next()?.len_utf8()..]),
+        _ => (root, rest),
+    };
+    let minor = match rest.trim().to_ascii_lowercase().as_str() {
+        "" | "maj" | "major" => false,
+        "m" | "min" | "minor" => true,
+        _ => return None,
+    };
+    Some(MusicalKey::from_pitch_class(pitch_class % 12, minor))
+}
+
+/* Keys are parsed from either notation */
+impl FromStr for MusicalKey {
+    type Err = ParseMusicalKeyError;
+
+    fn from_str(s: &str) -> Result<MusicalKey, ParseMusicalKeyError> {
+        let trimmed = s.trim();
+        parse_camelot(trimmed)
+            .or_else(|| parse_standard(trimmed))
+            .ok_or_else(|| ParseMusicalKeyError(s.to_owned()))
+    }
+}
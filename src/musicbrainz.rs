@@ -0,0 +1,90 @@
+// std library imports
+use std::str::Utf8Error;
+
+use {FieldWriteError, SetError, TagField, TagLibFile, TagLibTag};
+
+/* The MusicBrainz identifiers of a track, as UUID strings. taglib stores the recording
+ * id in a UFID frame in ID3v2, and the others in TXXX frames, and all of them in
+ * freeform atoms in MP4 */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct MusicBrainzIds {
+    // MUSICBRAINZ_TRACKID, which (confusingly) is the recording
+    pub recording_id: Option<String>,
+    // MUSICBRAINZ_ALBUMID
+    pub release_id: Option<String>,
+    // MUSICBRAINZ_RELEASEGROUPID
+    pub release_group_id: Option<String>,
+    // MUSICBRAINZ_ARTISTID
+    pub artist_id: Option<String>,
+    // MUSICBRAINZ_RELEASETRACKID, the track on a particular release
+    pub track_id: Option<String>,
+}
+
+impl MusicBrainzIds {
+    fn fields(self: &Self) -> [(&'static str, &Option<String>); 5] {
+        [
+            ("MUSICBRAINZ_TRACKID", &self.recording_id),
+            ("MUSICBRAINZ_ALBUMID", &self.release_id),
+            ("MUSICBRAINZ_RELEASEGROUPID", &self.release_group_id),
+            ("MUSICBRAINZ_ARTISTID", &self.artist_id),
+            ("MUSICBRAINZ_RELEASETRACKID", &self.track_id),
+        ]
+    }
+}
+
+// whether value looks like a UUID: 32 hex digits, grouped 8-4-4-4-12
+fn is_uuid(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    bytes.len() == 36 && bytes.iter().enumerate().all(|(i, &b)| match i {
+        8 | 13 | 18 | 23 => b == b'-',
+        _ => b.is_ascii_hexdigit(),
+    })
+}
+
+impl TagLibTag {
+    pub fn musicbrainz_ids(self: &Self) -> Result<MusicBrainzIds, Utf8Error> {
+        Ok(MusicBrainzIds {
+            recording_id: self.field_property("MUSICBRAINZ_TRACKID")?,
+            release_id: self.field_property("MUSICBRAINZ_ALBUMID")?,
+            release_group_id: self.field_property("MUSICBRAINZ_RELEASEGROUPID")?,
+            artist_id: self.field_property("MUSICBRAINZ_ARTISTID")?,
+            track_id: self.field_property("MUSICBRAINZ_RELEASETRACKID")?,
+        })
+    }
+
+    /* Replace all of the identifiers, removing those that are None. Every id must be
+     * shaped like a UUID, or nothing is written and the first one that isn't is
+     * returned in a Malformed error. */
+    pub fn set_musicbrainz_ids(self: &mut Self, ids: &MusicBrainzIds) -> FieldWriteError {
+        for &(_, id) in ids.fields().iter() {
+            if let Some(ref id) = *id {
+                if !is_uuid(id) {
+                    return Err(SetError::malformed(TagField::MusicBrainzIds, id));
+                }
+            }
+        }
+        self.set_musicbrainz_ids_unchecked(ids)
+    }
+
+    // as set_musicbrainz_ids, but writing the ids as they are
+    pub fn set_musicbrainz_ids_unchecked(self: &mut Self, ids: &MusicBrainzIds) -> FieldWriteError {
+        for &(key, id) in ids.fields().iter() {
+            self.set_field_property(TagField::MusicBrainzIds, key, id.as_deref())?;
+        }
+        Ok(())
+    }
+}
+
+impl TagLibFile {
+    pub fn musicbrainz_ids(self: &Self) -> Result<MusicBrainzIds, Utf8Error> {
+        self.tag.musicbrainz_ids()
+    }
+
+    pub fn set_musicbrainz_ids(self: &mut Self, ids: &MusicBrainzIds) -> FieldWriteError {
+        self.tag.set_musicbrainz_ids(ids)
+    }
+
+    pub fn set_musicbrainz_ids_unchecked(self: &mut Self, ids: &MusicBrainzIds) -> FieldWriteError {
+        self.tag.set_musicbrainz_ids_unchecked(ids)
+    }
+}
@@ -0,0 +1,36 @@
+// std library imports
+use std::ffi::CString;
+
+use {SetError, TagField};
+
+/* What to do with strings that contain nul bytes, which can't be passed to taglib */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NulHandling {
+    // refuse to write the string, returning a SetError (the default)
+    #[default]
+    Error,
+    // write everything up to the first nul
+    TruncateAtNul,
+    // write the string with all of the nuls removed
+    StripNuls,
+}
+
+impl NulHandling {
+    /* Convert a value for field into a c string, dealing with any nuls as configured */
+    pub fn to_c_string(self: Self, field: TagField, value: &str) -> Result<CString, SetError> {
+        CString::new(value).or_else(|err| match self {
+            NulHandling::Error => Err(SetError::interior_nul(field, err)),
+            NulHandling::TruncateAtNul => {
+                let position = err.nul_position();
+                let mut bytes = err.into_vec();
+                bytes.truncate(position);
+                Ok(CString::new(bytes).expect("truncated at the first nul"))
+            }
+            NulHandling::StripNuls => {
+                let mut bytes = err.into_vec();
+                bytes.retain(|&b| b != 0);
+                Ok(CString::new(bytes).expect("all nuls were removed"))
+            }
+        })
+    }
+}
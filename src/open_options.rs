@@ -0,0 +1,111 @@
+// std library imports
+#[cfg(unix)]
+use std::fs;
+use std::path::PathBuf;
+
+// taglib-sys imports
+use taglib_sys::*;
+
+use {FileError, FileType, Id3v2Encoding, StringEncodingPolicy, TagLibFile};
+
+/* How carefully taglib reads the audio properties of a file. Faster reads may give
+ * less accurate lengths and bitrates for some formats (variable bitrate mp3s, mostly) */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ReadStyle {
+    Fast,
+    #[default]
+    Average,
+    Accurate,
+}
+
+impl ReadStyle {
+    pub(crate) fn to_taglib(self: Self) -> TagLib_Ext_Read_Style {
+        match self {
+            ReadStyle::Fast => TagLib_Ext_Read_Style_TagLib_Ext_Read_Fast,
+            ReadStyle::Average => TagLib_Ext_Read_Style_TagLib_Ext_Read_Average,
+            ReadStyle::Accurate => TagLib_Ext_Read_Style_TagLib_Ext_Read_Accurate,
+        }
+    }
+}
+
+/* Settings for opening a file, which can be built once and used to open many files:
+ *
+ *     OpenOptions::new().file_type(FileType::Flac).read_only(true).open(path)
+ *
+ * The defaults are the same as TagLibFile::new: the type is guessed from the extension,
+ * the file can be saved, and audio properties are read with ReadStyle::Average */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenOptions {
+    pub(crate) file_type: Option<FileType>,
+    pub(crate) read_only: bool,
+    pub(crate) audio_properties: Option<ReadStyle>,
+    pub(crate) string_encoding: StringEncodingPolicy,
+    pub(crate) id3v2_encoding: Option<Id3v2Encoding>,
+}
+
+impl Default for OpenOptions {
+    fn default() -> OpenOptions {
+        OpenOptions::new()
+    }
+}
+
+impl OpenOptions {
+    pub fn new() -> OpenOptions {
+        OpenOptions {
+            file_type: None,
+            read_only: false,
+            audio_properties: Some(ReadStyle::default()),
+            string_encoding: StringEncodingPolicy::default(),
+            id3v2_encoding: None,
+        }
+    }
+
+    // open files as this type, or guess from the extension if None
+    pub fn file_type<T: Into<Option<FileType>>>(self: &mut Self, file_type: T) -> &mut Self {
+        self.file_type = file_type.into();
+        self
+    }
+
+    // forbid saving, see TagLibFile::open_read_only
+    pub fn read_only(self: &mut Self, read_only: bool) -> &mut Self {
+        self.read_only = read_only;
+        self
+    }
+
+    // how to read audio properties, or None to skip reading them (which is quicker, when
+    // only the tags are wanted), in which case TagLibFile::audio_properties is None
+    pub fn read_audio_properties<S: Into<Option<ReadStyle>>>(self: &mut Self, style: S) -> &mut Self {
+        self.audio_properties = style.into();
+        self
+    }
+
+    // which characters the string setters accept, see StringEncodingPolicy
+    pub fn string_encoding(self: &mut Self, policy: StringEncodingPolicy) -> &mut Self {
+        self.string_encoding = policy;
+        self
+    }
+
+    // the encoding that every text frame of an ID3v2 tag is given when the file is saved,
+    // or None to leave the frames as taglib makes them. Other tag formats are unaffected
+    pub fn id3v2_encoding<E: Into<Option<Id3v2Encoding>>>(self: &mut Self, encoding: E) -> &mut Self {
+        self.id3v2_encoding = encoding.into();
+        self
+    }
+
+    pub fn open<P: Into<PathBuf>>(self: &Self, filename: P) -> Result<TagLibFile, FileError> {
+        TagLibFile::open(filename.into(), self.clone())
+    }
+
+    // open a file from a copy of its contents, see TagLibFile::from_bytes. This needs a
+    // file_type, and fails with FileError::OpenFailure without one
+    pub fn open_bytes(self: &Self, data: &[u8]) -> Result<TagLibFile, FileError> {
+        TagLibFile::open_bytes(data, self.clone())
+    }
+
+    // open a file that's already open, see TagLibFile::from_file. As with open_bytes, this
+    // needs a file_type
+    #[cfg(unix)]
+    pub fn open_file(self: &Self, file: fs::File) -> Result<TagLibFile, FileError> {
+        TagLibFile::open_file(file, self.clone())
+    }
+}
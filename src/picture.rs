@@ -0,0 +1,577 @@
+// std library imports
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::ffi::{CStr, CString, NulError};
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::os::raw::{c_char, c_int, c_uint};
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::slice;
+use std::str::Utf8Error;
+
+// taglib-sys imports
+use taglib_sys::*;
+
+use {ConstraintViolation, TagLibFile, TagLibTag};
+
+/* What a picture shows, as the picture types of ID3v2 APIC frames, which FLAC and Ogg
+ * files share. MP4 has no types, so its pictures are all FrontCover. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PictureType {
+    Other,
+    // a 32x32 PNG
+    FileIcon,
+    OtherFileIcon,
+    FrontCover,
+    BackCover,
+    LeafletPage,
+    // e.g. the label side of a CD
+    Media,
+    LeadArtist,
+    Artist,
+    Conductor,
+    Band,
+    Composer,
+    Lyricist,
+    RecordingLocation,
+    DuringRecording,
+    DuringPerformance,
+    MovieScreenCapture,
+    ColouredFish,
+    Illustration,
+    BandLogo,
+    PublisherLogo,
+}
+
+// in the order of their ID3v2 numbers
+const PICTURE_TYPES: [PictureType; 21] = [
+    PictureType::Other,
+    PictureType::FileIcon,
+    PictureType::OtherFileIcon,
+    PictureType::FrontCover,
+    PictureType::BackCover,
+    PictureType::LeafletPage,
+    PictureType::Media,
+    PictureType::LeadArtist,
+    PictureType::Artist,
+    PictureType::Conductor,
+    PictureType::Band,
+    PictureType::Composer,
+    PictureType::Lyricist,
+    PictureType::RecordingLocation,
+    PictureType::DuringRecording,
+    PictureType::DuringPerformance,
+    PictureType::MovieScreenCapture,
+    PictureType::ColouredFish,
+    PictureType::Illustration,
+    PictureType::BandLogo,
+    PictureType::PublisherLogo,
+];
+
+impl PictureType {
+    /* The type with the given ID3v2 number, where numbers outside the list are Other */
+    pub fn from_id3v2(number: u8) -> PictureType {
+        PICTURE_TYPES.get(usize::from(number)).cloned().unwrap_or(PictureType::Other)
+    }
+
+    // the type's number in ID3v2, FLAC and Ogg files
+    pub fn id3v2_number(self: &Self) -> u8 {
+        PICTURE_TYPES.iter().position(|t| t == self).expect("every type is in the list") as u8
+    }
+}
+
+/* The size and colours of a picture, which FLAC and Ogg files store alongside it */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PictureDimensions {
+    pub width: u32,
+    pub height: u32,
+    // in bits per pixel
+    pub colour_depth: u32,
+    // the size of the palette for indexed images, otherwise 0
+    pub colours: u32,
+}
+
+/* A picture embedded in a file, e.g. the album art */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Picture {
+    pub data: Vec<u8>,
+    // e.g. "image/jpeg"
+    pub mime_type: String,
+    pub picture_type: PictureType,
+    pub description: Option<String>,
+    // only FLAC and Ogg files keep these, and they're None if the file doesn't say
+    pub dimensions: Option<PictureDimensions>,
+}
+
+/* Errors from reading or writing pictures */
+#[derive(Debug)]
+pub enum PictureError {
+    // the format of the file can't hold pictures
+    Unsupported,
+    // a picture's MIME type or description isn't valid UTF-8
+    InvalidValue(Utf8Error),
+    // the tag was created with from_ptr, so there's no file with pictures
+    NoFile,
+    // a picture's MIME type or description has a nul byte, so can't be written
+    NulByte(NulError),
+    // a picture's data is too big for taglib to write, in bytes
+    TooLarge(usize),
+    // the image file couldn't be read
+    Io(PathBuf, io::Error),
+    // the image file isn't a JPEG, PNG, GIF or WebP image
+    UnknownImageFormat(PathBuf),
+    // the image file is bigger than the limit it was given, in bytes
+    ImageTooLarge { path: PathBuf, size: u64, limit: u64 },
+    // there's no artwork to save
+    NoArtwork,
+    // the artwork's type is unknown, so it can't be given an extension
+    UnknownMimeType(String),
+    // the path's extension doesn't match the artwork, which needs the one given
+    WrongExtension(PathBuf, &'static str),
+    // the file to save the artwork to already exists
+    AlreadyExists(PathBuf),
+    // the artwork couldn't be written to the file
+    WriteFailed(PathBuf, io::Error),
+    // the file's format can't hold pictures of this MIME type, e.g. WebP in MP4
+    UnsupportedMimeType(String),
+    // the picture broke one of the constraints that it was given
+    ConstraintViolated(ConstraintViolation),
+}
+
+impl fmt::Display for PictureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PictureError::Unsupported => write!(f, "file format can't hold pictures"),
+            PictureError::InvalidValue(_) => write!(f, "picture MIME type or description is not valid UTF-8"),
+            PictureError::NoFile => write!(f, "tag has no file to read pictures from"),
+            PictureError::NulByte(_) => write!(f, "picture MIME type or description contains a nul byte"),
+            PictureError::TooLarge(size) => write!(f, "picture of {} bytes is too large to write", size),
+            PictureError::Io(path, err) => write!(f, "couldn't read image {}: {}", path.display(), err),
+            PictureError::UnknownImageFormat(path) => {
+                write!(f, "{} isn't a JPEG, PNG, GIF or WebP image", path.display())
+            }
+            PictureError::ImageTooLarge { path, size, limit } => write!(
+                f,
+                "image {} is {} bytes, more than the limit of {}",
+                path.display(),
+                size,
+                limit
+            ),
+            PictureError::NoArtwork => write!(f, "file has no artwork"),
+            PictureError::UnknownMimeType(mime_type) => write!(f, "unknown artwork type {:?}", mime_type),
+            PictureError::WrongExtension(path, extension) => {
+                write!(f, "{} should have the extension {}", path.display(), extension)
+            }
+            PictureError::AlreadyExists(path) => write!(f, "{} already exists", path.display()),
+            PictureError::WriteFailed(path, err) => write!(f, "couldn't write {}: {}", path.display(), err),
+            PictureError::UnsupportedMimeType(mime_type) => {
+                write!(f, "file format can't hold {} pictures", mime_type)
+            }
+            PictureError::ConstraintViolated(violation) => write!(f, "{}", violation),
+        }
+    }
+}
+
+impl Error for PictureError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PictureError::InvalidValue(err) => Some(err),
+            PictureError::NulByte(err) => Some(err),
+            PictureError::Io(_, err) | PictureError::WriteFailed(_, err) => Some(err),
+            PictureError::ConstraintViolated(violation) => Some(violation),
+            PictureError::Unsupported
+            | PictureError::NoFile
+            | PictureError::TooLarge(_)
+            | PictureError::UnknownImageFormat(_)
+            | PictureError::ImageTooLarge { .. }
+            | PictureError::NoArtwork
+            | PictureError::UnknownMimeType(_)
+            | PictureError::WrongExtension(..)
+            | PictureError::AlreadyExists(_)
+            | PictureError::UnsupportedMimeType(_) => None,
+        }
+    }
+}
+
+/* The MIME type of an image, from the magic bytes at its start, or None if it isn't
+ * a JPEG, PNG, GIF or WebP image */
+pub fn image_mime_type(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+// the usual extension for an image's MIME type, and the others that it can have
+fn image_extensions(mime_type: &str) -> Option<(&'static str, &'static [&'static str])> {
+    match mime_type.to_ascii_lowercase().as_str() {
+        "image/jpeg" | "image/jpg" => Some(("jpg", &["jpeg", "jpe"])),
+        "image/png" => Some(("png", &[])),
+        "image/gif" => Some(("gif", &[])),
+        "image/webp" => Some(("webp", &[])),
+        "image/bmp" => Some(("bmp", &[])),
+        _ => None,
+    }
+}
+
+// the MIME type that MP4 files store a picture's type as, which can only be one of these
+fn mp4_mime_type(mime_type: &str) -> Option<&'static str> {
+    match mime_type.to_ascii_lowercase().as_str() {
+        "image/jpeg" | "image/jpg" => Some("image/jpeg"),
+        "image/png" => Some("image/png"),
+        "image/gif" => Some("image/gif"),
+        "image/bmp" => Some("image/bmp"),
+        _ => None,
+    }
+}
+
+// copy a picture out of the extensions' array, which still has to be freed
+unsafe fn copy_picture(picture: &TagLib_Ext_Picture) -> Result<Picture, PictureError> {
+    let data = if picture.data.is_null() || picture.size == 0 {
+        Vec::new()
+    } else {
+        slice::from_raw_parts(picture.data as *const u8, picture.size as usize).to_vec()
+    };
+    let mime_type = CStr::from_ptr(picture.mime_type).to_str().map_err(PictureError::InvalidValue)?;
+    let description = CStr::from_ptr(picture.description).to_str().map_err(PictureError::InvalidValue)?;
+    let dimension = |value: c_int| u32::try_from(value).unwrap_or(0);
+    let dimensions = PictureDimensions {
+        width: dimension(picture.width),
+        height: dimension(picture.height),
+        colour_depth: dimension(picture.color_depth),
+        colours: dimension(picture.colors),
+    };
+    // MP4 pictures of unknown types have no MIME type, but may well be images anyway
+    let mime_type = match mime_type {
+        "" => image_mime_type(&data).unwrap_or(""),
+        _ => mime_type,
+    };
+    Ok(Picture {
+        data,
+        mime_type: mime_type.to_owned(),
+        picture_type: PictureType::from_id3v2(picture.type_ as u8),
+        description: Some(description).filter(|d| !d.is_empty()).map(str::to_owned),
+        dimensions: Some(dimensions).filter(|d| d.width != 0 && d.height != 0),
+    })
+}
+
+// a picture that's been checked, with everything that the extensions need
+struct PreparedPicture<'p> {
+    picture: &'p Picture,
+    mime_type: CString,
+    description: CString,
+    size: c_uint,
+}
+
+impl<'p> PreparedPicture<'p> {
+    // the picture for the extensions, which only borrows from this, so rust still frees all of it
+    fn ext_picture(self: &Self) -> TagLib_Ext_Picture {
+        let dimensions = self.picture.dimensions.unwrap_or(PictureDimensions {
+            width: 0,
+            height: 0,
+            colour_depth: 0,
+            colours: 0,
+        });
+        let dimension = |value: u32| c_int::try_from(value).unwrap_or(0);
+        TagLib_Ext_Picture {
+            mime_type: self.mime_type.as_ptr() as *mut c_char,
+            description: self.description.as_ptr() as *mut c_char,
+            type_: c_int::from(self.picture.picture_type.id3v2_number()),
+            data: self.picture.data.as_ptr() as *mut c_char,
+            size: self.size,
+            width: dimension(dimensions.width),
+            height: dimension(dimensions.height),
+            color_depth: dimension(dimensions.colour_depth),
+            colors: dimension(dimensions.colours),
+        }
+    }
+}
+
+impl TagLibTag {
+    // check that a picture can be written, and make the strings that the extensions need
+    fn prepare_picture<'p>(self: &Self, picture: &'p Picture) -> Result<PreparedPicture<'p>, PictureError> {
+        if self.file.is_null() {
+            return Err(PictureError::NoFile);
+        }
+        let size = c_uint::try_from(picture.data.len()).map_err(|_| PictureError::TooLarge(picture.data.len()))?;
+        let mime_type = if self.is_mp4() {
+            mp4_mime_type(&picture.mime_type).ok_or_else(|| PictureError::UnsupportedMimeType(picture.mime_type.clone()))?
+        } else {
+            picture.mime_type.as_str()
+        };
+        Ok(PreparedPicture {
+            picture,
+            mime_type: CString::new(mime_type).map_err(PictureError::NulByte)?,
+            description: CString::new(picture.description.as_deref().unwrap_or("")).map_err(PictureError::NulByte)?,
+            size,
+        })
+    }
+
+    // add a picture after the others, with the extensions copying everything they need
+    fn write_picture(self: &mut Self, prepared: &PreparedPicture) -> Result<(), PictureError> {
+        if unsafe { taglib_ext_add_picture(self.file, &prepared.ext_picture()) } == 0 {
+            return Err(PictureError::Unsupported);
+        }
+        self.modified = true;
+        Ok(())
+    }
+
+    // remove the pictures of a type, or all of them if there's no type
+    fn remove_pictures_of(self: &mut Self, picture_type: Option<PictureType>) -> Result<(), PictureError> {
+        if self.file.is_null() {
+            return Err(PictureError::NoFile);
+        }
+        let type_ = picture_type.map_or(-1, |t| c_int::from(t.id3v2_number()));
+        if unsafe { taglib_ext_remove_pictures(self.file, type_) } == 0 {
+            return Err(PictureError::Unsupported);
+        }
+        self.modified = true;
+        Ok(())
+    }
+
+    // replace every picture with these, in order, checking that they can all be written
+    // before any are removed. The pictures can still fail to be written, in which case
+    // the ones that were there are put back
+    pub(crate) fn set_pictures<'p, I: IntoIterator<Item = &'p Picture>>(self: &mut Self, pictures: I) -> Result<(), PictureError> {
+        let prepared = pictures.into_iter().map(|p| self.prepare_picture(p)).collect::<Result<Vec<_>, _>>()?;
+        let original = self.pictures()?;
+        self.remove_pictures_of(None)?;
+        for prepared_picture in &prepared {
+            if let Err(err) = self.write_picture(prepared_picture) {
+                let _ = self.remove_pictures_of(None);
+                for picture in &original {
+                    if let Ok(restored) = self.prepare_picture(picture) {
+                        let _ = self.write_picture(&restored);
+                    }
+                }
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    /* Every picture embedded in the file, in the order that it stores them */
+    pub fn pictures(self: &Self) -> Result<Vec<Picture>, PictureError> {
+        if self.file.is_null() {
+            return Err(PictureError::NoFile);
+        }
+        let mut pictures_ptr: *mut TagLib_Ext_Picture = ptr::null_mut();
+        let mut count: c_uint = 0;
+        unsafe {
+            if taglib_ext_pictures(self.file, &mut pictures_ptr, &mut count) == 0 {
+                return Err(PictureError::Unsupported);
+            }
+            if pictures_ptr.is_null() {
+                return Ok(Vec::new());
+            }
+            // copy everything out before freeing the array, even if a picture fails
+            let pictures = slice::from_raw_parts(pictures_ptr, count as usize)
+                .iter()
+                .map(|picture| copy_picture(picture))
+                .collect();
+            taglib_ext_free_pictures(pictures_ptr, count);
+            pictures
+        }
+    }
+
+    /* The front cover, or failing that, the first picture of any type, as many files
+     * only have one picture, with the type Other. None if there are no pictures. */
+    pub fn cover_art(self: &Self) -> Result<Option<Picture>, PictureError> {
+        let mut pictures = self.pictures()?;
+        let index = pictures.iter().position(|p| p.picture_type == PictureType::FrontCover).unwrap_or(0);
+        if pictures.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(pictures.swap_remove(index)))
+        }
+    }
+
+    /* Embeds a picture, replacing any pictures of the same type, so setting a front
+     * cover replaces the old one rather than adding another. MP4 files only hold JPEG,
+     * PNG, GIF and BMP pictures, and give every picture the FrontCover type. */
+    pub fn set_cover_art(self: &mut Self, picture: &Picture) -> Result<(), PictureError> {
+        self.replace_picture(picture.picture_type, picture)
+    }
+
+    /* Embeds a picture as the given type, in place of the first picture of that type,
+     * or after the others if there isn't one. Any other pictures of the type are
+     * removed, and the rest are kept as they were, in the same order. Nothing is
+     * changed if the picture can't be written. */
+    pub fn replace_picture(self: &mut Self, picture_type: PictureType, picture: &Picture) -> Result<(), PictureError> {
+        let replacement = if picture.picture_type == picture_type {
+            Cow::Borrowed(picture)
+        } else {
+            Cow::Owned(Picture {
+                picture_type,
+                ..picture.clone()
+            })
+        };
+        let existing = self.pictures()?;
+        if !existing.iter().any(|p| p.picture_type == picture_type) {
+            let prepared = self.prepare_picture(&replacement)?;
+            return self.write_picture(&prepared);
+        }
+        let mut replaced = false;
+        let mut pictures = Vec::with_capacity(existing.len());
+        for existing_picture in &existing {
+            if existing_picture.picture_type != picture_type {
+                pictures.push(existing_picture);
+            } else if !replaced {
+                pictures.push(&replacement);
+                replaced = true;
+            }
+        }
+        // the pictures can only be rewritten in order by removing them all
+        self.set_pictures(pictures)
+    }
+
+    /* Embeds a picture after the others, even if there's already one of its type, e.g.
+     * for the pages of a booklet. MP4 files give every picture the FrontCover type. */
+    pub fn add_picture(self: &mut Self, picture: &Picture) -> Result<(), PictureError> {
+        let prepared = self.prepare_picture(picture)?;
+        self.write_picture(&prepared)
+    }
+
+    /* Removes the embedded pictures of one type, leaving the others. As MP4 pictures
+     * are all front covers, removing that type removes all of them, and others do
+     * nothing. Files without pictures of the type are left alone. */
+    pub fn remove_picture(self: &mut Self, picture_type: PictureType) -> Result<(), PictureError> {
+        if !self.pictures()?.iter().any(|p| p.picture_type == picture_type) {
+            return Ok(());
+        }
+        self.remove_pictures_of(Some(picture_type))
+    }
+
+    /* Removes every embedded picture, whatever its type. Files without pictures are
+     * left alone, so they don't need saving afterwards. */
+    pub fn remove_pictures(self: &mut Self) -> Result<(), PictureError> {
+        if self.pictures()?.is_empty() {
+            return Ok(());
+        }
+        self.remove_pictures_of(None)
+    }
+
+    /* Writes the cover art (as chosen by cover_art) to dest, returning the path that it
+     * was written to. If dest has no extension, one is added to suit the image, and if
+     * it has the wrong one, nothing is written. Existing files are only replaced if
+     * overwrite is set. */
+    pub fn save_cover_to<P: AsRef<Path>>(self: &Self, dest: P, overwrite: bool) -> Result<PathBuf, PictureError> {
+        let cover = self.cover_art()?.ok_or(PictureError::NoArtwork)?;
+        // files sometimes have the wrong MIME type (or none), so fall back to the data
+        let (extension, others) = image_extensions(&cover.mime_type)
+            .or_else(|| image_mime_type(&cover.data).and_then(image_extensions))
+            .ok_or_else(|| PictureError::UnknownMimeType(cover.mime_type.clone()))?;
+        let mut path = dest.as_ref().to_owned();
+        match path.extension().map(|e| e.to_string_lossy().to_ascii_lowercase()) {
+            None => {
+                path.set_extension(extension);
+            }
+            Some(ref given) if given == extension || others.contains(&given.as_str()) => {}
+            Some(_) => return Err(PictureError::WrongExtension(path, extension)),
+        }
+        let mut options = fs::OpenOptions::new();
+        if overwrite {
+            options.write(true).create(true).truncate(true);
+        } else {
+            options.write(true).create_new(true);
+        }
+        let mut file = match options.open(&path) {
+            Ok(file) => file,
+            Err(ref err) if err.kind() == io::ErrorKind::AlreadyExists => return Err(PictureError::AlreadyExists(path)),
+            Err(err) => return Err(PictureError::WriteFailed(path, err)),
+        };
+        match file.write_all(&cover.data) {
+            Ok(()) => Ok(path),
+            Err(err) => Err(PictureError::WriteFailed(path, err)),
+        }
+    }
+
+    /* Embeds an image file as the front cover, replacing the old one. The file has to
+     * be a JPEG, PNG, GIF or WebP image, going by its contents rather than its name. */
+    pub fn set_cover_art_from_path<P: AsRef<Path>>(self: &mut Self, image_path: P) -> Result<(), PictureError> {
+        self.set_cover_art_from_path_with(image_path, None)
+    }
+
+    /* As set_cover_art_from_path, but refusing images bigger than max_size bytes, so
+     * that huge scans don't get copied into every track */
+    pub fn set_cover_art_from_path_with<P: AsRef<Path>>(
+        self: &mut Self,
+        image_path: P,
+        max_size: Option<u64>,
+    ) -> Result<(), PictureError> {
+        let path = image_path.as_ref();
+        let io_error = |err| PictureError::Io(path.to_owned(), err);
+        if let Some(limit) = max_size {
+            let size = fs::metadata(path).map_err(io_error)?.len();
+            if size > limit {
+                return Err(PictureError::ImageTooLarge { path: path.to_owned(), size, limit });
+            }
+        }
+        let data = fs::read(path).map_err(io_error)?;
+        let mime_type = image_mime_type(&data).ok_or_else(|| PictureError::UnknownImageFormat(path.to_owned()))?;
+        let picture = Picture {
+            data,
+            mime_type: mime_type.to_owned(),
+            picture_type: PictureType::FrontCover,
+            description: None,
+            dimensions: None,
+        };
+        self.set_cover_art(&picture)
+    }
+}
+
+impl TagLibFile {
+    pub fn pictures(self: &Self) -> Result<Vec<Picture>, PictureError> {
+        self.tag.pictures()
+    }
+
+    pub fn cover_art(self: &Self) -> Result<Option<Picture>, PictureError> {
+        self.tag.cover_art()
+    }
+
+    pub fn set_cover_art(self: &mut Self, picture: &Picture) -> Result<(), PictureError> {
+        self.tag.set_cover_art(picture)
+    }
+
+    pub fn replace_picture(self: &mut Self, picture_type: PictureType, picture: &Picture) -> Result<(), PictureError> {
+        self.tag.replace_picture(picture_type, picture)
+    }
+
+    pub fn add_picture(self: &mut Self, picture: &Picture) -> Result<(), PictureError> {
+        self.tag.add_picture(picture)
+    }
+
+    pub fn remove_picture(self: &mut Self, picture_type: PictureType) -> Result<(), PictureError> {
+        self.tag.remove_picture(picture_type)
+    }
+
+    pub fn remove_pictures(self: &mut Self) -> Result<(), PictureError> {
+        self.tag.remove_pictures()
+    }
+
+    pub fn save_cover_to<P: AsRef<Path>>(self: &Self, dest: P, overwrite: bool) -> Result<PathBuf, PictureError> {
+        self.tag.save_cover_to(dest, overwrite)
+    }
+
+    pub fn set_cover_art_from_path<P: AsRef<Path>>(self: &mut Self, image_path: P) -> Result<(), PictureError> {
+        self.tag.set_cover_art_from_path(image_path)
+    }
+
+    pub fn set_cover_art_from_path_with<P: AsRef<Path>>(
+        self: &mut Self,
+        image_path: P,
+        max_size: Option<u64>,
+    ) -> Result<(), PictureError> {
+        self.tag.set_cover_art_from_path_with(image_path, max_size)
+    }
+}
@@ -0,0 +1,146 @@
+// std library imports
+use std::error::Error;
+use std::fmt;
+
+use {Picture, PictureError, TagLibFile, TagLibTag};
+
+/* Limits that a picture has to be within to be embedded, to catch e.g. huge scans
+ * before they're copied into every track. Limits that are None aren't checked, so the
+ * default allows anything. */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct PictureConstraints<'a> {
+    // the most data the picture can have, in bytes
+    pub max_bytes: Option<u64>,
+    // the largest (width, height) in pixels, which needs the data to be a JPEG, PNG or
+    // GIF image, so that they can be measured
+    pub max_dimensions: Option<(u32, u32)>,
+    // the MIME types that the picture can have, compared ignoring case
+    pub allowed_mime: Option<&'a [&'a str]>,
+}
+
+/* The constraint that a picture broke, with what it actually was */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ConstraintViolation {
+    TooManyBytes { size: u64, limit: u64 },
+    TooLarge { dimensions: (u32, u32), limit: (u32, u32) },
+    // the data isn't an image whose dimensions can be read
+    UnknownDimensions,
+    MimeTypeNotAllowed(String),
+}
+
+impl fmt::Display for ConstraintViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConstraintViolation::TooManyBytes { size, limit } => {
+                write!(f, "picture is {} bytes, more than the limit of {}", size, limit)
+            }
+            ConstraintViolation::TooLarge { dimensions, limit } => write!(
+                f,
+                "picture is {}x{}, larger than the limit of {}x{}",
+                dimensions.0, dimensions.1, limit.0, limit.1
+            ),
+            ConstraintViolation::UnknownDimensions => write!(f, "picture's dimensions couldn't be read"),
+            ConstraintViolation::MimeTypeNotAllowed(mime_type) => {
+                write!(f, "picture's MIME type {:?} isn't allowed", mime_type)
+            }
+        }
+    }
+}
+
+impl Error for ConstraintViolation {}
+
+impl<'a> PictureConstraints<'a> {
+    /* Checks the picture against each limit, returning the first that it breaks */
+    pub fn check(self: &Self, picture: &Picture) -> Result<(), ConstraintViolation> {
+        if let Some(allowed) = self.allowed_mime {
+            if !allowed.iter().any(|m| m.eq_ignore_ascii_case(&picture.mime_type)) {
+                return Err(ConstraintViolation::MimeTypeNotAllowed(picture.mime_type.clone()));
+            }
+        }
+        let size = picture.data.len() as u64;
+        match self.max_bytes {
+            Some(limit) if size > limit => return Err(ConstraintViolation::TooManyBytes { size, limit }),
+            _ => {}
+        }
+        if let Some(limit) = self.max_dimensions {
+            let dimensions = image_dimensions(&picture.data).ok_or(ConstraintViolation::UnknownDimensions)?;
+            if dimensions.0 > limit.0 || dimensions.1 > limit.1 {
+                return Err(ConstraintViolation::TooLarge { dimensions, limit });
+            }
+        }
+        Ok(())
+    }
+}
+
+fn read_u16_be(data: &[u8], at: usize) -> Option<u32> {
+    data.get(at..at + 2).map(|b| u32::from(b[0]) << 8 | u32::from(b[1]))
+}
+
+// the dimensions in a JPEG's start of frame segment, without decoding anything else
+fn jpeg_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    let mut at = 2;
+    loop {
+        if *data.get(at)? != 0xFF {
+            return None;
+        }
+        // markers can be padded with any number of 0xFFs
+        while *data.get(at + 1)? == 0xFF {
+            at += 1;
+        }
+        let marker = data[at + 1];
+        at += 2;
+        match marker {
+            // markers without segments
+            0x01 | 0xD0..=0xD7 => continue,
+            // start of scan or end of image, before any frame
+            0xD9 | 0xDA => return None,
+            // every start of frame, except the Huffman, JPEG-LS extension and arithmetic
+            // coding tables that share their range
+            0xC0..=0xCF if marker != 0xC4 && marker != 0xC8 && marker != 0xCC => {
+                let height = read_u16_be(data, at + 3)?;
+                let width = read_u16_be(data, at + 5)?;
+                return Some((width, height));
+            }
+            _ => at += read_u16_be(data, at)? as usize,
+        }
+    }
+}
+
+/* The (width, height) of a JPEG, PNG or GIF image in pixels, read from its header, or
+ * None if the data isn't one of those */
+pub fn image_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        jpeg_dimensions(data)
+    } else if data.starts_with(b"\x89PNG\r\n\x1a\n") && data.get(12..16) == Some(b"IHDR") {
+        let dimension = |at: usize| data.get(at..at + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+        Some((dimension(16)?, dimension(20)?))
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        let dimension = |at: usize| data.get(at..at + 2).map(|b| u32::from(u16::from_le_bytes([b[0], b[1]])));
+        Some((dimension(6)?, dimension(8)?))
+    } else {
+        None
+    }
+}
+
+impl TagLibTag {
+    /* As set_cover_art, but first checking the picture against the constraints, so
+     * that nothing is changed if it breaks one */
+    pub fn set_cover_art_with(
+        self: &mut Self,
+        picture: &Picture,
+        constraints: &PictureConstraints,
+    ) -> Result<(), PictureError> {
+        constraints.check(picture).map_err(PictureError::ConstraintViolated)?;
+        self.set_cover_art(picture)
+    }
+}
+
+impl TagLibFile {
+    pub fn set_cover_art_with(
+        self: &mut Self,
+        picture: &Picture,
+        constraints: &PictureConstraints,
+    ) -> Result<(), PictureError> {
+        self.tag.set_cover_art_with(picture, constraints)
+    }
+}
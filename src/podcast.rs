@@ -0,0 +1,97 @@
+// std library imports
+use std::os::raw::c_int;
+use std::str::Utf8Error;
+
+// taglib-sys imports
+use taglib_sys::*;
+
+use {FieldWriteError, SetError, TagField, TagLibFile, TagLibTag};
+
+// the property key, ID3v2 frame and MP4 atom of each of the text fields. taglib only maps
+// the keys to the frames and atoms from 1.12, so those are used directly
+const CATEGORY: (&str, &str, &str) = ("PODCASTCATEGORY", "TCAT", "catg");
+const DESCRIPTION: (&str, &str, &str) = ("PODCASTDESC", "TDES", "desc");
+const EPISODE_ID: (&str, &str, &str) = ("PODCASTID", "TGID", "egid");
+
+/* The fields that players use to treat a file as a podcast episode, as iTunes writes
+ * them: PCST, TCAT, TDES and TGID in ID3v2, and pcst, catg, desc and egid in MP4.
+ * Other formats have no standard for these, so they're kept under taglib's property
+ * keys, PODCAST, PODCASTCATEGORY, PODCASTDESC and PODCASTID. */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct PodcastInfo {
+    // whether the file is marked as a podcast, which changes how iTunes and Apple
+    // Podcasts list it
+    pub podcast: bool,
+    pub category: Option<String>,
+    pub description: Option<String>,
+    // the episode's GUID in the podcast's feed
+    pub episode_id: Option<String>,
+}
+
+impl TagLibTag {
+    fn is_podcast(self: &Self) -> Result<bool, Utf8Error> {
+        if self.file.is_null() {
+            return Ok(false);
+        }
+        match unsafe { taglib_ext_podcast(self.file) } {
+            // the format has no mark of its own
+            -1 => Ok(self.field_property("PODCAST")?.as_deref().map(str::trim) == Some("1")),
+            podcast => Ok(podcast == 1),
+        }
+    }
+
+    fn set_podcast(self: &mut Self, podcast: bool) -> FieldWriteError {
+        if self.file.is_null() {
+            return if podcast { Err(SetError::unsupported(TagField::Podcast)) } else { Ok(()) };
+        }
+        if self.is_podcast().is_ok_and(|current| current == podcast) {
+            return Ok(());
+        }
+        if unsafe { taglib_ext_set_podcast(self.file, podcast as c_int) } != 0 {
+            self.modified = true;
+            return Ok(());
+        }
+        self.set_field_property(TagField::Podcast, "PODCAST", if podcast { Some("1") } else { None })
+    }
+
+    fn podcast_field(self: &Self, (key, id3v2_frame, mp4_atom): (&str, &str, &str)) -> Result<Option<String>, Utf8Error> {
+        self.mapped_field(key, id3v2_frame, mp4_atom)
+    }
+
+    fn set_podcast_field(self: &mut Self, (key, id3v2_frame, mp4_atom): (&str, &str, &str), value: Option<&str>) -> FieldWriteError {
+        self.set_mapped_field(TagField::Podcast, key, id3v2_frame, mp4_atom, value)
+    }
+
+    pub fn podcast_info(self: &Self) -> Result<PodcastInfo, Utf8Error> {
+        Ok(PodcastInfo {
+            podcast: self.is_podcast()?,
+            category: self.podcast_field(CATEGORY)?,
+            description: self.podcast_field(DESCRIPTION)?,
+            episode_id: self.podcast_field(EPISODE_ID)?,
+        })
+    }
+
+    /* Replace all of the podcast fields, removing those that are None, and the podcast
+     * mark if podcast is false. Every string is checked before any are written. */
+    pub fn set_podcast_info(self: &mut Self, info: &PodcastInfo) -> FieldWriteError {
+        for value in [&info.category, &info.description, &info.episode_id].iter() {
+            if let Some(ref value) = **value {
+                self.to_c_string(TagField::Podcast, value)?;
+            }
+        }
+        self.set_podcast(info.podcast)?;
+        self.set_podcast_field(CATEGORY, info.category.as_deref())?;
+        self.set_podcast_field(DESCRIPTION, info.description.as_deref())?;
+        self.set_podcast_field(EPISODE_ID, info.episode_id.as_deref())
+    }
+}
+
+impl TagLibFile {
+    pub fn podcast_info(self: &Self) -> Result<PodcastInfo, Utf8Error> {
+        self.tag.podcast_info()
+    }
+
+    pub fn set_podcast_info(self: &mut Self, info: &PodcastInfo) -> FieldWriteError {
+        self.tag.set_podcast_info(info)
+    }
+}
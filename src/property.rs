@@ -0,0 +1,331 @@
+// std library imports
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::ffi::{CString, NulError};
+use std::fmt::{self, Display};
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+use std::str::{FromStr, Utf8Error};
+
+// taglib-sys imports
+use taglib_sys::*;
+
+use {TagLibFile, TagLibTag};
+
+/* Errors from reading or writing the file's property map */
+#[derive(Debug)]
+pub enum PropertyError {
+    // the key has a nul byte in it, so it can't be passed to taglib
+    InvalidKey(NulError),
+    // a value stored in the file isn't valid UTF-8
+    InvalidValue(Utf8Error),
+    // a value to be written has a nul byte in it
+    InteriorNul(NulError),
+    // the format of the file can't store a property with this key
+    Unsupported(String),
+    // the tag was created with from_ptr, so there's no file with a property map
+    NoFile,
+}
+
+impl fmt::Display for PropertyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PropertyError::InvalidKey(_) => write!(f, "property key contains a nul byte"),
+            PropertyError::InvalidValue(_) => write!(f, "property value is not valid UTF-8"),
+            PropertyError::InteriorNul(_) => write!(f, "property value contains a nul byte"),
+            PropertyError::Unsupported(key) => write!(f, "file format can't store the property {}", key),
+            PropertyError::NoFile => write!(f, "tag has no file to read properties from"),
+        }
+    }
+}
+
+impl Error for PropertyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PropertyError::InvalidKey(err) => Some(err),
+            PropertyError::InvalidValue(err) => Some(err),
+            PropertyError::InteriorNul(err) => Some(err),
+            PropertyError::Unsupported(_) | PropertyError::NoFile => None,
+        }
+    }
+}
+
+/* Errors from reading a property as a type other than a string */
+#[derive(Debug)]
+pub enum PropertyParseError {
+    // the property couldn't be read at all
+    Read(PropertyError),
+    // the value doesn't parse, with the key that it was stored under, and the string
+    Parse { key: String, value: String },
+}
+
+impl fmt::Display for PropertyParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PropertyParseError::Read(err) => err.fmt(f),
+            PropertyParseError::Parse { key, value } => write!(f, "couldn't parse {} value {:?}", key, value),
+        }
+    }
+}
+
+impl Error for PropertyParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PropertyParseError::Read(err) => Some(err),
+            PropertyParseError::Parse { .. } => None,
+        }
+    }
+}
+
+impl From<PropertyError> for PropertyParseError {
+    fn from(err: PropertyError) -> Self {
+        PropertyParseError::Read(err)
+    }
+}
+
+impl TagLibTag {
+    // convert a key for taglib, checking that there's a file to look it up in
+    fn property_key(self: &Self, key: &str) -> Result<CString, PropertyError> {
+        let cs_key = CString::new(key).map_err(PropertyError::InvalidKey)?;
+        if self.file.is_null() {
+            return Err(PropertyError::NoFile);
+        }
+        Ok(cs_key)
+    }
+
+    /* The first value stored under key in the file's property map, or None if there
+     * isn't one. Keys are passed to taglib as they are, so use its upper case names,
+     * e.g. "ALBUMARTIST" or "DISCNUMBER". */
+    pub fn property(self: &Self, key: &str) -> Result<Option<String>, PropertyError> {
+        let cs_key = self.property_key(key)?;
+        unsafe {
+            let value_ptr = taglib_ext_property_get(self.file, cs_key.as_ptr());
+            if value_ptr.is_null() {
+                Ok(None)
+            } else {
+                Self::read_and_parse(value_ptr).map(Some).map_err(PropertyError::InvalidValue)
+            }
+        }
+    }
+
+    /* The first value stored under key, parsed as a T, e.g. property_as::<f64>("BPM").
+     * Whitespace around the value is ignored, as tags are often written with it. */
+    pub fn property_as<T: FromStr>(self: &Self, key: &str) -> Result<Option<T>, PropertyParseError> {
+        match self.property(key)? {
+            None => Ok(None),
+            Some(value) => match value.trim().parse() {
+                Ok(parsed) => Ok(Some(parsed)),
+                Err(_) => Err(PropertyParseError::Parse { key: key.to_owned(), value }),
+            },
+        }
+    }
+
+    /* Every value stored under key, in the order that the file has them, so a primary
+     * artist stays first. A key that isn't there gives an empty Vec, rather than None,
+     * as taglib doesn't distinguish between a property with no values and no property. */
+    pub fn property_values(self: &Self, key: &str) -> Result<Vec<String>, PropertyError> {
+        let cs_key = self.property_key(key)?;
+        unsafe {
+            Self::read_string_list(taglib_ext_property_values(self.file, cs_key.as_ptr())).map_err(PropertyError::InvalidValue)
+        }
+    }
+
+    fn write_property(self: &mut Self, key: &str, value: &str, append: bool) -> Result<(), PropertyError> {
+        let cs_key = self.property_key(key)?;
+        let cs_value = CString::new(value).map_err(PropertyError::InteriorNul)?;
+        self.modified = true;
+        unsafe {
+            if taglib_ext_property_set(self.file, cs_key.as_ptr(), cs_value.as_ptr(), append as c_int) == 0 {
+                return Err(PropertyError::Unsupported(key.to_owned()));
+            }
+        }
+        Ok(())
+    }
+
+    /* Replace all of the values stored under key with value. Unlike the setters for the
+     * standard fields, nuls in the value are always an error, and the string encoding
+     * policy isn't applied. */
+    pub fn set_property(self: &mut Self, key: &str, value: &str) -> Result<(), PropertyError> {
+        self.write_property(key, value, false)
+    }
+
+    // as set_property, with the value formatted with Display, e.g. set_property_display("BPM", 128.5)
+    pub fn set_property_display<T: Display>(self: &mut Self, key: &str, value: T) -> Result<(), PropertyError> {
+        self.write_property(key, &value.to_string(), false)
+    }
+
+    /* Add value after any values already stored under key, e.g. for a second artist.
+     * Formats that can only hold one value per key may join them, or refuse the key. */
+    pub fn append_property(self: &mut Self, key: &str, value: &str) -> Result<(), PropertyError> {
+        self.write_property(key, value, true)
+    }
+
+    /* Remove every value stored under key. Removing a key that isn't there isn't an
+     * error. */
+    pub fn remove_property(self: &mut Self, key: &str) -> Result<(), PropertyError> {
+        let cs_key = self.property_key(key)?;
+        self.modified = true;
+        unsafe {
+            taglib_ext_property_set(self.file, cs_key.as_ptr(), ptr::null(), 0);
+        }
+        Ok(())
+    }
+
+    /* The keys of every property in the file's property map, in sorted order. This
+     * includes the standard fields, as well as format specific ones, such as TXXX
+     * frames and Vorbis comments, under the names that taglib gives them. */
+    pub fn property_keys(self: &Self) -> Result<Vec<String>, PropertyError> {
+        if self.file.is_null() {
+            return Err(PropertyError::NoFile);
+        }
+        unsafe {
+            Self::read_string_list(taglib_ext_property_keys(self.file)).map_err(PropertyError::InvalidValue)
+        }
+    }
+
+    /* A snapshot of the whole property map, read in one go. The map is sorted by key, so
+     * that it's the same every time for the same file, and the values of each key are
+     * in the order that the file has them. */
+    pub fn properties(self: &Self) -> Result<BTreeMap<String, Vec<String>>, PropertyError> {
+        if self.file.is_null() {
+            return Err(PropertyError::NoFile);
+        }
+        let pairs = unsafe {
+            Self::read_string_list(taglib_ext_properties(self.file)).map_err(PropertyError::InvalidValue)?
+        };
+        let mut properties = BTreeMap::new();
+        let mut it = pairs.into_iter();
+        while let (Some(key), Some(value)) = (it.next(), it.next()) {
+            properties.entry(key).or_insert_with(Vec::new).push(value);
+        }
+        Ok(properties)
+    }
+
+    /* Replace the whole property map with properties, e.g. to restore a snapshot taken
+     * with properties(). Keys that aren't in the map, or that have no values, are removed.
+     * If the format can't store some of the keys, everything else is still set, and the
+     * first of them is returned as an Unsupported error. Nothing is changed if a key or
+     * value has a nul in it. */
+    pub fn set_properties(self: &mut Self, properties: &BTreeMap<String, Vec<String>>) -> Result<(), PropertyError> {
+        if self.file.is_null() {
+            return Err(PropertyError::NoFile);
+        }
+        let mut pairs = Vec::new();
+        for (key, values) in properties {
+            let cs_key = CString::new(key.as_str()).map_err(PropertyError::InvalidKey)?;
+            for value in values {
+                pairs.push(cs_key.clone());
+                pairs.push(CString::new(value.as_str()).map_err(PropertyError::InteriorNul)?);
+            }
+        }
+        let mut pointers: Vec<*const c_char> = pairs.iter().map(|s| s.as_ptr()).collect();
+        pointers.push(ptr::null());
+
+        self.modified = true;
+        let unsupported = unsafe {
+            Self::read_string_list(taglib_ext_properties_set(self.file, pointers.as_ptr())).unwrap_or_default()
+        };
+        match unsupported.into_iter().next() {
+            Some(key) => Err(PropertyError::Unsupported(key)),
+            None => Ok(()),
+        }
+    }
+
+    /* A user defined text field, by its description, e.g. "Analysis Version". That's a
+     * TXXX frame in ID3v2, a ----:com.apple.iTunes freeform atom in MP4, and a comment of
+     * that name in Vorbis comments. The property map upper cases TXXX descriptions, so
+     * a TXXX frame with the exact description is preferred, but one that only differs in
+     * case is read too. Descriptions are used as they are, colons and all. */
+    pub fn user_text(self: &Self, description: &str) -> Result<Option<String>, PropertyError> {
+        let cs_description = self.property_key(description)?;
+        unsafe {
+            let value_ptr = taglib_ext_user_text(self.file, cs_description.as_ptr());
+            if value_ptr.is_null() {
+                Ok(None)
+            } else {
+                Self::read_and_parse(value_ptr).map(Some).map_err(PropertyError::InvalidValue)
+            }
+        }
+    }
+
+    /* Replace the user defined text field with description, keeping the description's
+     * case where the format does. Vorbis comment names can only be printable ASCII, so
+     * other descriptions are Unsupported there. */
+    pub fn set_user_text(self: &mut Self, description: &str, value: &str) -> Result<(), PropertyError> {
+        let cs_description = self.property_key(description)?;
+        let cs_value = CString::new(value).map_err(PropertyError::InteriorNul)?;
+        self.modified = true;
+        unsafe {
+            if taglib_ext_set_user_text(self.file, cs_description.as_ptr(), cs_value.as_ptr()) == 0 {
+                return Err(PropertyError::Unsupported(description.to_owned()));
+            }
+        }
+        Ok(())
+    }
+
+    // removing a field that isn't there isn't an error
+    pub fn remove_user_text(self: &mut Self, description: &str) -> Result<(), PropertyError> {
+        let cs_description = self.property_key(description)?;
+        self.modified = true;
+        unsafe {
+            if taglib_ext_set_user_text(self.file, cs_description.as_ptr(), ptr::null()) == 0 {
+                return Err(PropertyError::Unsupported(description.to_owned()));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TagLibFile {
+    pub fn property(self: &Self, key: &str) -> Result<Option<String>, PropertyError> {
+        self.tag.property(key)
+    }
+
+    pub fn property_as<T: FromStr>(self: &Self, key: &str) -> Result<Option<T>, PropertyParseError> {
+        self.tag.property_as(key)
+    }
+
+    pub fn property_values(self: &Self, key: &str) -> Result<Vec<String>, PropertyError> {
+        self.tag.property_values(key)
+    }
+
+    pub fn property_keys(self: &Self) -> Result<Vec<String>, PropertyError> {
+        self.tag.property_keys()
+    }
+
+    pub fn set_property(self: &mut Self, key: &str, value: &str) -> Result<(), PropertyError> {
+        self.tag.set_property(key, value)
+    }
+
+    pub fn set_property_display<T: Display>(self: &mut Self, key: &str, value: T) -> Result<(), PropertyError> {
+        self.tag.set_property_display(key, value)
+    }
+
+    pub fn append_property(self: &mut Self, key: &str, value: &str) -> Result<(), PropertyError> {
+        self.tag.append_property(key, value)
+    }
+
+    pub fn remove_property(self: &mut Self, key: &str) -> Result<(), PropertyError> {
+        self.tag.remove_property(key)
+    }
+
+    pub fn properties(self: &Self) -> Result<BTreeMap<String, Vec<String>>, PropertyError> {
+        self.tag.properties()
+    }
+
+    pub fn set_properties(self: &mut Self, properties: &BTreeMap<String, Vec<String>>) -> Result<(), PropertyError> {
+        self.tag.set_properties(properties)
+    }
+
+    pub fn user_text(self: &Self, description: &str) -> Result<Option<String>, PropertyError> {
+        self.tag.user_text(description)
+    }
+
+    pub fn set_user_text(self: &mut Self, description: &str, value: &str) -> Result<(), PropertyError> {
+        self.tag.set_user_text(description, value)
+    }
+
+    pub fn remove_user_text(self: &mut Self, description: &str) -> Result<(), PropertyError> {
+        self.tag.remove_user_text(description)
+    }
+}
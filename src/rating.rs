@@ -0,0 +1,147 @@
+// std library imports
+use std::convert::TryFrom;
+use std::ffi::CString;
+
+// taglib-sys imports
+use taglib_sys::*;
+
+use {FieldWriteError, SetError, TagField, TagLibFile, TagLibTag};
+
+/* How players store star ratings in ID3v2 popularimeter (POPM) frames, which hold a
+ * byte from 0 to 255, along with the email of the player that wrote it. Ratings are
+ * written to a frame with the convention's email, and read from it, or from the first
+ * POPM frame if there's none with that email. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RatingConvention {
+    // stars stored as 1, 64, 128, 196 and 255, under "Windows Media Player 9 Series",
+    // which foobar2000 and MusicBee also use (the default)
+    #[default]
+    WindowsMedia,
+    // 0 to 255 on a linear scale, under "no@email"
+    MediaMonkey,
+    // stars stored as 1, 64, 128, 192 and 255, under "Banshee"
+    Banshee,
+}
+
+impl RatingConvention {
+    pub fn email(self: Self) -> &'static str {
+        match self {
+            RatingConvention::WindowsMedia => "Windows Media Player 9 Series",
+            RatingConvention::MediaMonkey => "no@email",
+            RatingConvention::Banshee => "Banshee",
+        }
+    }
+
+    // a rating from 0 to 100, as a POPM byte
+    fn to_popm(self: Self, rating: u8) -> u8 {
+        let stars = |values: [u8; 5]| match rating {
+            0 => 0,
+            // round to the nearest star, but never down to none
+            _ => values[usize::from((rating + 10) / 20).clamp(1, 5) - 1],
+        };
+        match self {
+            RatingConvention::WindowsMedia => stars([1, 64, 128, 196, 255]),
+            RatingConvention::MediaMonkey => ((u32::from(rating) * 255 + 50) / 100) as u8,
+            RatingConvention::Banshee => stars([1, 64, 128, 192, 255]),
+        }
+    }
+
+    // a POPM byte, as a rating from 0 to 100
+    fn from_popm(self: Self, popm: u8) -> u8 {
+        match self {
+            // the ranges that Windows Media Player reads as each number of stars, which
+            // cover the values that the other star based players write too
+            RatingConvention::WindowsMedia | RatingConvention::Banshee => match popm {
+                0 => 0,
+                1..=31 => 20,
+                32..=95 => 40,
+                96..=159 => 60,
+                160..=223 => 80,
+                _ => 100,
+            },
+            RatingConvention::MediaMonkey => ((u32::from(popm) * 100 + 127) / 255) as u8,
+        }
+    }
+}
+
+// a RATING value from a Vorbis comment or MP4 atom: 1 to 5 stars, or 0 to 100
+fn parse_rating(value: &str) -> Option<u8> {
+    let rating: f64 = value.trim().replace(',', ".").parse().ok()?;
+    if !(0.0..=100.0).contains(&rating) {
+        return None;
+    }
+    if rating <= 5.0 {
+        Some((rating * 20.0).round() as u8)
+    } else {
+        Some(rating.round() as u8)
+    }
+}
+
+impl TagLibTag {
+    /* The rating, from 0 to 100, where 80 is four stars, using the default convention
+     * for ID3v2 files. Elsewhere it's the RATING field, as 1 to 5 stars, or 0 to 100.
+     * None if the file isn't rated. */
+    pub fn rating(self: &Self) -> Option<u8> {
+        self.rating_with(RatingConvention::default())
+    }
+
+    pub fn rating_with(self: &Self, convention: RatingConvention) -> Option<u8> {
+        if self.file.is_null() {
+            return None;
+        }
+        if self.has_id3v2_properties() {
+            let email = CString::new(convention.email()).expect("emails don't contain nul bytes");
+            let popm = unsafe { taglib_ext_id3v2_rating(self.file, email.as_ptr()) };
+            u8::try_from(popm).ok().map(|popm| convention.from_popm(popm))
+        } else {
+            self.field_property("RATING").ok()?.and_then(|rating| parse_rating(&rating))
+        }
+    }
+
+    /* Set the rating, from 0 to 100, using the default convention for ID3v2 files, and
+     * writing RATING, from 0 to 100, everywhere else. None removes it, and a rating
+     * over 100 is Malformed. */
+    pub fn set_rating<V: Into<Option<u8>>>(self: &mut Self, rating: V) -> FieldWriteError {
+        self.set_rating_with(rating, RatingConvention::default())
+    }
+
+    pub fn set_rating_with<V: Into<Option<u8>>>(self: &mut Self, rating: V, convention: RatingConvention) -> FieldWriteError {
+        let rating = rating.into();
+        if let Some(rating) = rating.filter(|&r| r > 100) {
+            return Err(SetError::malformed(TagField::Rating, &rating.to_string()));
+        }
+        if self.file.is_null() || !self.has_id3v2_properties() {
+            let rating = rating.map(|r| r.to_string());
+            return self.set_field_property(TagField::Rating, "RATING", rating.as_deref());
+        }
+        let email = CString::new(convention.email()).expect("emails don't contain nul bytes");
+        let popm = rating.map_or(-1, |r| i32::from(convention.to_popm(r)));
+        // as with the other fields, setting what's already there isn't a change
+        if unsafe { taglib_ext_id3v2_rating(self.file, email.as_ptr()) } == popm {
+            return Ok(());
+        }
+        if unsafe { taglib_ext_id3v2_set_rating(self.file, email.as_ptr(), popm) } == 0 {
+            return Err(SetError::unsupported(TagField::Rating));
+        }
+        self.modified = true;
+        Ok(())
+    }
+}
+
+impl TagLibFile {
+    pub fn rating(self: &Self) -> Option<u8> {
+        self.tag.rating()
+    }
+
+    pub fn rating_with(self: &Self, convention: RatingConvention) -> Option<u8> {
+        self.tag.rating_with(convention)
+    }
+
+    pub fn set_rating<V: Into<Option<u8>>>(self: &mut Self, rating: V) -> FieldWriteError {
+        self.tag.set_rating(rating)
+    }
+
+    pub fn set_rating_with<V: Into<Option<u8>>>(self: &mut Self, rating: V, convention: RatingConvention) -> FieldWriteError {
+        self.tag.set_rating_with(rating, convention)
+    }
+}
@@ -0,0 +1,115 @@
+// std library imports
+use std::error::Error;
+use std::fmt;
+use std::str::Utf8Error;
+
+use {FieldWriteError, SetError, TagField, TagLibFile, TagLibTag};
+
+const TRACK_GAIN: &str = "REPLAYGAIN_TRACK_GAIN";
+const TRACK_PEAK: &str = "REPLAYGAIN_TRACK_PEAK";
+const ALBUM_GAIN: &str = "REPLAYGAIN_ALBUM_GAIN";
+const ALBUM_PEAK: &str = "REPLAYGAIN_ALBUM_PEAK";
+
+/* ReplayGain values, which files store as strings like "-7.23 dB" and "0.988553" */
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ReplayGain {
+    pub track_gain_db: Option<f64>,
+    pub track_peak: Option<f64>,
+    pub album_gain_db: Option<f64>,
+    pub album_peak: Option<f64>,
+}
+
+/* Errors from reading ReplayGain values */
+#[derive(Debug)]
+pub enum ReplayGainError {
+    // a value isn't valid UTF-8
+    Read(Utf8Error),
+    // a value isn't a number, with the key that it was stored under, and the string
+    Parse { key: String, value: String },
+}
+
+impl fmt::Display for ReplayGainError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayGainError::Read(_) => write!(f, "ReplayGain value is not valid UTF-8"),
+            ReplayGainError::Parse { key, value } => write!(f, "couldn't parse {} value {:?}", key, value),
+        }
+    }
+}
+
+impl Error for ReplayGainError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ReplayGainError::Read(err) => Some(err),
+            ReplayGainError::Parse { .. } => None,
+        }
+    }
+}
+
+// parse a gain or peak, allowing for a " dB" suffix, a leading "+", and a decimal comma
+fn parse_value(value: &str) -> Option<f64> {
+    let mut number = value.trim();
+    if number.len() >= 2 && number[number.len() - 2..].eq_ignore_ascii_case("db") {
+        number = number[..number.len() - 2].trim_end();
+    }
+    let number = number.strip_prefix('+').unwrap_or(number).replace(',', ".");
+    number.parse::<f64>().ok().filter(|n| n.is_finite())
+}
+
+impl TagLibTag {
+    fn replay_gain_value(self: &Self, key: &str) -> Result<Option<f64>, ReplayGainError> {
+        match self.field_property(key).map_err(ReplayGainError::Read)? {
+            None => Ok(None),
+            Some(value) => match parse_value(&value) {
+                Some(number) => Ok(Some(number)),
+                None => Err(ReplayGainError::Parse { key: key.to_owned(), value }),
+            },
+        }
+    }
+
+    /* The ReplayGain values of the file. A value that is there, but isn't a number, is an
+     * error, rather than None, so that it can be found and fixed. */
+    pub fn replay_gain(self: &Self) -> Result<ReplayGain, ReplayGainError> {
+        Ok(ReplayGain {
+            track_gain_db: self.replay_gain_value(TRACK_GAIN)?,
+            track_peak: self.replay_gain_value(TRACK_PEAK)?,
+            album_gain_db: self.replay_gain_value(ALBUM_GAIN)?,
+            album_peak: self.replay_gain_value(ALBUM_PEAK)?,
+        })
+    }
+
+    /* Replace all of the ReplayGain values, removing those that are None. Gains are
+     * written as "-7.23 dB", and peaks with six decimal places, as other tools expect.
+     * Infinite and NaN values are Malformed, and nothing is written. */
+    pub fn set_replay_gain(self: &mut Self, replay_gain: &ReplayGain) -> FieldWriteError {
+        let gain = |value: Option<f64>| value.map(|v| format!("{:.2} dB", v));
+        let peak = |value: Option<f64>| value.map(|v| format!("{:.6}", v));
+        let values = [
+            (TRACK_GAIN, gain(replay_gain.track_gain_db)),
+            (TRACK_PEAK, peak(replay_gain.track_peak)),
+            (ALBUM_GAIN, gain(replay_gain.album_gain_db)),
+            (ALBUM_PEAK, peak(replay_gain.album_peak)),
+        ];
+        for (_, value) in values.iter() {
+            if let Some(value) = value {
+                if parse_value(value).is_none() {
+                    return Err(SetError::malformed(TagField::ReplayGain, value));
+                }
+            }
+        }
+        for (key, value) in values.iter() {
+            self.set_field_property(TagField::ReplayGain, key, value.as_deref())?;
+        }
+        Ok(())
+    }
+}
+
+impl TagLibFile {
+    pub fn replay_gain(self: &Self) -> Result<ReplayGain, ReplayGainError> {
+        self.tag.replay_gain()
+    }
+
+    pub fn set_replay_gain(self: &mut Self, replay_gain: &ReplayGain) -> FieldWriteError {
+        self.tag.set_replay_gain(replay_gain)
+    }
+}
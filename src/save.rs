@@ -0,0 +1,336 @@
+// std library imports
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::SystemTime;
+#[cfg(test)]
+use std::cell::Cell;
+
+use backup::backup;
+use {BackupMode, FileError, Id3v2Version, PaddingPolicy, TagContainer, TagLibFile};
+
+/* Settings for how a file is saved */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SaveOptions {
+    pub(crate) overwrite: bool,
+    pub(crate) atomic: bool,
+    pub(crate) backup: Option<BackupMode>,
+    pub(crate) preserve_mtime: bool,
+    pub(crate) id3v2_padding: PaddingPolicy,
+    pub(crate) mpeg_tags: Option<(Vec<TagContainer>, OtherTags)>,
+    pub(crate) id3v2_version: Id3v2Version,
+}
+
+/* What happens to the kinds of tag that an MPEG file isn't saved with, see
+ * SaveOptions::mpeg_tags */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OtherTags {
+    // remove them from the file
+    Strip,
+    // leave them in the file as they were
+    Preserve,
+}
+
+/* Something that went wrong while saving, but after the changes were safely written, so
+ * didn't make the save fail */
+#[derive(Debug)]
+pub enum SaveWarning {
+    // the file's modification time couldn't be read, or put back, for preserve_mtime
+    MtimeNotRestored(io::Error),
+}
+
+impl SaveOptions {
+    pub fn new() -> SaveOptions {
+        SaveOptions::default()
+    }
+
+    // whether save_as can replace a file that's already at the destination. If not
+    // (the default), then saving fails with an io error of kind AlreadyExists
+    pub fn overwrite(self: &mut Self, overwrite: bool) -> &mut Self {
+        self.overwrite = overwrite;
+        self
+    }
+
+    // whether save_with saves through a copy, so that the file is never left half
+    // written. See TagLibFile::save_atomic
+    pub fn atomic(self: &mut Self, atomic: bool) -> &mut Self {
+        self.atomic = atomic;
+        self
+    }
+
+    // copy the original file before saving over it, see BackupMode. If the backup can't
+    // be made, then saving fails with an io error for the backup path, and the file isn't
+    // touched
+    pub fn backup<B: Into<Option<BackupMode>>>(self: &mut Self, mode: B) -> &mut Self {
+        self.backup = mode.into();
+        self
+    }
+
+    // put the file's modification time back to what it was before saving, so that tools
+    // that look for changed files don't see the new tags as a change. This is best effort:
+    // if the time can't be restored, the save still succeeds, with a SaveWarning
+    pub fn preserve_mtime(self: &mut Self, preserve: bool) -> &mut Self {
+        self.preserve_mtime = preserve;
+        self
+    }
+
+    // how much padding to give an ID3v2 tag, see PaddingPolicy
+    pub fn id3v2_padding(self: &mut Self, padding: PaddingPolicy) -> &mut Self {
+        self.id3v2_padding = padding;
+        self
+    }
+
+    // which kinds of tag an MPEG file is saved with, out of Id3v1, Id3v2 and Ape (the
+    // others can't be in one), and what happens to the rest. By default taglib writes
+    // every kind that has something in it, and the setters fill in ID3v1 as well as
+    // ID3v2, which mangles anything that isn't Latin-1. Other formats are saved as usual
+    pub fn mpeg_tags(self: &mut Self, tags: &[TagContainer], others: OtherTags) -> &mut Self {
+        self.mpeg_tags = Some((tags.to_vec(), others));
+        self
+    }
+
+    // the version of ID3v2 that MPEG files are saved with, see Id3v2Version
+    pub fn id3v2_version(self: &mut Self, version: Id3v2Version) -> &mut Self {
+        self.id3v2_version = version;
+        self
+    }
+}
+
+// counts the copies made by atomic saves, so that each one gets its own name
+static TEMP_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+// a name for the copy of target that an atomic save writes, in the same directory so
+// that it can be renamed over target. It's unique to the process and the save, and the
+// copy is only ever created new, so saves can't write over each other's copies
+fn temp_path(target: &Path) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(target.file_name().unwrap_or_default());
+    let count = TEMP_COUNT.fetch_add(1, Ordering::Relaxed);
+    name.push(format!(".{}.{}.taglib-rs.tmp", process::id(), count));
+    target.with_file_name(name)
+}
+
+// give the copy the same owner as the original, which (as permissions are copied along
+// with the contents) is all that renaming it over the original would lose
+#[cfg(unix)]
+fn copy_ownership(target: &Path, temp: &Path) -> io::Result<()> {
+    let original = fs::metadata(target)?;
+    let copy = fs::metadata(temp)?;
+    if (original.uid(), original.gid()) != (copy.uid(), copy.gid()) {
+        match std::os::unix::fs::chown(temp, Some(original.uid()), Some(original.gid())) {
+            // only root can give files away, so anyone else keeps the copy as their own,
+            // and gives it the original's group if they're in it
+            Err(ref err) if err.kind() == io::ErrorKind::PermissionDenied => {
+                let _ = std::os::unix::fs::chown(temp, None, Some(original.gid()));
+            }
+            result => result?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn copy_ownership(_target: &Path, _temp: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+fn set_modified(path: &Path, time: SystemTime) -> io::Result<()> {
+    fs::OpenOptions::new().write(true).open(path)?.set_modified(time)
+}
+
+// lets tests fail an atomic save after the copy is written, but before it replaces
+// the original
+#[cfg(test)]
+thread_local!(static FAIL_BEFORE_RENAME: Cell<bool> = const { Cell::new(false) });
+
+// make the saved copy durable, then move it over the original
+fn replace(target: &Path, temp: &Path) -> io::Result<()> {
+    fs::File::open(temp)?.sync_all()?;
+    copy_ownership(target, temp)?;
+    #[cfg(test)]
+    {
+        if FAIL_BEFORE_RENAME.with(|fail| fail.get()) {
+            return Err(io::Error::other("failing before the rename"));
+        }
+    }
+    fs::rename(temp, target)?;
+    // and make the rename itself durable, which needs the directory to be synced
+    #[cfg(unix)]
+    {
+        if let Some(dir) = target.parent() {
+            fs::File::open(dir)?.sync_all()?;
+        }
+    }
+    Ok(())
+}
+
+impl TagLibFile {
+    /* Save any changes to the file, without ever leaving it half written: the changes are
+     * saved to a copy in the same directory, which is synced to disk and then renamed
+     * over the original. The original's permissions and (where possible) owner are kept.
+     * If anything fails, then the copy is removed, and the original is as it was */
+    pub fn save_atomic(self: &mut Self) -> Result<(), FileError> {
+        self.save_with(SaveOptions::new().atomic(true)).map(|_| ())
+    }
+
+    /* Save, as set out in options, returning anything that went wrong without stopping
+     * the save. Files opened from memory or an open file have no path to back up, rename
+     * over, or set the time of, so are always just saved in place */
+    pub fn save_with(self: &mut Self, options: &SaveOptions) -> Result<Vec<SaveWarning>, FileError> {
+        if self.stream.is_some() {
+            return self.save_in_place(options).map(|_| Vec::new());
+        }
+        if self.options.read_only {
+            return Err(FileError::ReadOnly(self.path.clone()));
+        }
+        if !self.is_modified() {
+            return Ok(Vec::new());
+        }
+
+        // read before anything is written (including the backup, which is alongside)
+        let modified_time = if options.preserve_mtime {
+            Some(fs::metadata(&self.path).and_then(|metadata| metadata.modified()))
+        } else {
+            None
+        };
+        self.write_changes(options)?;
+
+        // after an atomic save this is the new file, which has the old one's path
+        let mut warnings = Vec::new();
+        if let Some(time) = modified_time {
+            if let Err(err) = time.and_then(|time| set_modified(&self.path, time)) {
+                warnings.push(SaveWarning::MtimeNotRestored(err));
+            }
+        }
+        Ok(warnings)
+    }
+
+    // the part of save_with that writes to disk
+    fn write_changes(self: &mut Self, options: &SaveOptions) -> Result<(), FileError> {
+        if let Some(ref mode) = options.backup {
+            backup(&self.path, mode).map_err(|(backup, err)| FileError::Io(backup, err))?;
+        }
+        if !options.atomic {
+            return self.save_in_place(options);
+        }
+
+        // replace the file that a symlink points to, rather than the symlink
+        let target = self.path.canonicalize().map_err(|err| FileError::Io(self.path.clone(), err))?;
+        let temp = temp_path(&target);
+        let mut copy_options = SaveOptions::new().id3v2_padding(options.id3v2_padding).clone();
+        copy_options.mpeg_tags = options.mpeg_tags.clone();
+        copy_options.id3v2_version = options.id3v2_version;
+        drop(self.save_as_with(&temp, &copy_options)?);
+        if let Err(err) = replace(&target, &temp) {
+            let _ = fs::remove_file(&temp);
+            return Err(FileError::Io(self.path.clone(), err));
+        }
+        // our handle is still on the original, which has been replaced, so open the new one
+        self.reload()
+    }
+
+    /* Write the file, with any unsaved changes, to dest, leaving this file as it is on
+     * disk (and still modified). The copy is opened with the same options, except that
+     * it can always be saved, and is returned. An existing file at dest is an error */
+    pub fn save_as<P: Into<PathBuf>>(self: &Self, dest: P) -> Result<TagLibFile, FileError> {
+        self.save_as_with(dest, &SaveOptions::new())
+    }
+
+    pub fn save_as_with<P: Into<PathBuf>>(
+        self: &Self,
+        dest: P,
+        options: &SaveOptions,
+    ) -> Result<TagLibFile, FileError> {
+        let dest = dest.into();
+        if let Err(err) = self.write_contents(&dest, options) {
+            return Err(FileError::Io(dest, err));
+        }
+
+        let mut open_options = self.options.clone();
+        open_options.read_only = false;
+        let mut copy = open_options.open(dest.clone())?;
+        copy.tag.nul_handling = self.tag.nul_handling;
+        if self.is_modified() {
+            // the copy has what's on disk, so bring the unsaved changes over
+            let saved = match self.copy_tags_to(&mut copy) {
+                Ok(()) => copy.save_in_place(options),
+                Err(err) => Err(FileError::CopyFailure(dest.clone(), err)),
+            };
+            if let Err(err) = saved {
+                drop(copy);
+                let _ = fs::remove_file(&dest);
+                return Err(err);
+            }
+        }
+        Ok(copy)
+    }
+
+    // copy the file's contents (as they were last saved) to a new file at dest
+    fn write_contents(self: &Self, dest: &PathBuf, options: &SaveOptions) -> io::Result<()> {
+        // overwriting the file with itself would truncate it before it's read
+        if self.stream.is_none() && self.path.canonicalize().ok() == dest.canonicalize().ok() {
+            let message = "can't save a file as itself";
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+        }
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .create_new(!options.overwrite)
+            .open(dest)?;
+        let copied = match self.stream {
+            Some(ref stream) => stream.copy_to(&mut file),
+            None => {
+                let mut source = fs::File::open(&self.path)?;
+                io::copy(&mut source, &mut file)?;
+                fs::metadata(&self.path).and_then(|metadata| file.set_permissions(metadata.permissions()))
+            }
+        };
+        if copied.is_err() {
+            drop(file);
+            let _ = fs::remove_file(dest);
+        }
+        copied
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::FAIL_BEFORE_RENAME;
+    use TagLibFile;
+
+    #[test]
+    fn failed_atomic_save_leaves_original_intact() {
+        let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join("silence.mp3");
+        // a directory of its own, so that anything left behind can be seen
+        let dir = env::temp_dir().join("taglib-rs-save_atomic_failure");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir(&dir).unwrap();
+        let path = dir.join("silence.mp3");
+        fs::copy(&fixture, &path).unwrap();
+        let before = fs::read(&path).unwrap();
+
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.set_title("Never written").unwrap();
+        FAIL_BEFORE_RENAME.with(|fail| fail.set(true));
+        let saved = file.save_atomic();
+        FAIL_BEFORE_RENAME.with(|fail| fail.set(false));
+        assert!(saved.is_err());
+        assert_eq!(fs::read(&path).unwrap(), before);
+        assert!(file.is_modified());
+        // and the copy has been removed
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,97 @@
+// std library imports
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/* Why a file couldn't be saved, as best as can be worked out after the fact: taglib
+ * only reports that saving failed, so the file is looked at again to find the cause */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveErrorKind {
+    // the file's read only flag (or permission bits) are set
+    ReadOnlyFile,
+    // the file can't be opened for writing by this process
+    PermissionDenied,
+    // the disk that the file is on is (nearly) full
+    NoSpace,
+    // nothing obviously wrong was found
+    Unknown,
+}
+
+/* An error from saving a file */
+#[derive(Debug)]
+pub struct SaveError {
+    pub path: PathBuf,
+    pub kind: SaveErrorKind,
+    // the error from trying to open the file for writing, if that failed
+    pub source: Option<io::Error>,
+}
+
+// below this much free space, a failed save is put down to the disk being full
+const LOW_SPACE: u64 = 1 << 20;
+
+#[cfg(unix)]
+fn available_space(path: &Path) -> Option<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let cs_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    unsafe {
+        let mut stats: libc::statvfs = std::mem::zeroed();
+        if libc::statvfs(cs_path.as_ptr(), &mut stats) != 0 {
+            return None;
+        }
+        Some(stats.f_bavail as u64 * stats.f_frsize as u64)
+    }
+}
+
+#[cfg(not(unix))]
+fn available_space(_path: &Path) -> Option<u64> {
+    None
+}
+
+impl SaveError {
+    /* Work out why path couldn't be saved */
+    pub fn probe(path: &Path) -> SaveError {
+        let error = |kind, source| SaveError { path: path.to_owned(), kind, source };
+        // files opened from memory, or an open file, have no path to look at
+        if path.as_os_str().is_empty() {
+            return error(SaveErrorKind::Unknown, None);
+        }
+        if let Ok(metadata) = fs::metadata(path) {
+            if metadata.permissions().readonly() {
+                return error(SaveErrorKind::ReadOnlyFile, None);
+            }
+        }
+        if let Err(err) = fs::OpenOptions::new().write(true).open(path) {
+            let kind = match err.kind() {
+                io::ErrorKind::PermissionDenied => SaveErrorKind::PermissionDenied,
+                _ => SaveErrorKind::Unknown,
+            };
+            return error(kind, Some(err));
+        }
+        match available_space(path) {
+            Some(space) if space < LOW_SPACE => error(SaveErrorKind::NoSpace, None),
+            _ => error(SaveErrorKind::Unknown, None),
+        }
+    }
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self.kind {
+            SaveErrorKind::ReadOnlyFile => "the file is read only",
+            SaveErrorKind::PermissionDenied => "permission denied",
+            SaveErrorKind::NoSpace => "the disk is full",
+            SaveErrorKind::Unknown => "unknown error",
+        };
+        write!(f, "couldn't save {}: {}", self.path.display(), reason)
+    }
+}
+
+impl Error for SaveError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source.as_ref().map(|err| err as &(dyn Error + 'static))
+    }
+}
@@ -0,0 +1,110 @@
+// std library imports
+use std::fs;
+use std::io;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use FileType;
+
+// how much of the start of a file is looked at
+const HEADER_LENGTH: usize = 512;
+
+const ASF_GUID: [u8; 16] = [
+    0x30, 0x26, 0xb2, 0x75, 0x8e, 0x66, 0xcf, 0x11, 0xa6, 0xd9, 0x00, 0xaa, 0x00, 0x62, 0xce, 0x6c,
+];
+
+// the size of an ID3v2 tag at the start of header (including its 10 byte header), if
+// there is one
+fn id3v2_length(header: &[u8]) -> Option<u64> {
+    if header.len() < 10 || !header.starts_with(b"ID3") {
+        return None;
+    }
+    // the size is "synchsafe": 7 bits in each byte
+    let size = header[6..10].iter().fold(0u64, |size, &byte| (size << 7) | u64::from(byte & 0x7f));
+    // a footer adds another 10 bytes
+    let footer = if header[5] & 0x10 != 0 { 10 } else { 0 };
+    Some(10 + size + footer)
+}
+
+// the type of an ogg stream, from the first packet in its first page
+fn sniff_ogg(header: &[u8]) -> Option<FileType> {
+    let segments = *header.get(26)? as usize;
+    let packet = header.get(27 + segments..)?;
+    if packet.starts_with(b"\x01vorbis") {
+        Some(FileType::OggVorbis)
+    } else if packet.starts_with(b"OpusHead") {
+        Some(FileType::Opus)
+    } else if packet.starts_with(b"\x7fFLAC") {
+        Some(FileType::OggFlac)
+    } else if packet.starts_with(b"Speex   ") {
+        Some(FileType::Speex)
+    } else {
+        None
+    }
+}
+
+// an mpeg audio frame header: 11 set sync bits, and a layer that isn't the reserved 00
+// (which is what ADTS AAC uses)
+fn is_mpeg_sync(header: &[u8]) -> bool {
+    header.len() >= 2 && header[0] == 0xff && header[1] & 0xe0 == 0xe0 && header[1] & 0x06 != 0
+}
+
+impl FileType {
+    /* Recognise a file's type from the magic numbers at the start of its contents.
+     * Unrecognised content gives None. An ID3v2 tag at the start is taken to mean mpeg,
+     * unless enough of the header is given to see something else after it */
+    pub fn from_header(header: &[u8]) -> Option<FileType> {
+        if let Some(length) = id3v2_length(header) {
+            let after = header.get(length as usize..).unwrap_or_default();
+            return FileType::from_header(after).or(Some(FileType::Mpeg));
+        }
+        let magic = |offset: usize, bytes: &[u8]| header.get(offset..offset + bytes.len()) == Some(bytes);
+        if magic(0, b"fLaC") {
+            Some(FileType::Flac)
+        } else if magic(0, b"OggS") {
+            sniff_ogg(header)
+        } else if magic(4, b"ftyp") {
+            Some(FileType::Mp4)
+        } else if magic(0, b"RIFF") && magic(8, b"WAVE") {
+            Some(FileType::Wav)
+        } else if magic(0, b"FORM") && (magic(8, b"AIFF") || magic(8, b"AIFC")) {
+            Some(FileType::Aiff)
+        } else if magic(0, &ASF_GUID) {
+            Some(FileType::Asf)
+        } else if magic(0, b"MPCK") || magic(0, b"MP+") {
+            Some(FileType::Mpc)
+        } else if magic(0, b"wvpk") {
+            Some(FileType::WavPack)
+        } else if magic(0, b"TTA1") {
+            Some(FileType::TrueAudio)
+        } else if is_mpeg_sync(header) {
+            Some(FileType::Mpeg)
+        } else {
+            None
+        }
+    }
+}
+
+// read up to HEADER_LENGTH bytes from the current position
+fn read_header(file: &mut fs::File) -> io::Result<Vec<u8>> {
+    let mut header = Vec::with_capacity(HEADER_LENGTH);
+    file.take(HEADER_LENGTH as u64).read_to_end(&mut header)?;
+    Ok(header)
+}
+
+/* Recognise the type of the file at path from its contents, rather than its extension,
+ * for use with TagLibFile::new_with_type. Only the start of the file is read (and the
+ * start of the audio, after an ID3v2 tag). Content that isn't recognised gives None,
+ * rather than an error */
+pub fn sniff_file_type<P: AsRef<Path>>(path: P) -> io::Result<Option<FileType>> {
+    let mut file = fs::File::open(path)?;
+    let header = read_header(&mut file)?;
+    match id3v2_length(&header) {
+        Some(length) => {
+            file.seek(SeekFrom::Start(length))?;
+            let after = read_header(&mut file)?;
+            Ok(FileType::from_header(&after).or(Some(FileType::Mpeg)))
+        }
+        None => Ok(FileType::from_header(&header)),
+    }
+}
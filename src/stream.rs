@@ -0,0 +1,115 @@
+// std library imports
+use std::fs;
+use std::io;
+#[cfg(unix)]
+use std::io::{Seek, SeekFrom};
+use std::io::Write;
+use std::os::raw::c_char;
+use std::os::raw::c_int;
+use std::os::raw::c_uint;
+#[cfg(unix)]
+use std::os::unix::io::IntoRawFd;
+use std::slice;
+
+// taglib-sys imports
+use taglib_sys::*;
+
+use file_type::TypeCode;
+use OpenOptions;
+
+// where a stream's data comes from
+enum Source {
+    // a copy of some bytes, which the stream holds
+    Bytes,
+    // a file, which the stream reads through a duplicate of its descriptor
+    #[cfg(unix)]
+    File(fs::File),
+}
+
+/* A taglib stream that a file is read from, instead of a path. Taglib doesn't free the
+ * stream along with the file, so the file must be freed before this is dropped */
+pub struct Stream {
+    stream: *mut TagLib_Ext_Stream,
+    source: Source,
+}
+
+impl Stream {
+    // a stream over a copy of data
+    pub fn from_bytes(data: &[u8]) -> Stream {
+        unsafe {
+            Stream {
+                stream: taglib_ext_stream_new_bytes(data.as_ptr() as *const c_char, data.len() as c_uint),
+                source: Source::Bytes,
+            }
+        }
+    }
+
+    // a stream over an open file. Taglib closes the descriptor that it's given, so it
+    // gets a duplicate, and the file itself is closed when the stream is dropped
+    #[cfg(unix)]
+    pub fn from_file(file: fs::File, read_only: bool) -> io::Result<Stream> {
+        let fd = file.try_clone()?.into_raw_fd();
+        unsafe {
+            Ok(Stream {
+                stream: taglib_ext_stream_new_fd(fd, read_only as c_int),
+                source: Source::File(file),
+            })
+        }
+    }
+
+    // the current contents of a stream made by from_bytes
+    fn bytes(self: &Self) -> Vec<u8> {
+        unsafe {
+            let mut length: c_uint = 0;
+            let data = taglib_ext_stream_bytes(self.stream, &mut length);
+            slice::from_raw_parts(data as *const u8, length as usize).to_vec()
+        }
+    }
+
+    /* A new stream over the same data: a copy of the current bytes, or the same file */
+    #[cfg_attr(not(unix), allow(unused_variables))]
+    pub fn try_clone(self: &Self, read_only: bool) -> io::Result<Stream> {
+        match self.source {
+            Source::Bytes => Ok(Stream::from_bytes(&self.bytes())),
+            #[cfg(unix)]
+            Source::File(ref file) => Stream::from_file(file.try_clone()?, read_only),
+        }
+    }
+
+    /* Write everything in the stream (including anything saved to it) to dest */
+    pub fn copy_to(self: &Self, dest: &mut fs::File) -> io::Result<()> {
+        match self.source {
+            Source::Bytes => dest.write_all(&self.bytes()),
+            #[cfg(unix)]
+            Source::File(ref file) => {
+                let mut file = file.try_clone()?;
+                file.seek(SeekFrom::Start(0))?;
+                io::copy(&mut file, dest).map(|_| ())
+            }
+        }
+    }
+
+    /* Open the stream as the type given in the options, which is needed, as there's no
+     * extension to guess it from. Gives null if there isn't one */
+    pub unsafe fn open(self: &Self, options: &OpenOptions) -> *mut TagLib_File {
+        let read_properties = options.audio_properties.is_some() as c_int;
+        let style = options.audio_properties.unwrap_or_default().to_taglib();
+        match options.file_type.map(|file_type| file_type.type_code()) {
+            None => std::ptr::null_mut(),
+            Some(TypeCode::TagC(code)) => {
+                taglib_ext_file_new_type_stream(self.stream, code, read_properties, style)
+            }
+            Some(TypeCode::Ext(code)) => {
+                taglib_ext_file_new_ext_type_stream(self.stream, code, read_properties, style)
+            }
+        }
+    }
+}
+
+impl Drop for Stream {
+    fn drop(&mut self) {
+        unsafe {
+            taglib_ext_stream_free(self.stream);
+        }
+    }
+}
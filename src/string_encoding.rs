@@ -0,0 +1,31 @@
+use {SetError, TagField};
+
+/* Which characters the string setters accept.
+ *
+ * Strings always cross into taglib as UTF-8: the crate sets taglib's global
+ * taglib_set_strings_unicode flag on every open, and never changes it, so there's no
+ * global state to race on. What is written to disk is decided by the format (and, for
+ * ID3v2, by OpenOptions::id3v2_encoding): taglib uses Latin-1 where the text fits, and
+ * moves to a unicode encoding where it doesn't. Latin1 makes sure that it always fits,
+ * for players that can't render anything else, by refusing any other characters */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum StringEncodingPolicy {
+    // accept any string (the default)
+    #[default]
+    Unicode,
+    // only accept characters up to U+00FF, returning a SetError for anything else
+    Latin1,
+}
+
+impl StringEncodingPolicy {
+    /* Check that a value for field can be written under this policy */
+    pub fn check(self: Self, field: TagField, value: &str) -> Result<(), SetError> {
+        match self {
+            StringEncodingPolicy::Unicode => Ok(()),
+            StringEncodingPolicy::Latin1 => match value.chars().find(|&c| c as u32 > 0xff) {
+                Some(c) => Err(SetError::not_latin1(field, c)),
+                None => Ok(()),
+            },
+        }
+    }
+}
@@ -0,0 +1,115 @@
+// taglib-sys imports
+use taglib_sys::*;
+
+use {FileError, FileType, SaveError, TagLibFile, TagLibTag};
+
+/* The kinds of tag that a file can hold, several of which can be in one file, e.g. an
+ * MP3 with an old ID3v1 tag left behind after its ID3v2 tag */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TagContainer {
+    Id3v1,
+    Id3v2,
+    Ape,
+    // a Vorbis comment, in FLAC and Ogg files
+    Xiph,
+    Mp4Ilst,
+    Asf,
+    // the INFO chunk of a WAV file
+    RiffInfo,
+}
+
+// each container and its bit in the extensions' masks, in the order that they're listed
+const CONTAINERS: [(TagContainer, TagLib_Ext_Tag_Container); 7] = [
+    (TagContainer::Id3v1, TagLib_Ext_Tag_Container_TagLib_Ext_Tag_ID3v1),
+    (TagContainer::Id3v2, TagLib_Ext_Tag_Container_TagLib_Ext_Tag_ID3v2),
+    (TagContainer::Ape, TagLib_Ext_Tag_Container_TagLib_Ext_Tag_APE),
+    (TagContainer::Xiph, TagLib_Ext_Tag_Container_TagLib_Ext_Tag_Xiph),
+    (TagContainer::Mp4Ilst, TagLib_Ext_Tag_Container_TagLib_Ext_Tag_MP4),
+    (TagContainer::Asf, TagLib_Ext_Tag_Container_TagLib_Ext_Tag_ASF),
+    (TagContainer::RiffInfo, TagLib_Ext_Tag_Container_TagLib_Ext_Tag_RIFF_Info),
+];
+
+impl TagContainer {
+    // the mask with the containers' bits set
+    pub(crate) fn to_mask(containers: &[TagContainer]) -> TagLib_Ext_Tag_Container {
+        CONTAINERS.iter().filter(|(c, _)| containers.contains(c)).fold(0, |mask, (_, bit)| mask | bit)
+    }
+
+    // the containers whose bits are set in a mask
+    pub(crate) fn from_mask(mask: TagLib_Ext_Tag_Container) -> Vec<TagContainer> {
+        CONTAINERS.iter().filter(|(_, bit)| mask & bit != 0).map(|(c, _)| *c).collect()
+    }
+}
+
+impl TagLibTag {
+    /* The kinds of tag in the file, as it was when it was opened or last saved, so
+     * tags that have been created since aren't included. Xiph comments, MP4 tags and
+     * ASF tags only count if there's something in them, as they can be left empty */
+    pub fn tag_containers(self: &Self) -> Vec<TagContainer> {
+        if self.file.is_null() {
+            return Vec::new();
+        }
+        TagContainer::from_mask(unsafe { taglib_ext_tag_containers(self.file) })
+    }
+
+    /* The vendor string of the file's Xiph comment, naming the library that wrote the
+     * file, e.g. "Lavf58.29.100" or "reference libFLAC 1.3.2 20170101". None for
+     * formats without Xiph comments. It can't be set, as it should always be true */
+    pub fn vendor_string(self: &Self) -> Option<String> {
+        if self.file.is_null() {
+            return None;
+        }
+        let vendor_ptr = unsafe { taglib_ext_vendor_string(self.file) };
+        if vendor_ptr.is_null() {
+            None
+        } else {
+            Some(Self::read_and_parse_lossy(vendor_ptr))
+        }
+    }
+}
+
+impl TagLibFile {
+    pub fn tag_containers(self: &Self) -> Vec<TagContainer> {
+        self.tag.tag_containers()
+    }
+
+    pub fn vendor_string(self: &Self) -> Option<String> {
+        self.tag.vendor_string()
+    }
+
+    /* Removes every tag and picture from the file, of whatever kind, and saves it. The
+     * audio isn't touched. Afterwards the file is reopened, with empty tags and no
+     * containers. If the save fails, the file is still reopened, as it is on disk */
+    pub fn strip_all_tags(self: &mut Self) -> Result<(), FileError> {
+        if self.options.read_only {
+            return Err(FileError::ReadOnly(self.path.clone()));
+        }
+        if unsafe { taglib_ext_strip_tags(self.file_handle) } == 0 {
+            return Err(FileError::SaveFailure(SaveError::probe(&self.path)));
+        }
+        self.tag.modified = true;
+        let saved = self.save();
+        // taglib has freed some of the tags, so setters would silently do nothing, whether
+        // or not the save worked
+        let reloaded = self.reload();
+        saved.and(reloaded)
+    }
+
+    /* Removes any ID3v1 and APE tags from an MPEG file, which old tools often left
+     * alongside its ID3v2 tag, keeping the ID3v2 tag exactly as it is. They're removed
+     * from the file straight away, and any unsaved changes are kept, still unsaved.
+     * Files of other formats are an Unsupported error */
+    pub fn strip_legacy_tags(self: &mut Self) -> Result<(), FileError> {
+        if self.tag.file_type() != Some(FileType::Mpeg) {
+            return Err(FileError::Unsupported(self.path.clone()));
+        }
+        if self.options.read_only {
+            return Err(FileError::ReadOnly(self.path.clone()));
+        }
+        let mask = TagContainer::to_mask(&[TagContainer::Id3v1, TagContainer::Ape]);
+        if unsafe { taglib_ext_mpeg_strip(self.file_handle, mask) } == 0 {
+            return Err(FileError::SaveFailure(SaveError::probe(&self.path)));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,361 @@
+// std library imports
+use std::error::Error;
+use std::fmt;
+use std::ffi::NulError;
+use std::str::Utf8Error;
+
+use TagLibTag;
+
+/* The fields of a tag: the standard ones, and those that are kept in the file's
+ * property map */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TagField {
+    Title,
+    Artist,
+    Album,
+    Comment,
+    Genre,
+    Year,
+    Track,
+    Bpm,
+    AlbumArtist,
+    Composer,
+    Disc,
+    Compilation,
+    Lyrics,
+    MusicBrainzIds,
+    ReplayGain,
+    ArtistSort,
+    AlbumArtistSort,
+    AlbumSort,
+    TitleSort,
+    Grouping,
+    InitialKey,
+    Isrc,
+    Label,
+    Copyright,
+    EncodedBy,
+    EncoderSettings,
+    Language,
+    OriginalDate,
+    Rating,
+    Mood,
+    Conductor,
+    Remixer,
+    Podcast,
+    Work,
+    MovementName,
+    Movement,
+}
+
+/* A field that couldn't be read while taking a snapshot */
+#[derive(Debug)]
+pub struct FieldReadError {
+    pub field: TagField,
+    pub error: Utf8Error,
+}
+
+impl fmt::Display for FieldReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?} is not valid UTF-8", self.field)
+    }
+}
+
+impl Error for FieldReadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/* Why a field couldn't be set */
+#[derive(Debug)]
+pub enum SetErrorKind {
+    InteriorNul(NulError),
+    // the string encoding policy is Latin1, and the value has a character outside it
+    NotLatin1(char),
+    // the file's format can't store the field, or the tag has no file (see from_ptr)
+    Unsupported,
+    // the value isn't in the format that the field requires
+    Malformed(String),
+}
+
+/* An error from setting a field, saying which field it was */
+#[derive(Debug)]
+pub struct SetError {
+    pub field: TagField,
+    pub kind: SetErrorKind,
+}
+
+impl SetError {
+    pub fn interior_nul(field: TagField, error: NulError) -> SetError {
+        SetError { field, kind: SetErrorKind::InteriorNul(error) }
+    }
+
+    pub fn not_latin1(field: TagField, character: char) -> SetError {
+        SetError { field, kind: SetErrorKind::NotLatin1(character) }
+    }
+
+    pub fn unsupported(field: TagField) -> SetError {
+        SetError { field, kind: SetErrorKind::Unsupported }
+    }
+
+    pub fn malformed(field: TagField, value: &str) -> SetError {
+        SetError { field, kind: SetErrorKind::Malformed(value.to_owned()) }
+    }
+}
+
+impl fmt::Display for SetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            SetErrorKind::InteriorNul(_) => write!(f, "{:?} contains a nul byte", self.field),
+            SetErrorKind::NotLatin1(character) => {
+                write!(f, "{:?} contains {:?}, which isn't Latin-1", self.field, character)
+            }
+            SetErrorKind::Unsupported => write!(f, "file format can't store {:?}", self.field),
+            SetErrorKind::Malformed(value) => write!(f, "{:?} isn't a valid {:?} value", value, self.field),
+        }
+    }
+}
+
+impl Error for SetError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            SetErrorKind::InteriorNul(err) => Some(err),
+            SetErrorKind::NotLatin1(_) | SetErrorKind::Unsupported | SetErrorKind::Malformed(_) => None,
+        }
+    }
+}
+
+/* An owned snapshot of the standard fields of a tag, which can outlive the file.
+ *
+ * Two snapshots are equal when every field is equal. Strings are compared exactly:
+ * case sensitively, and without any unicode normalisation, so "é" written as one
+ * code point isn't equal to "e" followed by a combining accent. A field that is None
+ * isn't equal to one that is Some(""). */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct TagData {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub comment: Option<String>,
+    pub genre: Option<String>,
+    pub year: Option<u32>,
+    pub track: Option<u32>,
+    pub bpm: Option<u32>,
+    pub composer: Option<String>,
+    pub conductor: Option<String>,
+    pub remixer: Option<String>,
+    pub artist_sort: Option<String>,
+    pub album_artist_sort: Option<String>,
+    pub album_sort: Option<String>,
+    pub title_sort: Option<String>,
+    pub label: Option<String>,
+}
+
+impl TagLibTag {
+    /* Read every field into a TagData. Fields that aren't valid UTF-8 are left
+     * as None in the snapshot, and reported alongside it, rather than failing the lot */
+    pub fn to_tag_data(self: &Self) -> (TagData, Vec<FieldReadError>) {
+        let mut errors = Vec::new();
+        let mut read = |field: TagField, value: Result<Option<String>, Utf8Error>| {
+            value.unwrap_or_else(|error| {
+                errors.push(FieldReadError { field, error });
+                None
+            })
+        };
+        let data = TagData {
+            title: read(TagField::Title, self.title_opt()),
+            artist: read(TagField::Artist, self.artist_opt()),
+            album: read(TagField::Album, self.album_opt()),
+            comment: read(TagField::Comment, self.comment_opt()),
+            genre: read(TagField::Genre, self.genre_opt()),
+            year: self.year(),
+            track: self.track(),
+            bpm: self.bpm(),
+            composer: read(TagField::Composer, self.composer()),
+            conductor: read(TagField::Conductor, self.conductor()),
+            remixer: read(TagField::Remixer, self.remixer()),
+            artist_sort: read(TagField::ArtistSort, self.artist_sort()),
+            album_artist_sort: read(TagField::AlbumArtistSort, self.album_artist_sort()),
+            album_sort: read(TagField::AlbumSort, self.album_sort()),
+            title_sort: read(TagField::TitleSort, self.title_sort()),
+            label: read(TagField::Label, self.label()),
+        };
+        (data, errors)
+    }
+
+    // check that every string in the data can be written, before we write any of them,
+    // so that a bad field doesn't leave the tag half-applied
+    fn validate(self: &Self, data: &TagData) -> Result<(), SetError> {
+        let fields = [
+            (TagField::Title, &data.title),
+            (TagField::Artist, &data.artist),
+            (TagField::Album, &data.album),
+            (TagField::Comment, &data.comment),
+            (TagField::Genre, &data.genre),
+        ];
+        for &(field, value) in fields.iter() {
+            if let Some(ref value) = *value {
+                self.to_c_string(field, value)?;
+            }
+        }
+        // these are kept in the file's property map, so a tag without a file can't hold them
+        let properties = [
+            (TagField::Composer, &data.composer),
+            (TagField::Conductor, &data.conductor),
+            (TagField::Remixer, &data.remixer),
+            (TagField::ArtistSort, &data.artist_sort),
+            (TagField::AlbumArtistSort, &data.album_artist_sort),
+            (TagField::AlbumSort, &data.album_sort),
+            (TagField::TitleSort, &data.title_sort),
+            (TagField::Label, &data.label),
+        ];
+        for &(field, value) in properties.iter() {
+            if let Some(ref value) = *value {
+                self.to_c_string(field, value)?;
+                if self.file.is_null() && !value.is_empty() {
+                    return Err(SetError::unsupported(field));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /* Write every field of data that is Some, leaving the others as they are */
+    pub fn apply(self: &mut Self, data: &TagData) -> Result<(), SetError> {
+        self.validate(data)?;
+        if let Some(ref title) = data.title {
+            self.set_title(title)?;
+        }
+        if let Some(ref artist) = data.artist {
+            self.set_artist(artist)?;
+        }
+        if let Some(ref album) = data.album {
+            self.set_album(album)?;
+        }
+        if let Some(ref comment) = data.comment {
+            self.set_comment(comment)?;
+        }
+        if let Some(ref genre) = data.genre {
+            self.set_genre(genre)?;
+        }
+        if data.year.is_some() {
+            self.set_year(data.year)?;
+        }
+        if data.track.is_some() {
+            self.set_track(data.track)?;
+        }
+        if data.bpm.is_some() {
+            self.set_bpm(data.bpm)?;
+        }
+        if let Some(ref composer) = data.composer {
+            self.set_composer(composer.as_str())?;
+        }
+        if let Some(ref conductor) = data.conductor {
+            self.set_conductor(conductor.as_str())?;
+        }
+        if let Some(ref remixer) = data.remixer {
+            self.set_remixer(remixer.as_str())?;
+        }
+        if let Some(ref artist_sort) = data.artist_sort {
+            self.set_artist_sort(artist_sort.as_str())?;
+        }
+        if let Some(ref album_artist_sort) = data.album_artist_sort {
+            self.set_album_artist_sort(album_artist_sort.as_str())?;
+        }
+        if let Some(ref album_sort) = data.album_sort {
+            self.set_album_sort(album_sort.as_str())?;
+        }
+        if let Some(ref title_sort) = data.title_sort {
+            self.set_title_sort(title_sort.as_str())?;
+        }
+        if let Some(ref label) = data.label {
+            self.set_label(label.as_str())?;
+        }
+        Ok(())
+    }
+
+    /* Make the tag match data exactly: as apply, but fields that are None are cleared */
+    pub fn apply_strict(self: &mut Self, data: &TagData) -> Result<(), SetError> {
+        self.validate(data)?;
+        self.set_title(data.title.as_ref().map_or("", |s| s.as_str()))?;
+        self.set_artist(data.artist.as_ref().map_or("", |s| s.as_str()))?;
+        self.set_album(data.album.as_ref().map_or("", |s| s.as_str()))?;
+        self.set_comment(data.comment.as_ref().map_or("", |s| s.as_str()))?;
+        self.set_genre(data.genre.as_ref().map_or("", |s| s.as_str()))?;
+        self.set_year(data.year)?;
+        self.set_track(data.track)?;
+        self.set_bpm(data.bpm)?;
+        self.set_composer(data.composer.as_deref())?;
+        self.set_conductor(data.conductor.as_deref())?;
+        self.set_remixer(data.remixer.as_deref())?;
+        self.set_artist_sort(data.artist_sort.as_deref())?;
+        self.set_album_artist_sort(data.album_artist_sort.as_deref())?;
+        self.set_album_sort(data.album_sort.as_deref())?;
+        self.set_title_sort(data.title_sort.as_deref())?;
+        self.set_label(data.label.as_deref())?;
+        Ok(())
+    }
+}
+
+impl TagData {
+    // take the fields that are kept in the file's property map from other
+    pub(crate) fn set_property_fields(self: &mut Self, other: &TagData) {
+        self.composer = other.composer.clone();
+        self.conductor = other.conductor.clone();
+        self.remixer = other.remixer.clone();
+        self.artist_sort = other.artist_sort.clone();
+        self.album_artist_sort = other.album_artist_sort.clone();
+        self.album_sort = other.album_sort.clone();
+        self.title_sort = other.title_sort.clone();
+        self.label = other.label.clone();
+    }
+}
+
+/* A one line summary, like "Artist – Title (Album, 2003) [#5]", leaving out any
+ * fields that are missing */
+impl fmt::Display for TagData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut parts = Vec::new();
+        match (&self.artist, &self.title) {
+            (Some(artist), Some(title)) => parts.push(format!("{} – {}", artist, title)),
+            (Some(artist), None) => parts.push(artist.clone()),
+            (None, Some(title)) => parts.push(title.clone()),
+            (None, None) => (),
+        }
+        match (&self.album, self.year) {
+            (Some(album), Some(year)) => parts.push(format!("({}, {})", album, year)),
+            (Some(album), None) => parts.push(format!("({})", album)),
+            (None, Some(year)) => parts.push(format!("({})", year)),
+            (None, None) => (),
+        }
+        if let Some(track) = self.track {
+            parts.push(format!("[#{}]", track));
+        }
+        if parts.is_empty() {
+            write!(f, "(untagged)")
+        } else {
+            write!(f, "{}", parts.join(" "))
+        }
+    }
+}
+
+/* Tags are displayed in the same way as a TagData. Invalid UTF-8 is replaced, rather
+ * than causing an error */
+impl fmt::Display for TagLibTag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let present = |s: String| if s.is_empty() { None } else { Some(s) };
+        let data = TagData {
+            title: present(self.title_lossy()),
+            artist: present(self.artist_lossy()),
+            album: present(self.album_lossy()),
+            comment: present(self.comment_lossy()),
+            genre: present(self.genre_lossy()),
+            year: self.year(),
+            track: self.track(),
+            bpm: self.bpm(),
+            ..TagData::default()
+        };
+        data.fmt(f)
+    }
+}
@@ -0,0 +1,170 @@
+// std library imports
+use std::error::Error;
+use std::fmt;
+
+use {FileError, SetError, TagData, TagField, TagLibFile};
+
+/* Errors from saving a TagEdit */
+#[derive(Debug)]
+pub enum EditError {
+    InvalidField(SetError),
+    Save(FileError),
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EditError::InvalidField(err) => err.fmt(f),
+            EditError::Save(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for EditError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            EditError::InvalidField(err) => Some(err),
+            EditError::Save(err) => Some(err),
+        }
+    }
+}
+
+/* A batch of changes to a file's tag, which are only written (and the file saved)
+ * when save() is called. Dropping the edit without saving discards the changes. */
+#[must_use = "a TagEdit does nothing unless it is saved"]
+#[derive(Debug)]
+pub struct TagEdit<'file> {
+    file: &'file mut TagLibFile,
+    changes: TagData,
+    changed: bool,
+    // the first field that failed validation, which is reported by save()
+    error: Option<SetError>,
+}
+
+impl<'file> TagEdit<'file> {
+    pub fn new(file: &'file mut TagLibFile) -> TagEdit<'file> {
+        TagEdit {
+            file,
+            changes: TagData::default(),
+            changed: false,
+            error: None,
+        }
+    }
+
+    // check for nul bytes (and the encoding policy) now, rather than when we come to
+    // write the value
+    fn string(mut self: Self, field: TagField, value: &str) -> Self {
+        self.changed = true;
+        match self.file.tag().to_c_string(field, value) {
+            Ok(cstr) => {
+                // nul handling only ever removes bytes, so the string is still valid UTF-8
+                let value = cstr.into_string().ok();
+                match field {
+                    TagField::Title => self.changes.title = value,
+                    TagField::Artist => self.changes.artist = value,
+                    TagField::Album => self.changes.album = value,
+                    TagField::Comment => self.changes.comment = value,
+                    TagField::Genre => self.changes.genre = value,
+                    TagField::Composer => self.changes.composer = value,
+                    TagField::Conductor => self.changes.conductor = value,
+                    TagField::Remixer => self.changes.remixer = value,
+                    TagField::ArtistSort => self.changes.artist_sort = value,
+                    TagField::AlbumArtistSort => self.changes.album_artist_sort = value,
+                    TagField::AlbumSort => self.changes.album_sort = value,
+                    TagField::TitleSort => self.changes.title_sort = value,
+                    TagField::Label => self.changes.label = value,
+                    _ => unreachable!("not a string field"),
+                }
+            }
+            Err(error) => {
+                if self.error.is_none() {
+                    self.error = Some(error);
+                }
+            }
+        }
+        self
+    }
+
+    pub fn title(self: Self, title: &str) -> Self {
+        self.string(TagField::Title, title)
+    }
+
+    pub fn artist(self: Self, artist: &str) -> Self {
+        self.string(TagField::Artist, artist)
+    }
+
+    pub fn album(self: Self, album: &str) -> Self {
+        self.string(TagField::Album, album)
+    }
+
+    pub fn comment(self: Self, comment: &str) -> Self {
+        self.string(TagField::Comment, comment)
+    }
+
+    pub fn genre(self: Self, genre: &str) -> Self {
+        self.string(TagField::Genre, genre)
+    }
+
+    pub fn composer(self: Self, composer: &str) -> Self {
+        self.string(TagField::Composer, composer)
+    }
+
+    pub fn conductor(self: Self, conductor: &str) -> Self {
+        self.string(TagField::Conductor, conductor)
+    }
+
+    pub fn remixer(self: Self, remixer: &str) -> Self {
+        self.string(TagField::Remixer, remixer)
+    }
+
+    pub fn artist_sort(self: Self, artist_sort: &str) -> Self {
+        self.string(TagField::ArtistSort, artist_sort)
+    }
+
+    pub fn album_artist_sort(self: Self, album_artist_sort: &str) -> Self {
+        self.string(TagField::AlbumArtistSort, album_artist_sort)
+    }
+
+    pub fn album_sort(self: Self, album_sort: &str) -> Self {
+        self.string(TagField::AlbumSort, album_sort)
+    }
+
+    pub fn title_sort(self: Self, title_sort: &str) -> Self {
+        self.string(TagField::TitleSort, title_sort)
+    }
+
+    pub fn label(self: Self, label: &str) -> Self {
+        self.string(TagField::Label, label)
+    }
+
+    pub fn year(mut self: Self, year: u32) -> Self {
+        self.changed = true;
+        self.changes.year = Some(year);
+        self
+    }
+
+    pub fn track(mut self: Self, track: u32) -> Self {
+        self.changed = true;
+        self.changes.track = Some(track);
+        self
+    }
+
+    pub fn bpm(mut self: Self, bpm: u32) -> Self {
+        self.changed = true;
+        self.changes.bpm = Some(bpm);
+        self
+    }
+
+    /* Write all of the changes and save the file, or report the first field that was
+     * invalid, in which case nothing is written. Does nothing if no fields were set. */
+    pub fn save(self: Self) -> Result<(), EditError> {
+        if let Some(error) = self.error {
+            return Err(EditError::InvalidField(error));
+        }
+        if !self.changed {
+            return Ok(());
+        }
+        self.file.tag_mut().apply(&self.changes).map_err(EditError::InvalidField)?;
+        self.file.save().map_err(EditError::Save)
+    }
+}
@@ -0,0 +1,94 @@
+// std library imports
+use std::ffi::CStr;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::os::raw::c_char;
+use std::os::raw::c_void;
+use std::str;
+use std::str::Utf8Error;
+
+// taglib-sys imports
+use taglib_sys::*;
+
+use TagLibTag;
+
+/* A string borrowed straight from taglib, without copying it. The string is freed
+ * when the guard is dropped, and the guard borrows the tag, so it can't outlive
+ * the file. It holds a raw pointer, so it isn't Send or Sync. */
+pub struct TagStr<'tag> {
+    c_string_pointer: *mut c_char,
+    tag: PhantomData<&'tag TagLibTag>,
+}
+
+impl<'tag> TagStr<'tag> {
+    // take ownership of a string returned by taglib, checking that it's valid UTF-8
+    pub(crate) fn from_ptr(c_string_pointer: *mut c_char) -> Result<TagStr<'tag>, Utf8Error> {
+        let guard = TagStr {
+            c_string_pointer,
+            tag: PhantomData,
+        };
+        // if this fails then the guard is dropped, which frees the string
+        str::from_utf8(guard.to_bytes())?;
+        Ok(guard)
+    }
+
+    /* The bytes of the string, without the trailing nul */
+    pub fn to_bytes(self: &Self) -> &[u8] {
+        unsafe { CStr::from_ptr(self.c_string_pointer).to_bytes() }
+    }
+}
+
+impl<'tag> Deref for TagStr<'tag> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        // checked in from_ptr
+        unsafe { str::from_utf8_unchecked(self.to_bytes()) }
+    }
+}
+
+impl<'tag> fmt::Debug for TagStr<'tag> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<'tag> fmt::Display for TagStr<'tag> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<'tag> Drop for TagStr<'tag> {
+    fn drop(&mut self) {
+        unsafe {
+            taglib_free(self.c_string_pointer as *mut c_void);
+        }
+    }
+}
+
+impl TagLibTag {
+    /* The _ref getters borrow the string from taglib, rather than copying it */
+    pub fn title_ref(self: &Self) -> Result<TagStr<'_>, Utf8Error> {
+        unsafe { TagStr::from_ptr(taglib_tag_title(self.tag)) }
+    }
+
+    // the first artist, as with artist
+    pub fn artist_ref(self: &Self) -> Result<TagStr<'_>, Utf8Error> {
+        TagStr::from_ptr(self.first_value_ptr("ARTIST", taglib_tag_artist))
+    }
+
+    pub fn album_ref(self: &Self) -> Result<TagStr<'_>, Utf8Error> {
+        unsafe { TagStr::from_ptr(taglib_tag_album(self.tag)) }
+    }
+
+    pub fn comment_ref(self: &Self) -> Result<TagStr<'_>, Utf8Error> {
+        unsafe { TagStr::from_ptr(taglib_tag_comment(self.tag)) }
+    }
+
+    // the first genre, as with genre
+    pub fn genre_ref(self: &Self) -> Result<TagStr<'_>, Utf8Error> {
+        TagStr::from_ptr(self.first_value_ptr("GENRE", taglib_tag_genre))
+    }
+}
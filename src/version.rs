@@ -0,0 +1,43 @@
+// std library imports
+use std::fmt;
+use std::os::raw::c_int;
+
+// taglib-sys imports
+use taglib_sys::*;
+
+/* A taglib version number. Versions compare in the obvious order, and features that
+ * depend on the version have methods like supports_millisecond_lengths, rather than
+ * needing numbers to be compared by hand */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl Version {
+    pub fn new(major: u32, minor: u32, patch: u32) -> Version {
+        Version { major, minor, patch }
+    }
+
+    // whether audio properties report lengths in milliseconds (taglib 1.10), rather than
+    // whole seconds. See AudioProperties::precision
+    pub fn supports_millisecond_lengths(self: &Self) -> bool {
+        *self >= Version::new(1, 10, 0)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/* The version of taglib that the crate is linked against */
+pub fn version() -> Version {
+    let (mut major, mut minor, mut patch): (c_int, c_int, c_int) = (0, 0, 0);
+    unsafe {
+        taglib_ext_version(&mut major, &mut minor, &mut patch);
+    }
+    Version::new(major as u32, minor as u32, patch as u32)
+}
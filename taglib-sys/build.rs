@@ -1,4 +1,5 @@
 extern crate bindgen;
+extern crate cc;
 extern crate cmake;
 
 use cmake::Config;
@@ -14,6 +15,19 @@ fn main() {
         .define("CMAKE_C_FLAGS","-fPIC -Wall -O3")
         .build();
 
+    // compile our extensions to the c interface against the headers that cmake installed.
+    // this has to happen before we emit the taglib link flags, so that the static linker
+    // sees the extensions before the libraries that they depend on
+    let include_dir = dst.join("include").join("taglib");
+    cc::Build::new()
+        .cpp(true)
+        .file("shim/tag_ext.cpp")
+        .include(&include_dir)
+        .flag_if_supported("-Wall")
+        .compile("tag_ext");
+    println!("cargo:rerun-if-changed=shim/tag_ext.h");
+    println!("cargo:rerun-if-changed=shim/tag_ext.cpp");
+
     // tell cargo to look for it when trying to link
     println!("cargo:rustc-link-search={}/lib", dst.display());
     // link libc++, as the static linker doesn't, and we need it for the tag internals
@@ -22,11 +36,13 @@ fn main() {
     println!("cargo:rustc-flags=-l dylib=stdc++");
     println!("cargo:rustc-flags=-l tag_c -l tag -l z");    
 
-    // create bindings for the static c library
-    let header = dst.join("include").join("taglib").join("tag_c.h");
+    // create bindings for the static c library, and our extensions to it
+    let header = include_dir.join("tag_c.h");
     let bindings = bindgen::Builder::default()
         // use the header from the dst, where cmake has writen the headers
         .header(header.to_str().unwrap())
+        .header("shim/tag_ext.h")
+        .clang_arg(format!("-I{}", include_dir.display()))
         // Finish the builder and generate the bindings.
         .generate()
         // Unwrap the Result and panic on failure.
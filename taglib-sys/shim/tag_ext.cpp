@@ -0,0 +1,1240 @@
+#include "tag_ext.h"
+
+#include <stdlib.h>
+#include <string.h>
+
+#include <aifffile.h>
+#include <apefile.h>
+#include <asffile.h>
+#include <attachedpictureframe.h>
+#include <audioproperties.h>
+#include <commentsframe.h>
+#include <fileref.h>
+#include <flacfile.h>
+#include <flacpicture.h>
+#include <id3v2tag.h>
+#include <mp4coverart.h>
+#include <mp4file.h>
+#include <mpcfile.h>
+#include <mpegfile.h>
+#include <oggflacfile.h>
+#include <opusfile.h>
+#include <podcastframe.h>
+#include <popularimeterframe.h>
+#include <speexfile.h>
+#include <textidentificationframe.h>
+#include <trueaudiofile.h>
+#include <unsynchronizedlyricsframe.h>
+#include <vorbisfile.h>
+#include <wavfile.h>
+#include <wavpackfile.h>
+#include <tag.h>
+#include <tbytevectorstream.h>
+#include <tfile.h>
+#include <tfilestream.h>
+#include <tpropertymap.h>
+#include <taglib.h>
+
+using namespace TagLib;
+
+namespace
+{
+  char **toStringList(const StringList &strings)
+  {
+    char **list = static_cast<char **>(::malloc(sizeof(char *) * (strings.size() + 1)));
+    char **out = list;
+    for(StringList::ConstIterator it = strings.begin(); it != strings.end(); ++it)
+      *out++ = ::strdup(it->toCString(true));
+    *out = NULL;
+    return list;
+  }
+
+  // the ID3v2 tag of any format that can have one, or NULL. If create is true, the tag
+  // is created for formats where it's the main tag
+  ID3v2::Tag *id3v2Tag(File *file, bool create = false)
+  {
+    if(MPEG::File *mpeg = dynamic_cast<MPEG::File *>(file))
+      return mpeg->ID3v2Tag(create);
+    if(FLAC::File *flac = dynamic_cast<FLAC::File *>(file))
+      return flac->ID3v2Tag(false);
+    if(TrueAudio::File *tta = dynamic_cast<TrueAudio::File *>(file))
+      return tta->ID3v2Tag(create);
+    if(RIFF::WAV::File *wav = dynamic_cast<RIFF::WAV::File *>(file))
+      return wav->ID3v2Tag();
+    if(RIFF::AIFF::File *aiff = dynamic_cast<RIFF::AIFF::File *>(file))
+      return aiff->tag();
+    return NULL;
+  }
+
+  // the Xiph comment of any format where it's the main tag, or NULL. If create is true,
+  // FLAC files are given one if they don't have one
+  Ogg::XiphComment *xiphComment(File *file, bool create = false)
+  {
+    if(FLAC::File *flac = dynamic_cast<FLAC::File *>(file))
+      return flac->xiphComment(create);
+    if(Ogg::Vorbis::File *vorbis = dynamic_cast<Ogg::Vorbis::File *>(file))
+      return vorbis->tag();
+    if(Ogg::FLAC::File *oggFlac = dynamic_cast<Ogg::FLAC::File *>(file))
+      return oggFlac->tag();
+    if(Ogg::Speex::File *speex = dynamic_cast<Ogg::Speex::File *>(file))
+      return speex->tag();
+    if(Ogg::Opus::File *opus = dynamic_cast<Ogg::Opus::File *>(file))
+      return opus->tag();
+    return NULL;
+  }
+
+  // the MP4 tag of an MP4 file, or NULL
+  MP4::Tag *mp4Tag(File *file)
+  {
+    if(MP4::File *mp4 = dynamic_cast<MP4::File *>(file))
+      return mp4->tag();
+    return NULL;
+  }
+
+  // the name of the MP4 freeform atom that other taggers use for a user text field
+  String freeformKey(const String &description)
+  {
+    return "----:com.apple.iTunes:" + description;
+  }
+
+  // the TXXX frames with the given description. The property map upper cases
+  // descriptions, so those written through it are matched case insensitively, but a
+  // frame with exactly the description is always first
+  ID3v2::FrameList userTextFrames(ID3v2::Tag *tag, const String &description)
+  {
+    ID3v2::FrameList matching;
+    const ID3v2::FrameList &frames = tag->frameList("TXXX");
+    for(ID3v2::FrameList::ConstIterator it = frames.begin(); it != frames.end(); ++it) {
+      ID3v2::UserTextIdentificationFrame *frame = dynamic_cast<ID3v2::UserTextIdentificationFrame *>(*it);
+      if(!frame)
+        continue;
+      if(frame->description() == description)
+        matching.prepend(frame);
+      else if(frame->description().upper() == description.upper())
+        matching.append(frame);
+    }
+    return matching;
+  }
+
+  // the ID3v2.4 frames that taglib drops when saving ID3v2.3, and the property keys that
+  // they're kept under instead, as TXXX frames
+  const char *const droppedFrames[][2] = {
+    { "TMOO", "MOOD" },
+    { "TSOA", "ALBUMSORT" },
+    { "TSOP", "ARTISTSORT" },
+    { "TSOT", "TITLESORT" },
+    { "TSST", "DISCSUBTITLE" },
+    { "TDRL", "RELEASEDATE" }
+  };
+
+  // get a tag ready to be saved as ID3v2.3, keeping what taglib would otherwise lose
+  void downgradeFrames(ID3v2::Tag *tag)
+  {
+    for(size_t i = 0; i < sizeof(droppedFrames) / sizeof(droppedFrames[0]); ++i) {
+      const ID3v2::FrameList frames = tag->frameList(droppedFrames[i][0]);
+      if(frames.isEmpty())
+        continue;
+      StringList values;
+      for(ID3v2::FrameList::ConstIterator it = frames.begin(); it != frames.end(); ++it) {
+        if(const ID3v2::TextIdentificationFrame *frame = dynamic_cast<const ID3v2::TextIdentificationFrame *>(*it))
+          values.append(frame->fieldList());
+        tag->removeFrame(*it);
+      }
+      const ID3v2::FrameList existing = userTextFrames(tag, droppedFrames[i][1]);
+      for(ID3v2::FrameList::ConstIterator it = existing.begin(); it != existing.end(); ++it)
+        tag->removeFrame(*it);
+      ID3v2::UserTextIdentificationFrame *frame =
+        new ID3v2::UserTextIdentificationFrame(ID3v2::FrameFactory::instance()->defaultTextEncoding());
+      frame->setDescription(droppedFrames[i][1]);
+      frame->setText(values);
+      tag->addFrame(frame);
+    }
+
+    // ID3v2.3 has no way to separate values, so join them as most tools do
+    const ID3v2::FrameList frames = tag->frameList();
+    for(ID3v2::FrameList::ConstIterator it = frames.begin(); it != frames.end(); ++it) {
+      if(ID3v2::UserTextIdentificationFrame *user = dynamic_cast<ID3v2::UserTextIdentificationFrame *>(*it)) {
+        // the first field is the description
+        StringList values = user->fieldList();
+        values.erase(values.begin());
+        if(values.size() > 1)
+          user->setText(values.toString("/"));
+      }
+      else if(ID3v2::TextIdentificationFrame *text = dynamic_cast<ID3v2::TextIdentificationFrame *>(*it)) {
+        // taglib converts the pairs in these to an IPLS frame itself
+        if((*it)->frameID() == "TIPL" || (*it)->frameID() == "TMCL")
+          continue;
+        if(text->fieldList().size() > 1)
+          text->setText(text->fieldList().toString("/"));
+      }
+    }
+  }
+
+  // a picture as the formats store them, before it's copied out for the caller
+  struct PictureData
+  {
+    String mimeType;
+    String description;
+    int type;
+    ByteVector data;
+    int width;
+    int height;
+    int colorDepth;
+    int colors;
+  };
+
+  PictureData pictureData(const String &mimeType, const String &description, int type,
+                          const ByteVector &data)
+  {
+    PictureData picture = { mimeType, description, type, data, 0, 0, 0, 0 };
+    return picture;
+  }
+
+  PictureData pictureData(const FLAC::Picture *flac)
+  {
+    PictureData picture = { flac->mimeType(), flac->description(), flac->type(), flac->data(),
+                            flac->width(), flac->height(), flac->colorDepth(), flac->numColors() };
+    return picture;
+  }
+
+  String coverArtMimeType(MP4::CoverArt::Format format)
+  {
+    switch(format) {
+    case MP4::CoverArt::JPEG:
+      return "image/jpeg";
+    case MP4::CoverArt::PNG:
+      return "image/png";
+    case MP4::CoverArt::GIF:
+      return "image/gif";
+    case MP4::CoverArt::BMP:
+      return "image/bmp";
+    default:
+      return String();
+    }
+  }
+
+  MP4::CoverArt::Format coverArtFormat(const String &mimeType)
+  {
+    if(mimeType == "image/jpeg" || mimeType == "image/jpg")
+      return MP4::CoverArt::JPEG;
+    if(mimeType == "image/png")
+      return MP4::CoverArt::PNG;
+    if(mimeType == "image/gif")
+      return MP4::CoverArt::GIF;
+    if(mimeType == "image/bmp")
+      return MP4::CoverArt::BMP;
+    return MP4::CoverArt::Unknown;
+  }
+
+  // the formats whose pictures are in an ID3v2 tag
+  bool hasID3v2Pictures(File *file)
+  {
+    return dynamic_cast<MPEG::File *>(file) || dynamic_cast<TrueAudio::File *>(file) ||
+           dynamic_cast<RIFF::WAV::File *>(file) || dynamic_cast<RIFF::AIFF::File *>(file);
+  }
+
+  FLAC::Picture *flacPicture(const TagLib_Ext_Picture *picture)
+  {
+    FLAC::Picture *flac = new FLAC::Picture();
+    flac->setType(static_cast<FLAC::Picture::Type>(picture->type));
+    flac->setMimeType(String(picture->mime_type, String::UTF8));
+    flac->setDescription(String(picture->description, String::UTF8));
+    flac->setData(ByteVector(picture->data, picture->size));
+    flac->setWidth(picture->width);
+    flac->setHeight(picture->height);
+    flac->setColorDepth(picture->color_depth);
+    flac->setNumColors(picture->colors);
+    return flac;
+  }
+
+  // whether a xiph comment has any fields or pictures, as taglib writes empty ones
+  bool hasContent(Ogg::XiphComment *xiph)
+  {
+    return xiph && (xiph->fieldCount() > 0 || !xiph->pictureList().isEmpty());
+  }
+
+  // the MPEG::File::TagTypes for a mask of TagLib_Ext_Tag_Container bits
+  int mpegTagTypes(unsigned int tags)
+  {
+    int types = MPEG::File::NoTags;
+    if(tags & TagLib_Ext_Tag_ID3v1)
+      types |= MPEG::File::ID3v1;
+    if(tags & TagLib_Ext_Tag_ID3v2)
+      types |= MPEG::File::ID3v2;
+    if(tags & TagLib_Ext_Tag_APE)
+      types |= MPEG::File::APE;
+    return types;
+  }
+
+  // every picture in the file, in the order that it has them. Returns false if the
+  // format can't have pictures
+  bool readPictures(File *file, List<PictureData> &pictures)
+  {
+    if(FLAC::File *flac = dynamic_cast<FLAC::File *>(file)) {
+      const List<FLAC::Picture *> list = flac->pictureList();
+      for(List<FLAC::Picture *>::ConstIterator it = list.begin(); it != list.end(); ++it)
+        pictures.append(pictureData(*it));
+      return true;
+    }
+    if(Ogg::XiphComment *xiph = xiphComment(file)) {
+      const List<FLAC::Picture *> list = xiph->pictureList();
+      for(List<FLAC::Picture *>::ConstIterator it = list.begin(); it != list.end(); ++it)
+        pictures.append(pictureData(*it));
+      return true;
+    }
+    if(MP4::Tag *tag = mp4Tag(file)) {
+      // covr has no types or descriptions, so every picture is a front cover
+      if(tag->contains("covr")) {
+        const MP4::CoverArtList list = tag->item("covr").toCoverArtList();
+        for(MP4::CoverArtList::ConstIterator it = list.begin(); it != list.end(); ++it)
+          pictures.append(pictureData(coverArtMimeType(it->format()), String(),
+                                      ID3v2::AttachedPictureFrame::FrontCover, it->data()));
+      }
+      return true;
+    }
+    if(hasID3v2Pictures(file)) {
+      if(ID3v2::Tag *tag = id3v2Tag(file)) {
+        const ID3v2::FrameList &frames = tag->frameList("APIC");
+        for(ID3v2::FrameList::ConstIterator it = frames.begin(); it != frames.end(); ++it) {
+          if(const ID3v2::AttachedPictureFrame *frame = dynamic_cast<const ID3v2::AttachedPictureFrame *>(*it))
+            pictures.append(pictureData(frame->mimeType(), frame->description(), frame->type(), frame->picture()));
+        }
+      }
+      return true;
+    }
+    return false;
+  }
+
+  // the popularimeter frame with the given email, or unless exact is true, the first one
+  ID3v2::PopularimeterFrame *popularimeter(ID3v2::Tag *tag, const String &email, bool exact)
+  {
+    const ID3v2::FrameList &frames = tag->frameList("POPM");
+    ID3v2::PopularimeterFrame *first = NULL;
+    for(ID3v2::FrameList::ConstIterator it = frames.begin(); it != frames.end(); ++it) {
+      ID3v2::PopularimeterFrame *frame = dynamic_cast<ID3v2::PopularimeterFrame *>(*it);
+      if(!frame)
+        continue;
+      if(frame->email() == email)
+        return frame;
+      if(!first)
+        first = frame;
+    }
+    return exact ? NULL : first;
+  }
+
+  AudioProperties::ReadStyle toReadStyle(TagLib_Ext_Read_Style style)
+  {
+    switch(style) {
+    case TagLib_Ext_Read_Fast:
+      return AudioProperties::Fast;
+    case TagLib_Ext_Read_Accurate:
+      return AudioProperties::Accurate;
+    default:
+      return AudioProperties::Average;
+    }
+  }
+
+  // tag_c's handles are just pointers to the File, so these can be used with it.
+  // FileName takes both narrow and (on windows) wide strings
+  TagLib_File *newFile(FileName filename, BOOL readProperties, TagLib_Ext_Read_Style style)
+  {
+    File *file = FileRef::create(filename, readProperties, toReadStyle(style));
+    return reinterpret_cast<TagLib_File *>(file);
+  }
+
+  // the stream constructors of formats that can have ID3v2 tags take a frame factory
+  // as well, and the filename ones accept it
+  template <class T, class Source>
+  File *newWithFrameFactory(Source source, BOOL readProperties, AudioProperties::ReadStyle style)
+  {
+    return new T(source, ID3v2::FrameFactory::instance(), readProperties, style);
+  }
+
+  // a Source is either a FileName or an IOStream *, which the constructors of every
+  // format accept
+  template <class Source>
+  TagLib_File *newType(Source source, TagLib_File_Type type,
+                       BOOL readProperties, TagLib_Ext_Read_Style style)
+  {
+    // the same as taglib_file_new_type() in tag_c
+    const AudioProperties::ReadStyle s = toReadStyle(style);
+    File *file = NULL;
+    switch(type) {
+    case TagLib_File_MPEG:
+      file = newWithFrameFactory<MPEG::File>(source, readProperties, s);
+      break;
+    case TagLib_File_OggVorbis:
+      file = new Ogg::Vorbis::File(source, readProperties, s);
+      break;
+    case TagLib_File_FLAC:
+      file = newWithFrameFactory<FLAC::File>(source, readProperties, s);
+      break;
+    case TagLib_File_MPC:
+      file = new MPC::File(source, readProperties, s);
+      break;
+    case TagLib_File_OggFlac:
+      file = new Ogg::FLAC::File(source, readProperties, s);
+      break;
+    case TagLib_File_WavPack:
+      file = new WavPack::File(source, readProperties, s);
+      break;
+    case TagLib_File_Speex:
+      file = new Ogg::Speex::File(source, readProperties, s);
+      break;
+    case TagLib_File_TrueAudio:
+      file = new TrueAudio::File(source, readProperties, s);
+      break;
+    case TagLib_File_MP4:
+      file = new MP4::File(source, readProperties, s);
+      break;
+    case TagLib_File_ASF:
+      file = new ASF::File(source, readProperties, s);
+      break;
+    }
+    return reinterpret_cast<TagLib_File *>(file);
+  }
+
+  template <class Source>
+  TagLib_File *newExtType(Source source, TagLib_Ext_File_Type type,
+                          BOOL readProperties, TagLib_Ext_Read_Style style)
+  {
+    const AudioProperties::ReadStyle s = toReadStyle(style);
+    switch(type) {
+    case TagLib_Ext_File_Opus:
+      return reinterpret_cast<TagLib_File *>(new Ogg::Opus::File(source, readProperties, s));
+    case TagLib_Ext_File_WAV:
+      return reinterpret_cast<TagLib_File *>(new RIFF::WAV::File(source, readProperties, s));
+    case TagLib_Ext_File_AIFF:
+      return reinterpret_cast<TagLib_File *>(new RIFF::AIFF::File(source, readProperties, s));
+    }
+    return NULL;
+  }
+}
+
+TagLib_File *taglib_ext_file_new(const char *filename, BOOL readProperties,
+                                 TagLib_Ext_Read_Style style)
+{
+  return newFile(filename, readProperties, style);
+}
+
+TagLib_File *taglib_ext_file_new_type(const char *filename, TagLib_File_Type type,
+                                      BOOL readProperties, TagLib_Ext_Read_Style style)
+{
+  return newType<FileName>(filename, type, readProperties, style);
+}
+
+TagLib_File *taglib_ext_file_new_ext_type(const char *filename, TagLib_Ext_File_Type type,
+                                          BOOL readProperties, TagLib_Ext_Read_Style style)
+{
+  return newExtType<FileName>(filename, type, readProperties, style);
+}
+
+int taglib_ext_file_type(const TagLib_File *file, TagLib_File_Type *type,
+                         TagLib_Ext_File_Type *extType)
+{
+  const File *f = reinterpret_cast<const File *>(file);
+  // the ogg formats are all separate subclasses of Ogg::File, so the order doesn't matter
+  if(dynamic_cast<const MPEG::File *>(f))
+    *type = TagLib_File_MPEG;
+  else if(dynamic_cast<const Ogg::Vorbis::File *>(f))
+    *type = TagLib_File_OggVorbis;
+  else if(dynamic_cast<const FLAC::File *>(f))
+    *type = TagLib_File_FLAC;
+  else if(dynamic_cast<const MPC::File *>(f))
+    *type = TagLib_File_MPC;
+  else if(dynamic_cast<const Ogg::FLAC::File *>(f))
+    *type = TagLib_File_OggFlac;
+  else if(dynamic_cast<const WavPack::File *>(f))
+    *type = TagLib_File_WavPack;
+  else if(dynamic_cast<const Ogg::Speex::File *>(f))
+    *type = TagLib_File_Speex;
+  else if(dynamic_cast<const TrueAudio::File *>(f))
+    *type = TagLib_File_TrueAudio;
+  else if(dynamic_cast<const MP4::File *>(f))
+    *type = TagLib_File_MP4;
+  else if(dynamic_cast<const ASF::File *>(f))
+    *type = TagLib_File_ASF;
+  else if(dynamic_cast<const Ogg::Opus::File *>(f)) {
+    *extType = TagLib_Ext_File_Opus;
+    return 2;
+  }
+  else if(dynamic_cast<const RIFF::WAV::File *>(f)) {
+    *extType = TagLib_Ext_File_WAV;
+    return 2;
+  }
+  else if(dynamic_cast<const RIFF::AIFF::File *>(f)) {
+    *extType = TagLib_Ext_File_AIFF;
+    return 2;
+  }
+  else
+    return 0;
+  return 1;
+}
+
+#ifdef _WIN32
+TagLib_File *taglib_ext_file_new_wchar(const wchar_t *filename, BOOL readProperties,
+                                       TagLib_Ext_Read_Style style)
+{
+  return newFile(filename, readProperties, style);
+}
+
+TagLib_File *taglib_ext_file_new_type_wchar(const wchar_t *filename, TagLib_File_Type type,
+                                            BOOL readProperties, TagLib_Ext_Read_Style style)
+{
+  return newType<FileName>(filename, type, readProperties, style);
+}
+
+TagLib_File *taglib_ext_file_new_ext_type_wchar(const wchar_t *filename, TagLib_Ext_File_Type type,
+                                                BOOL readProperties, TagLib_Ext_Read_Style style)
+{
+  return newExtType<FileName>(filename, type, readProperties, style);
+}
+#endif
+
+TagLib_Ext_Stream *taglib_ext_stream_new_bytes(const char *data, unsigned int length)
+{
+  IOStream *stream = new ByteVectorStream(ByteVector(data, length));
+  return reinterpret_cast<TagLib_Ext_Stream *>(stream);
+}
+
+#ifndef _WIN32
+TagLib_Ext_Stream *taglib_ext_stream_new_fd(int fd, BOOL readOnly)
+{
+  IOStream *stream = new FileStream(fd, readOnly);
+  return reinterpret_cast<TagLib_Ext_Stream *>(stream);
+}
+#endif
+
+const char *taglib_ext_stream_bytes(TagLib_Ext_Stream *stream, unsigned int *length)
+{
+  ByteVectorStream *s = static_cast<ByteVectorStream *>(reinterpret_cast<IOStream *>(stream));
+  ByteVector *data = s->data();
+  *length = data->size();
+  return data->data();
+}
+
+void taglib_ext_stream_free(TagLib_Ext_Stream *stream)
+{
+  delete reinterpret_cast<IOStream *>(stream);
+}
+
+TagLib_File *taglib_ext_file_new_type_stream(TagLib_Ext_Stream *stream, TagLib_File_Type type,
+                                             BOOL readProperties, TagLib_Ext_Read_Style style)
+{
+  return newType(reinterpret_cast<IOStream *>(stream), type, readProperties, style);
+}
+
+TagLib_File *taglib_ext_file_new_ext_type_stream(TagLib_Ext_Stream *stream, TagLib_Ext_File_Type type,
+                                                 BOOL readProperties, TagLib_Ext_Read_Style style)
+{
+  return newExtType(reinterpret_cast<IOStream *>(stream), type, readProperties, style);
+}
+
+void taglib_ext_version(int *major, int *minor, int *patch)
+{
+  *major = TAGLIB_MAJOR_VERSION;
+  *minor = TAGLIB_MINOR_VERSION;
+  *patch = TAGLIB_PATCH_VERSION;
+}
+
+int taglib_ext_audioproperties_length_ms(const TagLib_AudioProperties *audioProperties)
+{
+#if (TAGLIB_MAJOR_VERSION > 1) || (TAGLIB_MINOR_VERSION >= 10)
+  const AudioProperties *p = reinterpret_cast<const AudioProperties *>(audioProperties);
+  return p->lengthInMilliseconds();
+#else
+  (void)audioProperties;
+  return -1;
+#endif
+}
+
+char *taglib_ext_property_get(const TagLib_File *file, const char *key)
+{
+  const File *f = reinterpret_cast<const File *>(file);
+  const PropertyMap map = f->properties();
+  PropertyMap::ConstIterator it = map.find(String(key, String::UTF8));
+  if(it == map.end() || it->second.isEmpty())
+    return NULL;
+  return ::strdup(it->second.front().toCString(true));
+}
+
+char **taglib_ext_property_values(const TagLib_File *file, const char *key)
+{
+  const File *f = reinterpret_cast<const File *>(file);
+  const PropertyMap map = f->properties();
+  PropertyMap::ConstIterator it = map.find(String(key, String::UTF8));
+  if(it == map.end())
+    return toStringList(StringList());
+  return toStringList(it->second);
+}
+
+BOOL taglib_ext_property_set(TagLib_File *file, const char *key, const char *value, BOOL append)
+{
+  File *f = reinterpret_cast<File *>(file);
+  PropertyMap map = f->properties();
+  const String k(key, String::UTF8);
+  if(!value)
+    map.erase(k);
+  else if(append)
+    map[k].append(String(value, String::UTF8));
+  else
+    map.replace(k, StringList(String(value, String::UTF8)));
+  const PropertyMap unsupported = f->setProperties(map);
+  return !unsupported.contains(k);
+}
+
+BOOL taglib_ext_tag_is_empty(const TagLib_Tag *tag)
+{
+  const Tag *t = reinterpret_cast<const Tag *>(tag);
+  return t->isEmpty();
+}
+
+BOOL taglib_ext_properties_is_empty(const TagLib_File *file)
+{
+  const File *f = reinterpret_cast<const File *>(file);
+  return f->properties().isEmpty();
+}
+
+void taglib_ext_free_string_list(char **list)
+{
+  if(!list)
+    return;
+  for(char **it = list; *it; ++it)
+    ::free(*it);
+  ::free(list);
+}
+
+char **taglib_ext_properties_copy(const TagLib_File *from, TagLib_File *to)
+{
+  const File *source = reinterpret_cast<const File *>(from);
+  File *destination = reinterpret_cast<File *>(to);
+  const PropertyMap unsupported = destination->setProperties(source->properties());
+  StringList keys;
+  for(PropertyMap::ConstIterator it = unsupported.begin(); it != unsupported.end(); ++it)
+    keys.append(it->first);
+  return toStringList(keys);
+}
+
+char **taglib_ext_property_keys(const TagLib_File *file)
+{
+  const File *f = reinterpret_cast<const File *>(file);
+  const PropertyMap map = f->properties();
+  StringList keys;
+  for(PropertyMap::ConstIterator it = map.begin(); it != map.end(); ++it)
+    keys.append(it->first);
+  return toStringList(keys);
+}
+
+char **taglib_ext_properties(const TagLib_File *file)
+{
+  const File *f = reinterpret_cast<const File *>(file);
+  const PropertyMap map = f->properties();
+  StringList pairs;
+  for(PropertyMap::ConstIterator it = map.begin(); it != map.end(); ++it) {
+    for(StringList::ConstIterator value = it->second.begin(); value != it->second.end(); ++value) {
+      pairs.append(it->first);
+      pairs.append(*value);
+    }
+  }
+  return toStringList(pairs);
+}
+
+char **taglib_ext_properties_set(TagLib_File *file, const char *const *pairs)
+{
+  File *f = reinterpret_cast<File *>(file);
+  PropertyMap map;
+  for(const char *const *it = pairs; it[0] && it[1]; it += 2)
+    map[String(it[0], String::UTF8)].append(String(it[1], String::UTF8));
+  const PropertyMap unsupported = f->setProperties(map);
+  StringList keys;
+  for(PropertyMap::ConstIterator it = unsupported.begin(); it != unsupported.end(); ++it)
+    keys.append(it->first);
+  return toStringList(keys);
+}
+
+void taglib_ext_id3v2_set_text_encoding(TagLib_File *file, TagLib_ID3v2_Encoding encoding)
+{
+  ID3v2::Tag *tag = id3v2Tag(reinterpret_cast<File *>(file));
+  if(!tag)
+    return;
+
+  String::Type type = String::Latin1;
+  switch(encoding) {
+  case TagLib_ID3v2_Latin1:
+    type = String::Latin1;
+    break;
+  case TagLib_ID3v2_UTF16:
+    type = String::UTF16;
+    break;
+  case TagLib_ID3v2_UTF16BE:
+    type = String::UTF16BE;
+    break;
+  case TagLib_ID3v2_UTF8:
+    type = String::UTF8;
+    break;
+  }
+
+  const ID3v2::FrameList &frames = tag->frameList();
+  for(ID3v2::FrameList::ConstIterator it = frames.begin(); it != frames.end(); ++it) {
+    if(ID3v2::TextIdentificationFrame *text = dynamic_cast<ID3v2::TextIdentificationFrame *>(*it))
+      text->setTextEncoding(type);
+    else if(ID3v2::CommentsFrame *comment = dynamic_cast<ID3v2::CommentsFrame *>(*it))
+      comment->setTextEncoding(type);
+    else if(ID3v2::UnsynchronizedLyricsFrame *lyrics = dynamic_cast<ID3v2::UnsynchronizedLyricsFrame *>(*it))
+      lyrics->setTextEncoding(type);
+  }
+}
+
+void taglib_ext_id3v2_set_padding(TagLib_File *file, unsigned int padding)
+{
+  ID3v2::Tag *tag = id3v2Tag(reinterpret_cast<File *>(file));
+  if(!tag)
+    return;
+
+  // the tag is padded out to the size in its header, so set that to the size of the
+  // frames, plus the padding
+  unsigned int size = 0;
+  const ID3v2::FrameList &frames = tag->frameList();
+  for(ID3v2::FrameList::ConstIterator it = frames.begin(); it != frames.end(); ++it)
+    size += (*it)->render().size();
+  tag->header()->setTagSize(size + padding);
+}
+
+char *taglib_ext_id3v2_lyrics_language(const TagLib_File *file)
+{
+  ID3v2::Tag *tag = id3v2Tag(const_cast<File *>(reinterpret_cast<const File *>(file)));
+  if(!tag)
+    return NULL;
+
+  const ID3v2::FrameList &frames = tag->frameList("USLT");
+  if(frames.isEmpty())
+    return NULL;
+  const ID3v2::UnsynchronizedLyricsFrame *frame =
+    dynamic_cast<const ID3v2::UnsynchronizedLyricsFrame *>(frames.front());
+  if(!frame)
+    return NULL;
+  return ::strdup(String(frame->language(), String::Latin1).toCString(true));
+}
+
+BOOL taglib_ext_id3v2_has_frame_for_key(const char *key)
+{
+  return !ID3v2::Frame::keyToFrameID(String(key, String::UTF8)).isEmpty();
+}
+
+int taglib_ext_id3v2_rating(const TagLib_File *file, const char *email)
+{
+  ID3v2::Tag *tag = id3v2Tag(const_cast<File *>(reinterpret_cast<const File *>(file)));
+  if(!tag)
+    return -1;
+  const ID3v2::PopularimeterFrame *frame = popularimeter(tag, String(email, String::UTF8), false);
+  return frame ? frame->rating() : -1;
+}
+
+BOOL taglib_ext_id3v2_set_rating(TagLib_File *file, const char *email, int rating)
+{
+  ID3v2::Tag *tag = id3v2Tag(reinterpret_cast<File *>(file), rating >= 0);
+  if(!tag)
+    return rating < 0;
+
+  const String owner(email, String::UTF8);
+  ID3v2::PopularimeterFrame *frame = popularimeter(tag, owner, true);
+  if(rating < 0) {
+    if(frame)
+      tag->removeFrame(frame);
+    return true;
+  }
+  if(!frame) {
+    frame = new ID3v2::PopularimeterFrame();
+    frame->setEmail(owner);
+    tag->addFrame(frame);
+  }
+  frame->setRating(rating);
+  return true;
+}
+
+char *taglib_ext_user_text(const TagLib_File *file, const char *description)
+{
+  File *f = const_cast<File *>(reinterpret_cast<const File *>(file));
+  const String desc(description, String::UTF8);
+
+  StringList values;
+  if(Ogg::XiphComment *xiph = xiphComment(f)) {
+    const Ogg::FieldListMap &fields = xiph->fieldListMap();
+    Ogg::FieldListMap::ConstIterator it = fields.find(desc.upper());
+    if(it != fields.end())
+      values = it->second;
+  }
+  else if(ID3v2::Tag *tag = id3v2Tag(f)) {
+    const ID3v2::FrameList frames = userTextFrames(tag, desc);
+    if(!frames.isEmpty()) {
+      // the first field of a TXXX frame is its description
+      values = static_cast<ID3v2::UserTextIdentificationFrame *>(frames.front())->fieldList();
+      values.erase(values.begin());
+    }
+  }
+  else if(MP4::File *mp4 = dynamic_cast<MP4::File *>(f)) {
+    if(mp4->tag() && mp4->tag()->contains(freeformKey(desc)))
+      values = mp4->tag()->item(freeformKey(desc)).toStringList();
+  }
+  else {
+    const PropertyMap map = f->properties();
+    PropertyMap::ConstIterator it = map.find(desc.upper());
+    if(it != map.end())
+      values = it->second;
+  }
+
+  if(values.isEmpty())
+    return NULL;
+  return ::strdup(values.front().toCString(true));
+}
+
+BOOL taglib_ext_set_user_text(TagLib_File *file, const char *description, const char *value)
+{
+  File *f = reinterpret_cast<File *>(file);
+  const String desc(description, String::UTF8);
+
+  if(Ogg::XiphComment *xiph = xiphComment(f, value != NULL)) {
+    if(!Ogg::XiphComment::checkKey(desc))
+      return false;
+    if(value)
+      xiph->addField(desc, String(value, String::UTF8), true);
+    else
+      xiph->removeFields(desc);
+    return true;
+  }
+  if(ID3v2::Tag *tag = id3v2Tag(f, value != NULL)) {
+    const ID3v2::FrameList frames = userTextFrames(tag, desc);
+    for(ID3v2::FrameList::ConstIterator it = frames.begin(); it != frames.end(); ++it)
+      tag->removeFrame(*it);
+    if(value) {
+      ID3v2::UserTextIdentificationFrame *frame =
+        new ID3v2::UserTextIdentificationFrame(ID3v2::FrameFactory::instance()->defaultTextEncoding());
+      frame->setDescription(desc);
+      frame->setText(String(value, String::UTF8));
+      tag->addFrame(frame);
+    }
+    return true;
+  }
+  if(MP4::File *mp4 = dynamic_cast<MP4::File *>(f)) {
+    if(!mp4->tag())
+      return false;
+    if(value)
+      mp4->tag()->setItem(freeformKey(desc), StringList(String(value, String::UTF8)));
+    else
+      mp4->tag()->removeItem(freeformKey(desc));
+    return true;
+  }
+
+  // anything else only has its property map, where keys are upper case
+  PropertyMap map = f->properties();
+  if(value)
+    map.replace(desc.upper(), StringList(String(value, String::UTF8)));
+  else
+    map.erase(desc.upper());
+  return f->setProperties(map).isEmpty();
+}
+
+char *taglib_ext_id3v2_text_frame(const TagLib_File *file, const char *id)
+{
+  ID3v2::Tag *tag = id3v2Tag(const_cast<File *>(reinterpret_cast<const File *>(file)));
+  if(!tag)
+    return NULL;
+  const ID3v2::FrameList &frames = tag->frameList(ByteVector(id));
+  if(frames.isEmpty())
+    return NULL;
+  return ::strdup(frames.front()->toString().toCString(true));
+}
+
+BOOL taglib_ext_id3v2_set_text_frame(TagLib_File *file, const char *id, const char *value)
+{
+  ID3v2::Tag *tag = id3v2Tag(reinterpret_cast<File *>(file), value != NULL);
+  if(!tag)
+    return value == NULL;
+  tag->removeFrames(ByteVector(id));
+  if(value) {
+    ID3v2::TextIdentificationFrame *frame = new ID3v2::TextIdentificationFrame(
+      ByteVector(id), ID3v2::FrameFactory::instance()->defaultTextEncoding());
+    frame->setText(String(value, String::UTF8));
+    tag->addFrame(frame);
+  }
+  return true;
+}
+
+char *taglib_ext_mp4_item(const TagLib_File *file, const char *name)
+{
+  MP4::Tag *tag = mp4Tag(const_cast<File *>(reinterpret_cast<const File *>(file)));
+  const String key(name, String::UTF8);
+  if(!tag || !tag->contains(key))
+    return NULL;
+  const StringList values = tag->item(key).toStringList();
+  if(values.isEmpty())
+    return NULL;
+  return ::strdup(values.front().toCString(true));
+}
+
+BOOL taglib_ext_mp4_set_item(TagLib_File *file, const char *name, const char *value)
+{
+  MP4::Tag *tag = mp4Tag(reinterpret_cast<File *>(file));
+  if(!tag)
+    return false;
+  const String key(name, String::UTF8);
+  if(value)
+    tag->setItem(key, StringList(String(value, String::UTF8)));
+  else
+    tag->removeItem(key);
+  return true;
+}
+
+int taglib_ext_podcast(const TagLib_File *file)
+{
+  File *f = const_cast<File *>(reinterpret_cast<const File *>(file));
+  if(MP4::Tag *tag = mp4Tag(f))
+    return tag->contains("pcst") && tag->item("pcst").toBool();
+  if(dynamic_cast<MPEG::File *>(f) || dynamic_cast<TrueAudio::File *>(f) ||
+     dynamic_cast<RIFF::WAV::File *>(f) || dynamic_cast<RIFF::AIFF::File *>(f)) {
+    ID3v2::Tag *tag = id3v2Tag(f);
+    return tag && !tag->frameList("PCST").isEmpty();
+  }
+  return -1;
+}
+
+BOOL taglib_ext_set_podcast(TagLib_File *file, BOOL podcast)
+{
+  File *f = reinterpret_cast<File *>(file);
+  if(MP4::Tag *tag = mp4Tag(f)) {
+    if(podcast)
+      tag->setItem("pcst", MP4::Item(true));
+    else
+      tag->removeItem("pcst");
+    return true;
+  }
+  if(dynamic_cast<MPEG::File *>(f) || dynamic_cast<TrueAudio::File *>(f) ||
+     dynamic_cast<RIFF::WAV::File *>(f) || dynamic_cast<RIFF::AIFF::File *>(f)) {
+    ID3v2::Tag *tag = id3v2Tag(f, podcast);
+    if(!tag)
+      return !podcast;
+    tag->removeFrames("PCST");
+    if(podcast)
+      tag->addFrame(new ID3v2::PodcastFrame());
+    return true;
+  }
+  return false;
+}
+
+char *taglib_ext_id3v2_comment(const TagLib_File *file, const char *description)
+{
+  ID3v2::Tag *tag = id3v2Tag(const_cast<File *>(reinterpret_cast<const File *>(file)));
+  if(!tag)
+    return NULL;
+  const ID3v2::CommentsFrame *frame =
+    ID3v2::CommentsFrame::findByDescription(tag, String(description, String::UTF8));
+  if(!frame)
+    return NULL;
+  return ::strdup(frame->text().toCString(true));
+}
+
+int taglib_ext_mp4_int_item(const TagLib_File *file, const char *name)
+{
+  MP4::Tag *tag = mp4Tag(const_cast<File *>(reinterpret_cast<const File *>(file)));
+  const String key(name, String::UTF8);
+  if(!tag || !tag->contains(key))
+    return -1;
+  return tag->item(key).toInt();
+}
+
+BOOL taglib_ext_mp4_set_int_item(TagLib_File *file, const char *name, int value)
+{
+  MP4::Tag *tag = mp4Tag(reinterpret_cast<File *>(file));
+  if(!tag)
+    return false;
+  const String key(name, String::UTF8);
+  if(value >= 0)
+    tag->setItem(key, MP4::Item(value));
+  else
+    tag->removeItem(key);
+  return true;
+}
+
+BOOL taglib_ext_pictures(const TagLib_File *file, TagLib_Ext_Picture **pictures, unsigned int *count)
+{
+  *pictures = NULL;
+  *count = 0;
+  List<PictureData> list;
+  if(!readPictures(const_cast<File *>(reinterpret_cast<const File *>(file)), list))
+    return false;
+  if(list.isEmpty())
+    return true;
+
+  TagLib_Ext_Picture *out = static_cast<TagLib_Ext_Picture *>(::malloc(sizeof(TagLib_Ext_Picture) * list.size()));
+  TagLib_Ext_Picture *picture = out;
+  for(List<PictureData>::ConstIterator it = list.begin(); it != list.end(); ++it, ++picture) {
+    picture->mime_type = ::strdup(it->mimeType.toCString(true));
+    picture->description = ::strdup(it->description.toCString(true));
+    picture->type = it->type;
+    // the data is copied, rather than pointing into taglib's copy, so that it outlives
+    // changes to the tag
+    picture->size = it->data.size();
+    picture->data = static_cast<char *>(::malloc(it->data.size() > 0 ? it->data.size() : 1));
+    ::memcpy(picture->data, it->data.data(), it->data.size());
+    picture->width = it->width;
+    picture->height = it->height;
+    picture->color_depth = it->colorDepth;
+    picture->colors = it->colors;
+  }
+  *pictures = out;
+  *count = list.size();
+  return true;
+}
+
+void taglib_ext_free_pictures(TagLib_Ext_Picture *pictures, unsigned int count)
+{
+  if(!pictures)
+    return;
+  for(unsigned int i = 0; i < count; ++i) {
+    ::free(pictures[i].mime_type);
+    ::free(pictures[i].description);
+    ::free(pictures[i].data);
+  }
+  ::free(pictures);
+}
+
+BOOL taglib_ext_add_picture(TagLib_File *file, const TagLib_Ext_Picture *picture)
+{
+  File *f = reinterpret_cast<File *>(file);
+  if(FLAC::File *flac = dynamic_cast<FLAC::File *>(f)) {
+    flac->addPicture(flacPicture(picture));
+    return true;
+  }
+  if(Ogg::XiphComment *xiph = xiphComment(f)) {
+    xiph->addPicture(flacPicture(picture));
+    return true;
+  }
+  if(MP4::Tag *tag = mp4Tag(f)) {
+    MP4::CoverArtList list;
+    if(tag->contains("covr"))
+      list = tag->item("covr").toCoverArtList();
+    list.append(MP4::CoverArt(coverArtFormat(String(picture->mime_type, String::UTF8)),
+                              ByteVector(picture->data, picture->size)));
+    tag->setItem("covr", list);
+    return true;
+  }
+  if(hasID3v2Pictures(f)) {
+    ID3v2::Tag *tag = id3v2Tag(f, true);
+    if(!tag)
+      return false;
+    ID3v2::AttachedPictureFrame *frame = new ID3v2::AttachedPictureFrame();
+    frame->setTextEncoding(ID3v2::FrameFactory::instance()->defaultTextEncoding());
+    frame->setMimeType(String(picture->mime_type, String::UTF8));
+    frame->setDescription(String(picture->description, String::UTF8));
+    frame->setType(static_cast<ID3v2::AttachedPictureFrame::Type>(picture->type));
+    frame->setPicture(ByteVector(picture->data, picture->size));
+    tag->addFrame(frame);
+    return true;
+  }
+  return false;
+}
+
+BOOL taglib_ext_remove_pictures(TagLib_File *file, int type)
+{
+  File *f = reinterpret_cast<File *>(file);
+  if(FLAC::File *flac = dynamic_cast<FLAC::File *>(f)) {
+    const List<FLAC::Picture *> list = flac->pictureList();
+    for(List<FLAC::Picture *>::ConstIterator it = list.begin(); it != list.end(); ++it) {
+      if(type < 0 || (*it)->type() == type)
+        flac->removePicture(*it, true);
+    }
+    return true;
+  }
+  if(Ogg::XiphComment *xiph = xiphComment(f)) {
+    const List<FLAC::Picture *> list = xiph->pictureList();
+    for(List<FLAC::Picture *>::ConstIterator it = list.begin(); it != list.end(); ++it) {
+      if(type < 0 || (*it)->type() == type)
+        xiph->removePicture(*it, true);
+    }
+    return true;
+  }
+  if(MP4::Tag *tag = mp4Tag(f)) {
+    // every MP4 picture is a front cover
+    if(type < 0 || type == ID3v2::AttachedPictureFrame::FrontCover)
+      tag->removeItem("covr");
+    return true;
+  }
+  if(hasID3v2Pictures(f)) {
+    ID3v2::Tag *tag = id3v2Tag(f);
+    if(!tag)
+      return true;
+    const ID3v2::FrameList frames = tag->frameList("APIC");
+    for(ID3v2::FrameList::ConstIterator it = frames.begin(); it != frames.end(); ++it) {
+      const ID3v2::AttachedPictureFrame *frame = dynamic_cast<const ID3v2::AttachedPictureFrame *>(*it);
+      if(type < 0 || (frame && frame->type() == type))
+        tag->removeFrame(*it);
+    }
+    return true;
+  }
+  return false;
+}
+
+unsigned int taglib_ext_tag_containers(const TagLib_File *file)
+{
+  File *f = const_cast<File *>(reinterpret_cast<const File *>(file));
+  unsigned int containers = 0;
+  if(const MPEG::File *mpeg = dynamic_cast<const MPEG::File *>(f)) {
+    if(mpeg->hasID3v1Tag())
+      containers |= TagLib_Ext_Tag_ID3v1;
+    if(mpeg->hasID3v2Tag())
+      containers |= TagLib_Ext_Tag_ID3v2;
+    if(mpeg->hasAPETag())
+      containers |= TagLib_Ext_Tag_APE;
+  }
+  else if(const FLAC::File *flac = dynamic_cast<const FLAC::File *>(f)) {
+    if(flac->hasID3v1Tag())
+      containers |= TagLib_Ext_Tag_ID3v1;
+    if(flac->hasID3v2Tag())
+      containers |= TagLib_Ext_Tag_ID3v2;
+    if(flac->hasXiphComment() && hasContent(xiphComment(f)))
+      containers |= TagLib_Ext_Tag_Xiph;
+  }
+  else if(Ogg::XiphComment *xiph = xiphComment(f)) {
+    // Ogg streams always have a comment header, but it can be empty
+    if(hasContent(xiph))
+      containers |= TagLib_Ext_Tag_Xiph;
+  }
+  else if(const MP4::File *mp4 = dynamic_cast<const MP4::File *>(f)) {
+    if(mp4->hasMP4Tag() && !mp4Tag(f)->isEmpty())
+      containers |= TagLib_Ext_Tag_MP4;
+  }
+  else if(const ASF::File *asf = dynamic_cast<const ASF::File *>(f)) {
+    // taglib always makes an ASF tag, so only count it if it has something in it
+    if(asf->tag() && !asf->tag()->isEmpty())
+      containers |= TagLib_Ext_Tag_ASF;
+  }
+  else if(const MPC::File *mpc = dynamic_cast<const MPC::File *>(f)) {
+    if(mpc->hasID3v1Tag())
+      containers |= TagLib_Ext_Tag_ID3v1;
+    if(mpc->hasAPETag())
+      containers |= TagLib_Ext_Tag_APE;
+  }
+  else if(const WavPack::File *wavPack = dynamic_cast<const WavPack::File *>(f)) {
+    if(wavPack->hasID3v1Tag())
+      containers |= TagLib_Ext_Tag_ID3v1;
+    if(wavPack->hasAPETag())
+      containers |= TagLib_Ext_Tag_APE;
+  }
+  else if(const APE::File *ape = dynamic_cast<const APE::File *>(f)) {
+    if(ape->hasID3v1Tag())
+      containers |= TagLib_Ext_Tag_ID3v1;
+    if(ape->hasAPETag())
+      containers |= TagLib_Ext_Tag_APE;
+  }
+  else if(const TrueAudio::File *trueAudio = dynamic_cast<const TrueAudio::File *>(f)) {
+    if(trueAudio->hasID3v1Tag())
+      containers |= TagLib_Ext_Tag_ID3v1;
+    if(trueAudio->hasID3v2Tag())
+      containers |= TagLib_Ext_Tag_ID3v2;
+  }
+  else if(const RIFF::WAV::File *wav = dynamic_cast<const RIFF::WAV::File *>(f)) {
+    if(wav->hasID3v2Tag())
+      containers |= TagLib_Ext_Tag_ID3v2;
+    if(wav->hasInfoTag())
+      containers |= TagLib_Ext_Tag_RIFF_Info;
+  }
+  else if(const RIFF::AIFF::File *aiff = dynamic_cast<const RIFF::AIFF::File *>(f)) {
+    if(aiff->hasID3v2Tag())
+      containers |= TagLib_Ext_Tag_ID3v2;
+  }
+  return containers;
+}
+
+BOOL taglib_ext_mpeg_save(TagLib_File *file, unsigned int tags, BOOL stripOthers, int id3v2Version)
+{
+  MPEG::File *mpeg = dynamic_cast<MPEG::File *>(reinterpret_cast<File *>(file));
+  if(!mpeg)
+    return false;
+  if(id3v2Version == 3 && mpeg->ID3v2Tag())
+    downgradeFrames(mpeg->ID3v2Tag());
+#if (TAGLIB_MAJOR_VERSION > 1) || (TAGLIB_MINOR_VERSION >= 12)
+  return mpeg->save(mpegTagTypes(tags), stripOthers ? File::StripOthers : File::StripNone,
+                    id3v2Version == 3 ? ID3v2::v3 : ID3v2::v4, File::Duplicate);
+#else
+  return mpeg->save(mpegTagTypes(tags), stripOthers, id3v2Version == 3 ? 3 : 4);
+#endif
+}
+
+BOOL taglib_ext_strip_tags(TagLib_File *file)
+{
+  File *f = reinterpret_cast<File *>(file);
+  if(MPEG::File *mpeg = dynamic_cast<MPEG::File *>(f))
+    return mpeg->strip(MPEG::File::AllTags);
+  if(FLAC::File *flac = dynamic_cast<FLAC::File *>(f)) {
+    flac->strip(FLAC::File::AllTags);
+    flac->removePictures();
+    return true;
+  }
+  if(Ogg::XiphComment *xiph = xiphComment(f)) {
+    xiph->removeAllFields();
+    xiph->removeAllPictures();
+    return true;
+  }
+  if(MP4::Tag *tag = mp4Tag(f)) {
+    const StringList names = tag->itemMap().keys();
+    for(StringList::ConstIterator it = names.begin(); it != names.end(); ++it)
+      tag->removeItem(*it);
+    return true;
+  }
+  if(ASF::File *asf = dynamic_cast<ASF::File *>(f)) {
+    ASF::Tag *tag = asf->tag();
+    const ASF::AttributeListMap attributes = tag->attributeListMap();
+    for(ASF::AttributeListMap::ConstIterator it = attributes.begin(); it != attributes.end(); ++it)
+      tag->removeItem(it->first);
+    tag->setTitle(String());
+    tag->setArtist(String());
+    tag->setComment(String());
+    tag->setCopyright(String());
+    tag->setRating(String());
+    return true;
+  }
+  if(MPC::File *mpc = dynamic_cast<MPC::File *>(f)) {
+    mpc->strip(MPC::File::AllTags);
+    return true;
+  }
+  if(WavPack::File *wavPack = dynamic_cast<WavPack::File *>(f)) {
+    wavPack->strip(WavPack::File::AllTags);
+    return true;
+  }
+  if(APE::File *ape = dynamic_cast<APE::File *>(f)) {
+    ape->strip(APE::File::AllTags);
+    return true;
+  }
+  if(TrueAudio::File *trueAudio = dynamic_cast<TrueAudio::File *>(f)) {
+    trueAudio->strip(TrueAudio::File::AllTags);
+    return true;
+  }
+  if(RIFF::WAV::File *wav = dynamic_cast<RIFF::WAV::File *>(f)) {
+    wav->strip(RIFF::WAV::File::AllTags);
+    return true;
+  }
+  if(RIFF::AIFF::File *aiff = dynamic_cast<RIFF::AIFF::File *>(f)) {
+    ID3v2::Tag *tag = aiff->tag();
+    const ID3v2::FrameList frames = tag->frameList();
+    for(ID3v2::FrameList::ConstIterator it = frames.begin(); it != frames.end(); ++it)
+      tag->removeFrame(*it);
+    return true;
+  }
+  return false;
+}
+
+BOOL taglib_ext_mpeg_strip(TagLib_File *file, unsigned int tags)
+{
+  MPEG::File *mpeg = dynamic_cast<MPEG::File *>(reinterpret_cast<File *>(file));
+  if(!mpeg)
+    return false;
+  return mpeg->strip(mpegTagTypes(tags));
+}
+
+char *taglib_ext_vendor_string(const TagLib_File *file)
+{
+  Ogg::XiphComment *xiph = xiphComment(const_cast<File *>(reinterpret_cast<const File *>(file)));
+  if(!xiph)
+    return NULL;
+  return ::strdup(xiph->vendorID().toCString(true));
+}
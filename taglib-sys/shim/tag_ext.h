@@ -0,0 +1,416 @@
+/*
+ * Extensions to the taglib C interface (tag_c.h).
+ *
+ * These expose parts of the C++ API that tag_c does not, while keeping the
+ * same conventions: opaque handles come from tag_c, strings returned are
+ * allocated with malloc and must be released with taglib_free(), and
+ * booleans are returned as ints.
+ */
+
+#ifndef TAGLIB_EXT_H
+#define TAGLIB_EXT_H
+
+#include <tag_c.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/*
+ * File types that taglib supports, but that tag_c's TagLib_File_Type doesn't
+ * include.
+ */
+typedef enum {
+  TagLib_Ext_File_Opus,
+  TagLib_Ext_File_WAV,
+  TagLib_Ext_File_AIFF
+} TagLib_Ext_File_Type;
+
+/*
+ * How carefully audio properties are read, as TagLib::AudioProperties::ReadStyle.
+ */
+typedef enum {
+  TagLib_Ext_Read_Fast,
+  TagLib_Ext_Read_Average,
+  TagLib_Ext_Read_Accurate
+} TagLib_Ext_Read_Style;
+
+/*
+ * As taglib_file_new() and taglib_file_new_type(), but letting the caller
+ * choose whether (and how carefully) the audio properties are read, and also
+ * accepting the types in TagLib_Ext_File_Type. taglib_file_new() is the same
+ * as taglib_ext_file_new(filename, true, TagLib_Ext_Read_Average). The file
+ * should be freed with taglib_file_free().
+ */
+TagLib_File *taglib_ext_file_new(const char *filename, BOOL readProperties,
+                                 TagLib_Ext_Read_Style style);
+TagLib_File *taglib_ext_file_new_type(const char *filename, TagLib_File_Type type,
+                                      BOOL readProperties, TagLib_Ext_Read_Style style);
+TagLib_File *taglib_ext_file_new_ext_type(const char *filename, TagLib_Ext_File_Type type,
+                                          BOOL readProperties, TagLib_Ext_Read_Style style);
+
+/*
+ * Finds which format taglib opened a file as. Returns 1 and sets type for the
+ * formats in TagLib_File_Type, 2 and sets extType for those in
+ * TagLib_Ext_File_Type, and 0 for any other format.
+ */
+int taglib_ext_file_type(const TagLib_File *file, TagLib_File_Type *type,
+                         TagLib_Ext_File_Type *extType);
+
+#ifdef _WIN32
+#include <wchar.h>
+
+/*
+ * As above, but taking the filename as a wide string, so that paths which
+ * can't be represented in the current code page work.
+ */
+TagLib_File *taglib_ext_file_new_wchar(const wchar_t *filename, BOOL readProperties,
+                                       TagLib_Ext_Read_Style style);
+TagLib_File *taglib_ext_file_new_type_wchar(const wchar_t *filename, TagLib_File_Type type,
+                                            BOOL readProperties, TagLib_Ext_Read_Style style);
+TagLib_File *taglib_ext_file_new_ext_type_wchar(const wchar_t *filename, TagLib_Ext_File_Type type,
+                                                BOOL readProperties, TagLib_Ext_Read_Style style);
+#endif
+
+/*
+ * A TagLib::IOStream, for opening files from somewhere other than a path.
+ * Files opened from a stream don't own it: the stream must be freed with
+ * taglib_ext_stream_free(), after the file has been freed.
+ */
+typedef struct { int dummy; } TagLib_Ext_Stream;
+
+/*
+ * Creates a stream over a copy of length bytes of data.
+ */
+TagLib_Ext_Stream *taglib_ext_stream_new_bytes(const char *data, unsigned int length);
+
+#ifndef _WIN32
+/*
+ * Creates a stream over an open file descriptor, which the stream takes
+ * ownership of, and closes when it's freed. If readOnly is false, but the
+ * descriptor can't be written to, then the stream falls back to reading.
+ */
+TagLib_Ext_Stream *taglib_ext_stream_new_fd(int fd, BOOL readOnly);
+#endif
+
+/*
+ * Returns the current contents of a stream made by
+ * taglib_ext_stream_new_bytes(), with its length in length. The data belongs
+ * to the stream, and is only valid until the stream is changed or freed.
+ */
+const char *taglib_ext_stream_bytes(TagLib_Ext_Stream *stream, unsigned int *length);
+
+void taglib_ext_stream_free(TagLib_Ext_Stream *stream);
+
+/*
+ * As taglib_ext_file_new_type() and taglib_ext_file_new_ext_type(), reading
+ * from a stream rather than a path.
+ */
+TagLib_File *taglib_ext_file_new_type_stream(TagLib_Ext_Stream *stream, TagLib_File_Type type,
+                                             BOOL readProperties, TagLib_Ext_Read_Style style);
+TagLib_File *taglib_ext_file_new_ext_type_stream(TagLib_Ext_Stream *stream, TagLib_Ext_File_Type type,
+                                                 BOOL readProperties, TagLib_Ext_Read_Style style);
+
+/*
+ * Gets the version of taglib that is linked, which is the version of the headers
+ * that the extensions were built against, as taglib 1 can't report it at runtime.
+ */
+void taglib_ext_version(int *major, int *minor, int *patch);
+
+/*
+ * Returns the length of the file in milliseconds, or -1 if the linked taglib
+ * only reports whole seconds.
+ */
+int taglib_ext_audioproperties_length_ms(const TagLib_AudioProperties *audioProperties);
+
+/*
+ * Returns the first value stored under key in the file's property map, or
+ * NULL if the file has no such property.
+ */
+char *taglib_ext_property_get(const TagLib_File *file, const char *key);
+
+/*
+ * Returns every value stored under key in the file's property map, in order.
+ * The list is empty if the file has no such property.
+ */
+char **taglib_ext_property_values(const TagLib_File *file, const char *key);
+
+/*
+ * Sets the values stored under key in the file's property map to just value,
+ * or adds value after the existing ones if append is true. If value is NULL,
+ * the property is removed. Returns false if the format can't store the
+ * property.
+ */
+BOOL taglib_ext_property_set(TagLib_File *file, const char *key, const char *value, BOOL append);
+
+/*
+ * Returns true if none of the tag's standard fields (title, artist, album,
+ * comment, genre, year and track) are set.
+ */
+BOOL taglib_ext_tag_is_empty(const TagLib_Tag *tag);
+
+/*
+ * Returns true if the file's property map has no entries.
+ */
+BOOL taglib_ext_properties_is_empty(const TagLib_File *file);
+
+/*
+ * Lists of strings are returned as NULL terminated arrays, which must be
+ * released with taglib_ext_free_string_list().
+ */
+void taglib_ext_free_string_list(char **list);
+
+/*
+ * Replaces the properties of the file to with those of the file from. Returns
+ * the keys of any properties that the format of to couldn't store.
+ */
+char **taglib_ext_properties_copy(const TagLib_File *from, TagLib_File *to);
+
+/*
+ * Returns the keys of the file's property map, in the map's (sorted) order.
+ */
+char **taglib_ext_property_keys(const TagLib_File *file);
+
+/*
+ * Returns the whole of the file's property map as a flat list of key, value
+ * pairs, with a pair for each value, in the map's order.
+ */
+char **taglib_ext_properties(const TagLib_File *file);
+
+/*
+ * Replaces the file's property map with the key, value pairs in the NULL
+ * terminated list pairs, in the format returned by taglib_ext_properties().
+ * Returns the keys of any properties that the format couldn't store.
+ */
+char **taglib_ext_properties_set(TagLib_File *file, const char *const *pairs);
+
+/*
+ * Sets the text encoding of every text, comment and lyrics frame in the file's
+ * ID3v2 tag, if it has one. Unlike taglib_id3v2_set_default_text_encoding(),
+ * this affects frames that already exist, and isn't global. As with the
+ * default, taglib still moves Latin1 frames to a unicode encoding if their
+ * text can't be represented, and writes UTF8 and UTF16BE as UTF16 when saving
+ * ID3v2.3.
+ */
+void taglib_ext_id3v2_set_text_encoding(TagLib_File *file, TagLib_ID3v2_Encoding encoding);
+
+/*
+ * Sets the amount of padding that the file's ID3v2 tag, if it has one, is
+ * given the next time that the file is saved. taglib writes 1024 bytes when
+ * asked for none at all, or for more than 1% of the file size (or 1MB).
+ */
+void taglib_ext_id3v2_set_padding(TagLib_File *file, unsigned int padding);
+
+/*
+ * Returns the language code of the first unsynchronised lyrics frame in the
+ * file's ID3v2 tag, or NULL if it doesn't have one.
+ */
+char *taglib_ext_id3v2_lyrics_language(const TagLib_File *file);
+
+/*
+ * Returns true if the linked taglib stores the property key in an ID3v2 frame
+ * of its own, rather than in a TXXX frame.
+ */
+BOOL taglib_ext_id3v2_has_frame_for_key(const char *key);
+
+/*
+ * Returns the rating (from 0 to 255) of the popularimeter frame in the file's
+ * ID3v2 tag with the given email, or of the first one if there's none with that
+ * email, or -1 if the tag has no popularimeter frames.
+ */
+int taglib_ext_id3v2_rating(const TagLib_File *file, const char *email);
+
+/*
+ * Sets the rating of the popularimeter frame with the given email, creating
+ * the frame (and the ID3v2 tag) if needed, or removes the frame if rating is
+ * negative. Returns false if the file can't have an ID3v2 tag.
+ */
+BOOL taglib_ext_id3v2_set_rating(TagLib_File *file, const char *email, int rating);
+
+/*
+ * Returns the first value of the user defined text field with the given
+ * description, or NULL if there isn't one. That's a TXXX frame in ID3v2 (with
+ * the exact description if there is one, or else one that differs only in
+ * case), a freeform ----:com.apple.iTunes atom in MP4, a comment in Xiph
+ * comments, and a property for other formats.
+ */
+char *taglib_ext_user_text(const TagLib_File *file, const char *description);
+
+/*
+ * Replaces the user defined text field with the given description, creating
+ * the tag if needed, or removes it (along with any whose description differs
+ * only in case) if value is NULL. Returns false if the format can't store the
+ * description, e.g. a Xiph comment name that isn't printable ASCII.
+ */
+BOOL taglib_ext_set_user_text(TagLib_File *file, const char *description, const char *value);
+
+/*
+ * Returns the text of the first frame with the given id (e.g. "TCAT") in the
+ * file's ID3v2 tag, or NULL if there isn't one.
+ */
+char *taglib_ext_id3v2_text_frame(const TagLib_File *file, const char *id);
+
+/*
+ * Replaces every frame with the given id in the file's ID3v2 tag with a text
+ * frame holding value, creating the tag if needed, or removes them if value is
+ * NULL. Returns false if the file can't have an ID3v2 tag.
+ */
+BOOL taglib_ext_id3v2_set_text_frame(TagLib_File *file, const char *id, const char *value);
+
+/*
+ * Returns the first value of the item with the given atom name (e.g. "catg",
+ * or "©wrk" in UTF-8) in an MP4 file's tag as a string, or NULL if there
+ * isn't one.
+ */
+char *taglib_ext_mp4_item(const TagLib_File *file, const char *name);
+
+/*
+ * Replaces the item with the given atom name in an MP4 file's tag with the
+ * string value, or removes it if value is NULL. Returns false if the file
+ * isn't MP4.
+ */
+BOOL taglib_ext_mp4_set_item(TagLib_File *file, const char *name, const char *value);
+
+/*
+ * Returns 1 if the file is marked as a podcast (by a PCST frame in ID3v2, or
+ * the pcst atom in MP4), 0 if it isn't, or -1 if the format has no such mark.
+ */
+int taglib_ext_podcast(const TagLib_File *file);
+
+/*
+ * Marks the file as a podcast, or removes the mark. Returns false if the
+ * format has no such mark.
+ */
+BOOL taglib_ext_set_podcast(TagLib_File *file, BOOL podcast);
+
+/*
+ * Returns the text of the first comment frame with the given description in
+ * the file's ID3v2 tag, or NULL if there isn't one.
+ */
+char *taglib_ext_id3v2_comment(const TagLib_File *file, const char *description);
+
+/*
+ * Returns the value of the integer item with the given atom name (e.g. "©mvi")
+ * in an MP4 file's tag, or -1 if there isn't one. taglib only reads and writes
+ * the movement atoms as integers from 1.12.
+ */
+int taglib_ext_mp4_int_item(const TagLib_File *file, const char *name);
+
+/*
+ * Replaces the integer item with the given atom name in an MP4 file's tag, or
+ * removes it if value is negative. Returns false if the file isn't MP4.
+ */
+BOOL taglib_ext_mp4_set_int_item(TagLib_File *file, const char *name, int value);
+
+/*
+ * A picture embedded in a file. type is one of the ID3v2 APIC picture types,
+ * which FLAC and Xiph comments share. The dimensions and colours are only
+ * stored by FLAC picture blocks, and are 0 for other formats.
+ */
+typedef struct {
+  char *mime_type;
+  char *description;
+  int type;
+  char *data;
+  unsigned int size;
+  int width;
+  int height;
+  int color_depth;
+  int colors;
+} TagLib_Ext_Picture;
+
+/*
+ * Copies every picture in the file, in the order that it stores them, into
+ * an array of count pictures, which must be freed with
+ * taglib_ext_free_pictures(). That's the APIC frames of an ID3v2 tag, FLAC
+ * picture blocks, METADATA_BLOCK_PICTURE comments in Ogg files, and the covr
+ * atom in MP4, where every picture is a front cover. Returns false (with no
+ * pictures) for formats that can't have pictures.
+ */
+BOOL taglib_ext_pictures(const TagLib_File *file, TagLib_Ext_Picture **pictures, unsigned int *count);
+
+void taglib_ext_free_pictures(TagLib_Ext_Picture *pictures, unsigned int count);
+
+/*
+ * Adds a copy of picture after the file's other pictures, creating the tag if
+ * needed. The MIME type of MP4 pictures must be image/jpeg, image/png,
+ * image/gif or image/bmp, or other tools won't be able to show them. Returns
+ * false for formats that can't have pictures.
+ */
+BOOL taglib_ext_add_picture(TagLib_File *file, const TagLib_Ext_Picture *picture);
+
+/*
+ * Removes the file's pictures of the given type, or all of them if type is
+ * negative. As every MP4 picture is a front cover, removing that type removes
+ * all of them. Returns false for formats that can't have pictures.
+ */
+BOOL taglib_ext_remove_pictures(TagLib_File *file, int type);
+
+/*
+ * The kinds of tag that files can hold, as the bits of the mask that
+ * taglib_ext_tag_containers() returns.
+ */
+typedef enum {
+  TagLib_Ext_Tag_ID3v1 = 1 << 0,
+  TagLib_Ext_Tag_ID3v2 = 1 << 1,
+  TagLib_Ext_Tag_APE = 1 << 2,
+  TagLib_Ext_Tag_Xiph = 1 << 3,
+  TagLib_Ext_Tag_MP4 = 1 << 4,
+  TagLib_Ext_Tag_ASF = 1 << 5,
+  TagLib_Ext_Tag_RIFF_Info = 1 << 6
+} TagLib_Ext_Tag_Container;
+
+/*
+ * Finds which kinds of tag are in the file, as a mask of
+ * TagLib_Ext_Tag_Container bits, as the file was when it was opened or last
+ * saved. Xiph comments, MP4 tags and ASF tags only count if they have
+ * something in them, as taglib can leave them empty (and always makes ASF
+ * tags).
+ */
+unsigned int taglib_ext_tag_containers(const TagLib_File *file);
+
+/*
+ * Saves an MPEG file, writing only the ID3v1, ID3v2 and APE tags in the mask
+ * of TagLib_Ext_Tag_Container bits. If stripOthers is true, the other tags
+ * are removed from the file, otherwise they're left as they were.
+ *
+ * The ID3v2 tag is written as ID3v2.3 if id3v2Version is 3, and ID3v2.4
+ * otherwise. For ID3v2.3, the mood, sort order, disc subtitle and release
+ * date frames, which taglib would drop, are first moved to TXXX frames under
+ * their property keys, and text frames with several values have them joined
+ * with "/". taglib converts TDRC to TYER, TDAT and TIME, TDOR to TORY, and
+ * TIPL and TMCL to IPLS, and drops the other frames that ID3v2.3 doesn't have.
+ *
+ * Returns false if the file isn't an MPEG file, or couldn't be saved.
+ */
+BOOL taglib_ext_mpeg_save(TagLib_File *file, unsigned int tags, BOOL stripOthers, int id3v2Version);
+
+/*
+ * Removes every tag and picture from the file. MPEG files are changed on disk
+ * straight away, and most other formats when they're next saved, so the file
+ * should always be saved (and then reopened, as the MPEG file's tags are
+ * gone). Returns false if the format isn't one that can be stripped, or the
+ * MPEG file couldn't be changed.
+ */
+BOOL taglib_ext_strip_tags(TagLib_File *file);
+
+/*
+ * Removes the ID3v1, ID3v2 and APE tags in the mask of
+ * TagLib_Ext_Tag_Container bits from an MPEG file, on disk straight away,
+ * leaving its other tags as they are. Returns false if the file isn't an MPEG
+ * file, or couldn't be changed.
+ */
+BOOL taglib_ext_mpeg_strip(TagLib_File *file, unsigned int tags);
+
+/*
+ * Returns the vendor string of the file's Xiph comment, which names the
+ * library that wrote it, e.g. "reference libFLAC 1.3.2 20170101". Returns
+ * NULL for formats without Xiph comments, and for FLAC files without one.
+ */
+char *taglib_ext_vendor_string(const TagLib_File *file);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
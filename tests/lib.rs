@@ -0,0 +1,3559 @@
+extern crate taglib;
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use taglib::{
+    image_dimensions, image_mime_type, ConstraintViolation, DiffOptions, Id3v2Version, FieldChange, FieldValue, OpenOptions, PartialDate, Picture, PictureConstraints, PictureDimensions, PictureType, ReadStyle, StringEncodingPolicy,
+    TagContainer, TagData, TagField, TagLibFile,
+};
+
+// copy a fixture into the temp directory, so that tests which save don't modify the originals
+fn scratch_copy(fixture: &str, name: &str) -> PathBuf {
+    let source = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(fixture);
+    let extension = source.extension().unwrap().to_owned();
+    let destination = env::temp_dir()
+        .join(format!("taglib-rs-{}", name))
+        .with_extension(extension);
+    fs::copy(&source, &destination).expect("Couldn't copy fixture");
+    destination
+}
+
+#[test]
+fn duration_of_silence_flac() {
+    let file = TagLibFile::new("tests/fixtures/silence.flac").unwrap();
+    let properties = file.audio_properties().unwrap();
+    match properties.precision() {
+        taglib::DurationPrecision::Milliseconds => assert_eq!(properties.duration().as_millis(), 1500),
+        // rounded to whole seconds, by older versions of taglib
+        taglib::DurationPrecision::Seconds => assert_eq!(properties.duration().subsec_millis(), 0),
+    }
+}
+
+fn bpm_round_trip(fixture: &str, name: &str) {
+    let path = scratch_copy(fixture, name);
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.tag_mut().set_bpm(128).unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.tag().bpm(), Some(128));
+}
+
+#[test]
+fn bpm_round_trip_mp3() {
+    bpm_round_trip("silence.mp3", "bpm_round_trip_mp3");
+}
+
+#[test]
+fn bpm_round_trip_flac() {
+    bpm_round_trip("silence.flac", "bpm_round_trip_flac");
+}
+
+#[test]
+fn clear_numeric_fields() {
+    let path = scratch_copy("silence.mp3", "clear_numeric_fields");
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.tag_mut().set_year(1999).unwrap();
+        file.tag_mut().set_track(3).unwrap();
+        file.tag_mut().set_bpm(120).unwrap();
+        file.save().unwrap();
+    }
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.tag_mut().set_year(None).unwrap();
+        file.tag_mut().set_track(None).unwrap();
+        file.tag_mut().set_bpm(Some(0)).unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.tag().year(), None);
+    assert_eq!(file.tag().track(), None);
+    assert_eq!(file.tag().bpm(), None);
+}
+
+#[test]
+fn absent_string_fields() {
+    let path = scratch_copy("silence.flac", "absent_string_fields");
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.tag().title_opt().unwrap(), None);
+        assert_eq!(file.tag().artist_opt().unwrap(), None);
+        file.tag_mut().set_title("Silence").unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.tag().title_opt().unwrap(), Some("Silence".to_owned()));
+    assert_eq!(file.tag().artist_opt().unwrap(), None);
+}
+
+#[test]
+fn clear_all_fields() {
+    let path = scratch_copy("silence.mp3", "clear_all_fields");
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.tag_mut().set_title("Title").unwrap();
+        file.tag_mut().set_artist("Artist").unwrap();
+        file.tag_mut().set_album("Album").unwrap();
+        file.tag_mut().set_comment("Comment").unwrap();
+        file.tag_mut().set_genre("Genre").unwrap();
+        file.tag_mut().set_year(2001).unwrap();
+        file.tag_mut().set_track(7).unwrap();
+        file.tag_mut().set_bpm(90).unwrap();
+        file.save().unwrap();
+    }
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.tag_mut().clear().unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    let tag = file.tag();
+    assert_eq!(tag.title_opt().unwrap(), None);
+    assert_eq!(tag.artist_opt().unwrap(), None);
+    assert_eq!(tag.album_opt().unwrap(), None);
+    assert_eq!(tag.comment_opt().unwrap(), None);
+    assert_eq!(tag.genre_opt().unwrap(), None);
+    assert_eq!(tag.year(), None);
+    assert_eq!(tag.track(), None);
+    assert_eq!(tag.bpm(), None);
+}
+
+#[test]
+fn snapshot_outlives_file() {
+    let path = scratch_copy("silence.flac", "snapshot_outlives_file");
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.tag_mut().set_artist("Artist").unwrap();
+        file.tag_mut().set_year(2001).unwrap();
+        file.save().unwrap();
+    }
+    let (data, errors) = {
+        let file = TagLibFile::new(&path).unwrap();
+        file.tag().to_tag_data()
+    };
+    assert!(errors.is_empty());
+    assert_eq!(data.artist, Some("Artist".to_owned()));
+    assert_eq!(data.year, Some(2001));
+    assert_eq!(data.title, None);
+}
+
+#[test]
+fn apply_round_trip() {
+    let path = scratch_copy("silence.mp3", "apply_round_trip");
+    let data = TagData {
+        title: Some("Title".to_owned()),
+        artist: Some("Artist".to_owned()),
+        album: Some("Album".to_owned()),
+        comment: Some("Comment".to_owned()),
+        genre: Some("Genre".to_owned()),
+        year: Some(1984),
+        track: Some(2),
+        bpm: Some(140),
+        composer: Some("Composer".to_owned()),
+        ..TagData::default()
+    };
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.tag_mut().apply(&data).unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    let (read, errors) = file.tag().to_tag_data();
+    assert!(errors.is_empty());
+    assert_eq!(read.title, data.title);
+    assert_eq!(read.artist, data.artist);
+    assert_eq!(read.album, data.album);
+    assert_eq!(read.comment, data.comment);
+    assert_eq!(read.genre, data.genre);
+    assert_eq!(read.year, data.year);
+    assert_eq!(read.track, data.track);
+    assert_eq!(read.bpm, data.bpm);
+    assert_eq!(read.composer, data.composer);
+}
+
+#[test]
+fn apply_reports_failing_field() {
+    let path = scratch_copy("silence.mp3", "apply_reports_failing_field");
+    let mut file = TagLibFile::new(&path).unwrap();
+    let data = TagData {
+        title: Some("Title".to_owned()),
+        comment: Some("bad\0comment".to_owned()),
+        ..TagData::default()
+    };
+    let error = file.tag_mut().apply(&data).unwrap_err();
+    assert_eq!(error.field, TagField::Comment);
+    match error.kind {
+        taglib::SetErrorKind::InteriorNul(_) => (),
+        other => panic!("unexpected error {:?}", other),
+    }
+    // nothing should have been written
+    assert_eq!(file.tag().title_opt().unwrap(), None);
+}
+
+#[test]
+fn edit_builder_saves_once() {
+    let path = scratch_copy("silence.flac", "edit_builder_saves_once");
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.edit().title("Foo").artist("Bar").year(2001).save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.tag().title().unwrap(), "Foo");
+    assert_eq!(file.tag().artist().unwrap(), "Bar");
+    assert_eq!(file.tag().year(), Some(2001));
+}
+
+#[test]
+fn edit_builder_rejects_nul() {
+    let path = scratch_copy("silence.flac", "edit_builder_rejects_nul");
+    let mut file = TagLibFile::new(&path).unwrap();
+    let result = file.edit().title("Foo").artist("B\0ar").save();
+    match result {
+        Err(taglib::EditError::InvalidField(error)) => assert_eq!(error.field, TagField::Artist),
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert_eq!(file.tag().title_opt().unwrap(), None);
+}
+
+#[test]
+fn read_tags_one_shot() {
+    let path = scratch_copy("silence.flac", "read_tags_one_shot");
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.tag_mut().set_album("Album").unwrap();
+        file.save().unwrap();
+    }
+    let data = taglib::read_tags(&path).unwrap();
+    assert_eq!(data.album, Some("Album".to_owned()));
+}
+
+#[test]
+fn read_tags_missing_file() {
+    match taglib::read_tags("/nonexistent/file.mp3") {
+        Err(taglib::FileError::Io(path, err)) => {
+            assert_eq!(path, PathBuf::from("/nonexistent/file.mp3"));
+            assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn update_tags_saves_on_success() {
+    let path = scratch_copy("silence.mp3", "update_tags_saves_on_success");
+    taglib::update_tags(&path, |tag| tag.set_genre("Ambient")).unwrap();
+    assert_eq!(taglib::read_tags(&path).unwrap().genre, Some("Ambient".to_owned()));
+}
+
+#[test]
+fn update_tags_skips_save_on_failure() {
+    let path = scratch_copy("silence.mp3", "update_tags_skips_save_on_failure");
+    let result = taglib::update_tags(&path, |tag| {
+        tag.set_genre("Ambient").unwrap();
+        Err("changed my mind")
+    });
+    match result {
+        Err(taglib::UpdateError::Edit("changed my mind")) => (),
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert_eq!(taglib::read_tags(&path).unwrap().genre, None);
+}
+
+#[test]
+fn forwarding_and_tag_accessors_agree() {
+    let path = scratch_copy("silence.mp3", "forwarding_and_tag_accessors_agree");
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_title("Forwarded").unwrap();
+    file.tag_mut().set_artist("Direct").unwrap();
+    file.set_year(2010).unwrap();
+    assert_eq!(file.tag().title().unwrap(), "Forwarded");
+    assert_eq!(file.artist().unwrap(), "Direct");
+    assert_eq!(file.tag().year(), file.year());
+}
+
+#[test]
+fn setters_mark_tag_modified() {
+    let path = scratch_copy("silence.mp3", "setters_mark_tag_modified");
+    let mut file = TagLibFile::new(&path).unwrap();
+    assert!(!file.tag().is_modified());
+    file.tag_mut().set_track(1).unwrap();
+    assert!(file.tag().is_modified());
+    file.save().unwrap();
+    assert!(!file.tag().is_modified());
+}
+
+#[test]
+fn unchanged_values_skip_save() {
+    let path = scratch_copy("silence.mp3", "unchanged_values_skip_save");
+    let podcast = taglib::PodcastInfo {
+        podcast: true,
+        category: Some("Technology".to_owned()),
+        ..Default::default()
+    };
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.set_title("Same").unwrap();
+        file.set_year(2000).unwrap();
+        file.set_album_artist("Same").unwrap();
+        file.set_rating(80).unwrap();
+        file.set_moods(&["Calm", "Happy"]).unwrap();
+        file.set_podcast_info(&podcast).unwrap();
+        file.save().unwrap();
+    }
+    let before = fs::metadata(&path).unwrap().modified().unwrap();
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_title("Same").unwrap();
+    file.set_year(2000).unwrap();
+    file.set_album_artist("Same").unwrap();
+    file.set_rating(80).unwrap();
+    file.set_moods(&["Calm", "Happy"]).unwrap();
+    file.set_podcast_info(&podcast).unwrap();
+    file.set_composer(None).unwrap();
+    assert!(!file.is_modified());
+    file.save().unwrap();
+    let after = fs::metadata(&path).unwrap().modified().unwrap();
+    assert_eq!(before, after);
+}
+
+#[test]
+fn reload_sees_external_changes() {
+    let path = scratch_copy("silence.flac", "reload_sees_external_changes");
+    let mut viewer = TagLibFile::new(&path).unwrap();
+    assert_eq!(viewer.title_opt().unwrap(), None);
+    {
+        let mut editor = TagLibFile::new(&path).unwrap();
+        editor.set_title("Edited elsewhere").unwrap();
+        editor.save().unwrap();
+    }
+    viewer.reload().unwrap();
+    assert_eq!(viewer.title().unwrap(), "Edited elsewhere");
+}
+
+#[test]
+fn reload_keeps_state_when_file_disappears() {
+    let path = scratch_copy("silence.flac", "reload_keeps_state_when_file_disappears");
+    let mut file = TagLibFile::new(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(file.reload().is_err());
+    assert_eq!(file.title_opt().unwrap(), None);
+}
+
+#[test]
+fn path_is_kept_as_given() {
+    let path = scratch_copy("silence.mp3", "path_is_kept_as_given");
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.path(), path.as_path());
+}
+
+#[test]
+fn display_fully_tagged() {
+    let data = TagData {
+        title: Some("Title".to_owned()),
+        artist: Some("Artist".to_owned()),
+        album: Some("Album".to_owned()),
+        year: Some(2003),
+        track: Some(5),
+        ..TagData::default()
+    };
+    assert_eq!(data.to_string(), "Artist – Title (Album, 2003) [#5]");
+}
+
+#[test]
+fn display_partially_tagged() {
+    let data = TagData {
+        title: Some("Title".to_owned()),
+        year: Some(2003),
+        ..TagData::default()
+    };
+    assert_eq!(data.to_string(), "Title (2003)");
+}
+
+#[test]
+fn display_untagged() {
+    assert_eq!(TagData::default().to_string(), "(untagged)");
+    let path = scratch_copy("silence.flac", "display_untagged");
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.tag().to_string(), "(untagged)");
+}
+
+#[test]
+fn snapshot_equality_distinguishes_none_and_empty() {
+    let empty = TagData {
+        title: Some(String::new()),
+        ..TagData::default()
+    };
+    assert_ne!(empty, TagData::default());
+    assert_eq!(empty.clone(), empty);
+}
+
+#[test]
+fn snapshot_equality_is_exact_for_unicode() {
+    let composed = TagData {
+        artist: Some("Beyonc\u{e9}".to_owned()),
+        ..TagData::default()
+    };
+    let decomposed = TagData {
+        artist: Some("Beyonce\u{301}".to_owned()),
+        ..TagData::default()
+    };
+    let lowercase = TagData {
+        artist: Some("beyonc\u{e9}".to_owned()),
+        ..TagData::default()
+    };
+    assert_ne!(composed, decomposed);
+    assert_ne!(composed, lowercase);
+}
+
+#[test]
+fn snapshots_dedupe_in_a_set() {
+    let mut set = std::collections::HashSet::new();
+    let data = TagData {
+        title: Some("Title".to_owned()),
+        year: Some(1999),
+        ..TagData::default()
+    };
+    set.insert(data.clone());
+    set.insert(data);
+    assert_eq!(set.len(), 1);
+}
+
+#[test]
+fn file_tags_equal_snapshot() {
+    let path = scratch_copy("silence.mp3", "file_tags_equal_snapshot");
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_title("Title").unwrap();
+    let (snapshot, _) = file.tag().to_tag_data();
+    assert!(file.tags_equal(&snapshot).unwrap());
+    file.set_title("Other").unwrap();
+    assert!(!file.tags_equal(&snapshot).unwrap());
+}
+
+#[test]
+fn debug_shows_path_and_state() {
+    let path = scratch_copy("silence.mp3", "debug_shows_path_and_state");
+    let mut file = TagLibFile::new(&path).unwrap();
+    assert_eq!(
+        format!("{:?}", file),
+        format!("TagLibFile {{ path: {:?}, file_type: Some(Mpeg), valid: true, modified: false, read_only: false }}", path)
+    );
+    file.set_title("Title").unwrap();
+    assert_eq!(
+        format!("{:?}", file),
+        format!("TagLibFile {{ path: {:?}, file_type: Some(Mpeg), valid: true, modified: true, read_only: false }}", path)
+    );
+    assert_eq!(format!("{:?}", file.tag()), "TagLibTag { modified: true }");
+}
+
+#[test]
+fn setter_errors_name_the_field() {
+    let path = scratch_copy("silence.mp3", "setter_errors_name_the_field");
+    let mut file = TagLibFile::new(&path).unwrap();
+    let error = file.set_album("Al\0bum").unwrap_err();
+    assert_eq!(error.field, TagField::Album);
+    assert!(file.set_year(2000).is_ok());
+}
+
+fn write_with_nul_handling(mode: taglib::NulHandling, name: &str) -> Result<String, taglib::SetError> {
+    let path = scratch_copy("silence.flac", name);
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.set_nul_handling(mode);
+        file.set_title("before\0after")?;
+        file.save().unwrap();
+    }
+    Ok(TagLibFile::new(&path).unwrap().title().unwrap())
+}
+
+#[test]
+fn nul_handling_error() {
+    let error = write_with_nul_handling(taglib::NulHandling::Error, "nul_handling_error").unwrap_err();
+    assert_eq!(error.field, TagField::Title);
+    match error.kind {
+        taglib::SetErrorKind::InteriorNul(err) => assert_eq!(err.nul_position(), 6),
+        other => panic!("unexpected error {:?}", other),
+    }
+}
+
+#[test]
+fn nul_handling_truncate() {
+    let title = write_with_nul_handling(taglib::NulHandling::TruncateAtNul, "nul_handling_truncate");
+    assert_eq!(title.unwrap(), "before");
+}
+
+#[test]
+fn nul_handling_strip() {
+    let title = write_with_nul_handling(taglib::NulHandling::StripNuls, "nul_handling_strip");
+    assert_eq!(title.unwrap(), "beforeafter");
+}
+
+#[test]
+fn lossy_reads_of_malformed_frames() {
+    let path = scratch_copy("invalid_utf8.mp3", "lossy_reads_of_malformed_frames");
+    let file = TagLibFile::new(&path).unwrap();
+    // the frame claims to be UTF-8, but is really latin-1 ("Caf\xe9 Bl\xfc"). taglib
+    // decodes it before we see it, emptying it (or before 1.11, cutting it off at the
+    // first bad byte), so the lossy getters get valid UTF-8 with nothing to replace
+    let expected = if taglib::version() >= taglib::Version::new(1, 11, 0) { "" } else { "Caf" };
+    assert_eq!(file.title_lossy(), expected);
+    assert_eq!(file.title().unwrap(), expected);
+    assert_eq!(file.artist_lossy(), "");
+}
+
+#[test]
+fn raw_bytes_match_strings() {
+    let path = scratch_copy("silence.flac", "raw_bytes_match_strings");
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_artist("Sigur R\u{f3}s").unwrap();
+    assert_eq!(file.artist_bytes(), "Sigur R\u{f3}s".as_bytes());
+    assert_eq!(file.title_bytes(), b"");
+}
+
+#[test]
+fn borrowed_strings_match_owned() {
+    let path = scratch_copy("silence.mp3", "borrowed_strings_match_owned");
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_artist("Artist").unwrap();
+    let artist = file.tag().artist_ref().unwrap();
+    assert_eq!(&*artist, "Artist");
+    assert_eq!(artist.to_bytes(), b"Artist");
+    assert!(artist.starts_with("Art"));
+}
+
+#[test]
+fn untagged_file_is_empty() {
+    let path = scratch_copy("silence.mp3", "untagged_file_is_empty");
+    let file = TagLibFile::new(&path).unwrap();
+    assert!(file.tag().is_empty());
+}
+
+#[test]
+fn year_only_is_not_empty() {
+    let path = scratch_copy("silence.mp3", "year_only_is_not_empty");
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_year(1970).unwrap();
+    assert!(!file.tag().is_empty());
+}
+
+#[test]
+fn copy_tags_between_formats() {
+    let source_path = scratch_copy("silence.flac", "copy_tags_between_formats_source");
+    let dest_path = scratch_copy("silence.mp3", "copy_tags_between_formats_dest");
+    let mut source = TagLibFile::new(&source_path).unwrap();
+    source.set_title("Title").unwrap();
+    source.set_artist("Artist").unwrap();
+    source.set_year(1967).unwrap();
+    source.set_bpm(100).unwrap();
+    {
+        let mut dest = TagLibFile::new(&dest_path).unwrap();
+        source.copy_tags_to(&mut dest).unwrap();
+        dest.save().unwrap();
+    }
+    let (copied, _) = TagLibFile::new(&dest_path).unwrap().tag().to_tag_data();
+    let (original, _) = source.tag().to_tag_data();
+    assert_eq!(copied, original);
+}
+
+#[test]
+fn diff_reports_changed_fields() {
+    let current = TagData {
+        artist: Some("Beatles".to_owned()),
+        title: Some("Help!".to_owned()),
+        ..TagData::default()
+    };
+    let desired = TagData {
+        artist: Some("The Beatles".to_owned()),
+        title: Some("Help!".to_owned()),
+        year: Some(1967),
+        ..TagData::default()
+    };
+    assert_eq!(
+        current.diff(&desired, DiffOptions::default()),
+        vec![
+            FieldChange {
+                field: TagField::Artist,
+                current: Some(FieldValue::Text("Beatles".to_owned())),
+                desired: Some(FieldValue::Text("The Beatles".to_owned())),
+            },
+            FieldChange {
+                field: TagField::Year,
+                current: None,
+                desired: Some(FieldValue::Number(1967)),
+            },
+        ]
+    );
+}
+
+#[test]
+fn diff_treats_empty_as_unset() {
+    let current = TagData::default();
+    let desired = TagData {
+        title: Some(String::new()),
+        year: Some(0),
+        ..TagData::default()
+    };
+    assert!(current.diff(&desired, DiffOptions::default()).is_empty());
+}
+
+#[test]
+fn diff_options_loosen_comparison() {
+    let current = TagData {
+        artist: Some("the  beatles ".to_owned()),
+        ..TagData::default()
+    };
+    let desired = TagData {
+        artist: Some("The Beatles".to_owned()),
+        ..TagData::default()
+    };
+    assert_eq!(current.diff(&desired, DiffOptions::default()).len(), 1);
+    let options = DiffOptions {
+        ignore_case: true,
+        ignore_whitespace: true,
+    };
+    assert!(current.diff(&desired, options).is_empty());
+}
+
+#[test]
+fn cloned_handles_are_independent() {
+    let path = scratch_copy("silence.flac", "cloned_handles_are_independent");
+    let mut first = TagLibFile::new(&path).unwrap();
+    let mut second = first.try_clone().unwrap();
+    first.set_title("From first").unwrap();
+    second.set_artist("From second").unwrap();
+    assert_eq!(second.title_opt().unwrap(), None);
+    first.save().unwrap();
+    // the second handle was read before the first save, so its save wins
+    second.save().unwrap();
+    let data = taglib::read_tags(&path).unwrap();
+    assert_eq!(data.title, None);
+    assert_eq!(data.artist, Some("From second".to_owned()));
+}
+
+#[test]
+fn unknown_format_is_an_open_failure() {
+    let source = scratch_copy("silence.mp3", "unknown_format_is_an_open_failure");
+    let path = source.with_extension("jpg");
+    fs::rename(&source, &path).unwrap();
+    match TagLibFile::new(&path) {
+        Err(taglib::FileError::OpenFailure(ref failed)) => assert_eq!(failed, &path),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn file_errors_chain_their_source() {
+    use std::error::Error;
+    let err = TagLibFile::new("/nonexistent/file.mp3").unwrap_err();
+    assert!(err.source().is_some());
+    assert!(err.to_string().contains("/nonexistent/file.mp3"));
+}
+
+#[test]
+fn open_extensionless_file_with_type() {
+    let source = scratch_copy("silence.mp3", "open_extensionless_file_with_type");
+    let path = source.with_extension("");
+    fs::rename(&source, &path).unwrap();
+    assert!(TagLibFile::new(&path).is_err());
+    let file = TagLibFile::new_with_type(&path, taglib::FileType::Mpeg).unwrap();
+    assert!(file.tag().is_empty());
+}
+
+#[test]
+fn wrong_type_hint_is_invalid() {
+    let path = scratch_copy("silence.mp3", "wrong_type_hint_is_invalid");
+    match TagLibFile::new_with_type(&path, taglib::FileType::Flac) {
+        Err(taglib::FileError::InvalidTagFile(_)) => (),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn file_types_from_odd_paths() {
+    use taglib::FileType;
+    let cases = [
+        ("FOO.MP3", Some(FileType::Mpeg)),
+        ("track.opus", Some(FileType::Opus)),
+        ("album/01 - intro.Flac", Some(FileType::Flac)),
+        ("book.m4b", Some(FileType::Mp4)),
+        ("voice.OGA", Some(FileType::OggVorbis)),
+        ("old.wma", Some(FileType::Asf)),
+        ("take.aiff", Some(FileType::Aiff)),
+        ("take.wav", Some(FileType::Wav)),
+        ("noext", None),
+        ("cover.jpg", None),
+        (".mp3", None),
+        ("archive.mp3.gz", None),
+    ];
+    for &(path, expected) in cases.iter() {
+        assert_eq!(FileType::from_path(path), expected, "{}", path);
+    }
+}
+
+#[test]
+fn file_types_round_trip_through_strings() {
+    use taglib::FileType;
+    for &file_type in [FileType::Mpeg, FileType::OggVorbis, FileType::Mp4, FileType::Wav].iter() {
+        assert_eq!(file_type.to_string().parse::<FileType>(), Ok(file_type));
+    }
+    assert_eq!("FLAC".parse::<FileType>(), Ok(FileType::Flac));
+    assert!("jpeg".parse::<FileType>().is_err());
+}
+
+#[test]
+#[cfg(windows)]
+fn opens_non_ascii_path_on_windows() {
+    let source = scratch_copy("silence.mp3", "wide-path");
+    let destination = source.with_file_name("taglib-rs-静かな曲.mp3");
+    fs::rename(&source, &destination).unwrap();
+    let opened = TagLibFile::new(&destination).is_ok();
+    fs::remove_file(&destination).unwrap();
+    assert!(opened);
+}
+
+#[test]
+#[cfg(unix)]
+fn tags_non_utf8_path_on_unix() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let source = scratch_copy("silence.mp3", "latin1-path");
+    // "café" in latin-1, which isn't valid UTF-8
+    let name = OsStr::from_bytes(b"taglib-rs-caf\xe9.mp3");
+    let destination = source.with_file_name(name);
+    fs::rename(&source, &destination).unwrap();
+    {
+        let mut file = TagLibFile::new(&destination).unwrap();
+        file.set_title("Café").unwrap();
+        file.save().unwrap();
+    }
+    let title = TagLibFile::new(&destination).unwrap().title().unwrap();
+    fs::remove_file(&destination).unwrap();
+    assert_eq!(title, "Café");
+}
+
+#[test]
+fn read_only_file_refuses_to_save() {
+    let path = scratch_copy("silence.mp3", "read-only");
+    let before = fs::read(&path).unwrap();
+    let mut file = TagLibFile::open_read_only(&path).unwrap();
+    assert!(file.is_read_only());
+    file.set_title("Not written").unwrap();
+    match file.save() {
+        Err(taglib::FileError::ReadOnly(ref failed)) => assert_eq!(failed, &path),
+        other => panic!("expected a read only error, got {:?}", other),
+    }
+    assert!(file.try_clone().unwrap().is_read_only());
+    assert_eq!(fs::read(&path).unwrap(), before);
+}
+
+#[test]
+fn open_options_defaults_match_new() {
+    let path = scratch_copy("silence.flac", "open_options_defaults");
+    let file = OpenOptions::new().open(&path).unwrap();
+    assert_eq!(file.options(), TagLibFile::new(&path).unwrap().options());
+    assert!(!file.is_read_only());
+    assert!(file.audio_properties().is_some());
+}
+
+#[test]
+fn open_options_file_type() {
+    let source = scratch_copy("silence.flac", "open_options_file_type");
+    let path = source.with_extension("audio");
+    fs::rename(&source, &path).unwrap();
+    assert!(OpenOptions::new().open(&path).is_err());
+    let file = OpenOptions::new().file_type(taglib::FileType::Flac).open(&path).unwrap();
+    assert!(file.audio_properties().is_some());
+}
+
+#[test]
+fn open_options_read_only() {
+    let path = scratch_copy("silence.flac", "open_options_read_only");
+    let mut file = OpenOptions::new().read_only(true).open(&path).unwrap();
+    file.set_title("Not written").unwrap();
+    assert!(file.save().is_err());
+    assert!(file.reload().is_ok());
+    assert!(file.is_read_only());
+}
+
+#[test]
+fn open_options_skip_audio_properties() {
+    let path = scratch_copy("silence.flac", "open_options_skip_audio_properties");
+    let file = OpenOptions::new().read_audio_properties(None).open(&path).unwrap();
+    assert!(file.audio_properties().is_none());
+    let file = OpenOptions::new().read_audio_properties(ReadStyle::Accurate).open(&path).unwrap();
+    assert_eq!(file.audio_properties().unwrap().duration().as_millis(), 1500);
+}
+
+#[test]
+fn open_options_reused_for_many_files() {
+    let mut options = OpenOptions::new();
+    options
+        .file_type(taglib::FileType::Mpeg)
+        .read_only(true)
+        .read_audio_properties(ReadStyle::Fast);
+    let scanner = options.clone();
+    for name in &["open_options_combined_a", "open_options_combined_b"] {
+        let path = scratch_copy("silence.mp3", name);
+        let file = scanner.open(&path).unwrap();
+        assert!(file.is_read_only());
+        assert!(file.audio_properties().is_some());
+        assert_eq!(file.options(), &options);
+    }
+}
+
+// whether the bytes appear anywhere in the file
+fn file_contains(path: &PathBuf, needle: &[u8]) -> bool {
+    fs::read(path).unwrap().windows(needle.len()).any(|window| window == needle)
+}
+
+#[test]
+fn latin1_policy_writes_latin1_bytes() {
+    let path = scratch_copy("silence.mp3", "latin1_policy_writes");
+    let mut file = OpenOptions::new()
+        .string_encoding(StringEncodingPolicy::Latin1)
+        .open(&path)
+        .unwrap();
+    file.set_title("Caf\u{e9}").unwrap();
+    file.save().unwrap();
+    assert!(file_contains(&path, b"Caf\xe9"));
+    assert_eq!(TagLibFile::new(&path).unwrap().title().unwrap(), "Caf\u{e9}");
+}
+
+#[test]
+fn latin1_policy_rejects_other_characters() {
+    let path = scratch_copy("silence.mp3", "latin1_policy_rejects");
+    let mut file = OpenOptions::new()
+        .string_encoding(StringEncodingPolicy::Latin1)
+        .open(&path)
+        .unwrap();
+    let error = file.set_title("\u{6771}\u{4eac}").unwrap_err();
+    assert_eq!(error.field, TagField::Title);
+    match error.kind {
+        taglib::SetErrorKind::NotLatin1(c) => assert_eq!(c, '\u{6771}'),
+        other => panic!("unexpected error {:?}", other),
+    }
+    assert!(!file.is_modified());
+}
+
+#[test]
+fn unicode_policy_writes_unicode_bytes() {
+    let path = scratch_copy("silence.mp3", "unicode_policy_writes");
+    let mut file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.tag().string_encoding(), StringEncodingPolicy::Unicode);
+    file.set_title("\u{6771}\u{4eac}").unwrap();
+    file.save().unwrap();
+    // taglib can't use Latin-1 for this, so the (ID3v2.4) frame is UTF-8
+    assert!(file_contains(&path, "\u{6771}\u{4eac}".as_bytes()));
+    assert_eq!(TagLibFile::new(&path).unwrap().title().unwrap(), "\u{6771}\u{4eac}");
+}
+
+// the text encoding byte of the first frame with the given id
+fn id3v2_frame_encoding(path: &PathBuf, id: &[u8; 4]) -> u8 {
+    let bytes = fs::read(path).unwrap();
+    let position = bytes.windows(4).position(|window| window == id).expect("frame not found");
+    // after the id come four bytes of size and two of flags
+    bytes[position + 10]
+}
+
+fn write_with_id3v2_encoding(encoding: taglib::Id3v2Encoding, name: &str) -> PathBuf {
+    let path = scratch_copy("silence.mp3", name);
+    let mut file = OpenOptions::new().id3v2_encoding(encoding).open(&path).unwrap();
+    file.set_title("Caf\u{e9}").unwrap();
+    file.set_comment("Comment").unwrap();
+    file.save().unwrap();
+    path
+}
+
+#[test]
+fn id3v2_encoding_latin1() {
+    let path = write_with_id3v2_encoding(taglib::Id3v2Encoding::Latin1, "id3v2_encoding_latin1");
+    assert_eq!(id3v2_frame_encoding(&path, b"TIT2"), 0);
+    assert_eq!(id3v2_frame_encoding(&path, b"COMM"), 0);
+}
+
+#[test]
+fn id3v2_encoding_utf16() {
+    let path = write_with_id3v2_encoding(taglib::Id3v2Encoding::Utf16, "id3v2_encoding_utf16");
+    assert_eq!(id3v2_frame_encoding(&path, b"TIT2"), 1);
+    assert_eq!(id3v2_frame_encoding(&path, b"COMM"), 1);
+}
+
+#[test]
+fn id3v2_encoding_utf8() {
+    let path = write_with_id3v2_encoding(taglib::Id3v2Encoding::Utf8, "id3v2_encoding_utf8");
+    assert_eq!(id3v2_frame_encoding(&path, b"TIT2"), 3);
+    assert_eq!(TagLibFile::new(&path).unwrap().title().unwrap(), "Caf\u{e9}");
+}
+
+#[test]
+fn id3v2_encoding_applies_to_existing_frames() {
+    let path = write_with_id3v2_encoding(taglib::Id3v2Encoding::Utf8, "id3v2_encoding_existing");
+    let mut file = OpenOptions::new()
+        .id3v2_encoding(taglib::Id3v2Encoding::Utf16)
+        .open(&path)
+        .unwrap();
+    file.set_artist("Artist").unwrap();
+    file.save().unwrap();
+    assert_eq!(id3v2_frame_encoding(&path, b"TIT2"), 1);
+}
+
+#[test]
+fn version_orders_and_displays() {
+    use taglib::Version;
+    assert!(Version::new(1, 13, 1) > Version::new(1, 9, 0));
+    assert!(Version::new(2, 0, 0) > Version::new(1, 13, 1));
+    assert_eq!(Version::new(1, 13, 1).to_string(), "1.13.1");
+    assert!(!Version::new(1, 9, 1).supports_millisecond_lengths());
+    assert!(Version::new(1, 10, 0).supports_millisecond_lengths());
+}
+
+#[test]
+fn linked_version_matches_duration_precision() {
+    let path = scratch_copy("silence.flac", "linked_version");
+    let file = TagLibFile::new(&path).unwrap();
+    let precision = file.audio_properties().unwrap().precision();
+    assert!(taglib::version().major >= 1);
+    assert_eq!(
+        taglib::version().supports_millisecond_lengths(),
+        precision == taglib::DurationPrecision::Milliseconds
+    );
+}
+
+#[test]
+fn open_from_bytes() {
+    let path = scratch_copy("silence.mp3", "open_from_bytes");
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.set_title("In memory").unwrap();
+        file.save().unwrap();
+    }
+    let data = fs::read(&path).unwrap();
+    let mut file = TagLibFile::from_bytes(&data, taglib::FileType::Mpeg).unwrap();
+    drop(data);
+    assert_eq!(file.title().unwrap(), "In memory");
+    assert!(file.audio_properties().is_some());
+    assert_eq!(file.try_clone().unwrap().title().unwrap(), "In memory");
+    // there's nowhere to save to
+    assert!(file.is_read_only());
+    file.set_title("Changed").unwrap();
+    assert!(file.save().is_err());
+}
+
+#[test]
+fn open_from_bytes_needs_a_type() {
+    let data = fs::read(scratch_copy("silence.flac", "open_from_bytes_needs_a_type")).unwrap();
+    match OpenOptions::new().open_bytes(&data) {
+        Err(taglib::FileError::OpenFailure(_)) => (),
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert!(OpenOptions::new().file_type(taglib::FileType::Flac).open_bytes(&data).is_ok());
+}
+
+#[test]
+#[cfg(unix)]
+fn open_from_std_file() {
+    let path = scratch_copy("silence.mp3", "open_from_std_file");
+    let std_file = fs::OpenOptions::new().read(true).write(true).open(&path).unwrap();
+    let mut file = TagLibFile::from_file(std_file, taglib::FileType::Mpeg).unwrap();
+    file.set_title("Through a descriptor").unwrap();
+    file.save().unwrap();
+    assert_eq!(file.try_clone().unwrap().title().unwrap(), "Through a descriptor");
+    drop(file);
+    assert_eq!(TagLibFile::new(&path).unwrap().title().unwrap(), "Through a descriptor");
+}
+
+#[test]
+#[cfg(unix)]
+fn read_only_std_file_fails_to_save() {
+    let path = scratch_copy("silence.mp3", "read_only_std_file");
+    let mut file = TagLibFile::from_file(fs::File::open(&path).unwrap(), taglib::FileType::Mpeg).unwrap();
+    file.set_title("Not written").unwrap();
+    assert!(file.save().is_err());
+    assert!(TagLibFile::new(&path).unwrap().tag().is_empty());
+}
+
+#[test]
+fn directory_is_rejected() {
+    let dir = env::temp_dir().join("taglib-rs-directory.mp3");
+    let _ = fs::create_dir(&dir);
+    match TagLibFile::new(&dir) {
+        Err(taglib::FileError::IsADirectory(ref path)) => assert_eq!(path, &dir),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+#[cfg(unix)]
+fn symlink_to_directory_is_rejected() {
+    let dir = env::temp_dir().join("taglib-rs-symlinked-directory");
+    let _ = fs::create_dir(&dir);
+    let link = env::temp_dir().join("taglib-rs-directory-link.mp3");
+    let _ = fs::remove_file(&link);
+    std::os::unix::fs::symlink(&dir, &link).unwrap();
+    match TagLibFile::new(&link) {
+        Err(taglib::FileError::IsADirectory(_)) => (),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+#[cfg(unix)]
+fn fifo_is_rejected() {
+    let fifo = env::temp_dir().join("taglib-rs-fifo.mp3");
+    let _ = fs::remove_file(&fifo);
+    let status = std::process::Command::new("mkfifo").arg(&fifo).status().unwrap();
+    assert!(status.success());
+    match TagLibFile::new(&fifo) {
+        Err(taglib::FileError::NotARegularFile(_)) => (),
+        other => panic!("unexpected result {:?}", other),
+    }
+    fs::remove_file(&fifo).unwrap();
+}
+
+#[test]
+fn save_as_leaves_source_untouched() {
+    let path = scratch_copy("silence.mp3", "save_as_source");
+    let dest = env::temp_dir().join("taglib-rs-save_as_dest.mp3");
+    let _ = fs::remove_file(&dest);
+    let before = fs::read(&path).unwrap();
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_title("Edited").unwrap();
+    let copy = file.save_as(&dest).unwrap();
+    assert_eq!(copy.path(), dest.as_path());
+    assert!(!copy.is_modified());
+    assert!(file.is_modified());
+    assert_eq!(fs::read(&path).unwrap(), before);
+    assert_eq!(TagLibFile::new(&dest).unwrap().title().unwrap(), "Edited");
+}
+
+#[test]
+fn save_as_refuses_to_overwrite_by_default() {
+    let path = scratch_copy("silence.mp3", "save_as_overwrite_source");
+    let dest = scratch_copy("silence.mp3", "save_as_overwrite_dest");
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_title("Edited").unwrap();
+    match file.save_as(&dest) {
+        Err(taglib::FileError::Io(_, ref err)) => assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists),
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert!(TagLibFile::new(&dest).unwrap().tag().is_empty());
+    file.save_as_with(&dest, taglib::SaveOptions::new().overwrite(true)).unwrap();
+    assert_eq!(TagLibFile::new(&dest).unwrap().title().unwrap(), "Edited");
+}
+
+#[test]
+fn save_as_itself_is_an_error() {
+    let path = scratch_copy("silence.mp3", "save_as_itself");
+    let file = TagLibFile::new(&path).unwrap();
+    assert!(file.save_as_with(&path, taglib::SaveOptions::new().overwrite(true)).is_err());
+    assert!(TagLibFile::new(&path).is_ok());
+}
+
+#[test]
+fn save_atomic_replaces_the_file() {
+    let path = scratch_copy("silence.mp3", "save_atomic");
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_title("Atomic").unwrap();
+    file.save_atomic().unwrap();
+    assert!(!file.is_modified());
+    assert_eq!(file.title().unwrap(), "Atomic");
+    assert_eq!(TagLibFile::new(&path).unwrap().title().unwrap(), "Atomic");
+    // saving again goes to the new file, not the one that was replaced
+    file.set_artist("Again").unwrap();
+    file.save_atomic().unwrap();
+    assert_eq!(TagLibFile::new(&path).unwrap().artist().unwrap(), "Again");
+}
+
+#[test]
+#[cfg(unix)]
+fn atomic_save_keeps_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+    let path = scratch_copy("silence.mp3", "save_atomic_permissions");
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_title("Permissions").unwrap();
+    file.save_atomic().unwrap();
+    assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o640);
+}
+
+#[test]
+fn save_as_and_atomic_save_keep_unsaved_pictures() {
+    let path = scratch_copy("silence.flac", "save_pictures");
+    let dest = env::temp_dir().join("taglib-rs-save_pictures_dest.flac");
+    let _ = fs::remove_file(&dest);
+    let cover = png_cover();
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_cover_art(&cover).unwrap();
+    drop(file.save_as(&dest).unwrap());
+    assert_eq!(TagLibFile::new(&dest).unwrap().cover_art().unwrap().unwrap().data, cover.data);
+    file.save_atomic().unwrap();
+    assert_eq!(TagLibFile::new(&path).unwrap().cover_art().unwrap().unwrap().data, cover.data);
+}
+
+// remove any backups left by an earlier run
+fn remove_backups(path: &Path, suffix: &str) {
+    let name = path.file_name().unwrap().to_string_lossy().into_owned();
+    for entry in fs::read_dir(path.parent().unwrap()).unwrap().filter_map(|entry| entry.ok()) {
+        if entry.file_name().to_string_lossy().starts_with(&format!("{}{}", name, suffix)) {
+            fs::remove_file(entry.path()).unwrap();
+        }
+    }
+}
+
+fn save_title_with_backup(path: &PathBuf, title: &str, mode: &taglib::BackupMode) {
+    let mut file = TagLibFile::new(path).unwrap();
+    file.set_title(title).unwrap();
+    file.save_with(taglib::SaveOptions::new().backup(mode.clone())).unwrap();
+}
+
+// backups don't have the extension of the original, so the type has to be given
+fn title_of(path: PathBuf) -> String {
+    TagLibFile::new_with_type(path, taglib::FileType::Mpeg).unwrap().title().unwrap()
+}
+
+#[test]
+fn backup_keeps_first() {
+    let path = scratch_copy("silence.mp3", "backup_keeps_first");
+    remove_backups(&path, ".bak");
+    let mode = taglib::BackupMode::Suffix(".bak".to_owned());
+    save_title_with_backup(&path, "First", &mode);
+    save_title_with_backup(&path, "Second", &mode);
+    let backup = path.with_extension("mp3.bak");
+    assert_eq!(title_of(backup.clone()), "");
+    taglib::restore_from_backup(&path, &mode).unwrap();
+    assert!(TagLibFile::new(&path).unwrap().tag().is_empty());
+}
+
+#[test]
+fn backup_overwrite() {
+    let path = scratch_copy("silence.mp3", "backup_overwrite");
+    remove_backups(&path, ".bak");
+    let mode = taglib::BackupMode::Overwrite(".bak".to_owned());
+    save_title_with_backup(&path, "First", &mode);
+    save_title_with_backup(&path, "Second", &mode);
+    assert_eq!(title_of(path.with_extension("mp3.bak")), "First");
+}
+
+#[test]
+fn backup_numbered() {
+    let path = scratch_copy("silence.mp3", "backup_numbered");
+    remove_backups(&path, ".bak");
+    let mode = taglib::BackupMode::Numbered(".bak".to_owned());
+    save_title_with_backup(&path, "First", &mode);
+    save_title_with_backup(&path, "Second", &mode);
+    assert_eq!(title_of(path.with_extension("mp3.bak.1")), "");
+    assert_eq!(title_of(path.with_extension("mp3.bak.2")), "First");
+    taglib::restore_from_backup(&path, &mode).unwrap();
+    assert_eq!(title_of(path), "First");
+}
+
+#[test]
+fn failed_backup_leaves_file_untouched() {
+    let path = scratch_copy("silence.mp3", "backup_failure");
+    remove_backups(&path, ".bak");
+    // a directory in the way of the backup stops it being made
+    fs::create_dir(path.with_extension("mp3.bak")).unwrap();
+    let before = fs::read(&path).unwrap();
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_title("Not saved").unwrap();
+    let mode = taglib::BackupMode::Overwrite(".bak".to_owned());
+    let options = taglib::SaveOptions::new().backup(mode).clone();
+    assert!(file.save_with(&options).is_err());
+    assert_eq!(fs::read(&path).unwrap(), before);
+    fs::remove_dir(path.with_extension("mp3.bak")).unwrap();
+}
+
+#[test]
+fn restore_without_backup_is_not_found() {
+    let path = scratch_copy("silence.mp3", "backup_missing");
+    remove_backups(&path, ".bak");
+    let err = taglib::restore_from_backup(&path, &taglib::BackupMode::Suffix(".bak".to_owned())).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
+
+#[test]
+fn save_failure_reports_read_only_file() {
+    let path = scratch_copy("silence.mp3", "save_failure_read_only");
+    let original = fs::metadata(&path).unwrap().permissions();
+    let mut permissions = original.clone();
+    permissions.set_readonly(true);
+    fs::set_permissions(&path, permissions).unwrap();
+    // with enough privileges the flag is ignored, and there's no failure to report
+    if fs::OpenOptions::new().write(true).open(&path).is_ok() {
+        return;
+    }
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_title("Read only").unwrap();
+    match file.save() {
+        Err(taglib::FileError::SaveFailure(ref err)) => {
+            assert_eq!(err.kind, taglib::SaveErrorKind::ReadOnlyFile);
+            assert_eq!(err.path, path);
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+    // so that the next run can copy over it
+    fs::set_permissions(&path, original).unwrap();
+}
+
+// an ogg page header with a single segment, followed by the start of its first packet
+fn ogg_page(packet: &[u8]) -> Vec<u8> {
+    let mut page = b"OggS".to_vec();
+    page.extend_from_slice(&[0; 22]);
+    page.extend_from_slice(&[1, packet.len() as u8]);
+    page.extend_from_slice(packet);
+    page
+}
+
+#[test]
+fn file_types_from_headers() {
+    use taglib::FileType;
+    let headers: Vec<(Vec<u8>, Option<FileType>)> = vec![
+        (b"ID3\x04\x00\x00\x00\x00\x00\x00".to_vec(), Some(FileType::Mpeg)),
+        (b"ID3\x04\x00\x00\x00\x00\x00\x00fLaC".to_vec(), Some(FileType::Flac)),
+        (vec![0xff, 0xfb, 0x90, 0xc4], Some(FileType::Mpeg)),
+        // ADTS AAC has the sync bits, but not a valid mpeg layer
+        (vec![0xff, 0xf1, 0x50, 0x80], None),
+        (b"fLaC\x00\x00\x00\x22".to_vec(), Some(FileType::Flac)),
+        (ogg_page(b"\x01vorbis"), Some(FileType::OggVorbis)),
+        (ogg_page(b"OpusHead"), Some(FileType::Opus)),
+        (ogg_page(b"\x7fFLAC"), Some(FileType::OggFlac)),
+        (ogg_page(b"Speex   "), Some(FileType::Speex)),
+        (ogg_page(b"\x80theora"), None),
+        (b"\x00\x00\x00\x20ftypM4A ".to_vec(), Some(FileType::Mp4)),
+        (b"RIFF\x24\x00\x00\x00WAVEfmt ".to_vec(), Some(FileType::Wav)),
+        (b"FORM\x00\x00\x00\x00AIFFCOMM".to_vec(), Some(FileType::Aiff)),
+        (b"FORM\x00\x00\x00\x00AIFCFVER".to_vec(), Some(FileType::Aiff)),
+        (
+            vec![0x30, 0x26, 0xb2, 0x75, 0x8e, 0x66, 0xcf, 0x11, 0xa6, 0xd9, 0x00, 0xaa, 0x00, 0x62, 0xce, 0x6c],
+            Some(FileType::Asf),
+        ),
+        (b"MPCKSH".to_vec(), Some(FileType::Mpc)),
+        (b"MP+\x17".to_vec(), Some(FileType::Mpc)),
+        (b"wvpk\x00\x00".to_vec(), Some(FileType::WavPack)),
+        (b"TTA1\x01\x00".to_vec(), Some(FileType::TrueAudio)),
+        (b"<html>".to_vec(), None),
+        (Vec::new(), None),
+    ];
+    for (header, expected) in headers {
+        assert_eq!(FileType::from_header(&header), expected, "header {:?}", header);
+    }
+}
+
+#[test]
+fn sniff_fixture_types() {
+    use taglib::FileType;
+    for &(fixture, expected) in &[("silence.mp3", FileType::Mpeg), ("silence.flac", FileType::Flac)] {
+        let source = scratch_copy(fixture, &format!("sniff_{}", fixture));
+        let path = source.with_extension("bin");
+        fs::rename(&source, &path).unwrap();
+        assert_eq!(taglib::sniff_file_type(&path).unwrap(), Some(expected));
+        assert!(TagLibFile::new_with_type(&path, expected).is_ok());
+    }
+}
+
+#[test]
+fn detected_file_type() {
+    use taglib::FileType;
+    let mp3 = TagLibFile::new(scratch_copy("silence.mp3", "detected_file_type")).unwrap();
+    assert_eq!(mp3.file_type(), Some(FileType::Mpeg));
+    let flac = TagLibFile::new(scratch_copy("silence.flac", "detected_file_type")).unwrap();
+    assert_eq!(flac.file_type(), Some(FileType::Flac));
+    assert!(format!("{:?}", flac).contains("Flac"));
+    let data = fs::read(scratch_copy("silence.flac", "detected_file_type_bytes")).unwrap();
+    let from_bytes = TagLibFile::from_bytes(&data, FileType::Flac).unwrap();
+    assert_eq!(from_bytes.file_type(), Some(FileType::Flac));
+}
+
+fn save_with_old_mtime(name: &str, options: &taglib::SaveOptions) -> (std::time::SystemTime, std::time::SystemTime) {
+    let path = scratch_copy("silence.mp3", name);
+    // put the time well in the past, so that saving now would certainly change it
+    let old = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+    fs::OpenOptions::new().write(true).open(&path).unwrap().set_modified(old).unwrap();
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_title("New title").unwrap();
+    let warnings = file.save_with(options).unwrap();
+    assert!(warnings.is_empty(), "{:?}", warnings);
+    (old, fs::metadata(&path).unwrap().modified().unwrap())
+}
+
+#[test]
+fn preserve_mtime() {
+    let (before, after) = save_with_old_mtime("preserve_mtime", taglib::SaveOptions::new().preserve_mtime(true));
+    assert_eq!(before, after);
+    let (before, after) = save_with_old_mtime("no_preserve_mtime", &taglib::SaveOptions::new());
+    assert_ne!(before, after);
+}
+
+#[test]
+fn preserve_mtime_with_atomic_save() {
+    let options = taglib::SaveOptions::new().preserve_mtime(true).atomic(true).clone();
+    let (before, after) = save_with_old_mtime("preserve_mtime_atomic", &options);
+    assert_eq!(before, after);
+}
+
+fn save_title(path: &Path, title: &str, padding: taglib::PaddingPolicy) -> u64 {
+    let mut file = TagLibFile::new(path).unwrap();
+    file.set_title(title).unwrap();
+    file.save_with(taglib::SaveOptions::new().id3v2_padding(padding)).unwrap();
+    fs::metadata(path).unwrap().len()
+}
+
+#[test]
+fn reserved_padding_makes_later_edits_in_place() {
+    let path = scratch_copy("silence.mp3", "id3v2_padding_reserve");
+    // start with no room to spare, so that the longer title only fits in what's reserved.
+    // This stays under 1 KiB, the most that taglib keeps for a file this small
+    save_title(&path, "Title", taglib::PaddingPolicy::Minimal);
+    let reserved = save_title(&path, "Title 2", taglib::PaddingPolicy::Reserve(1000));
+    let edited = save_title(&path, &"A much longer title ".repeat(25), taglib::PaddingPolicy::Default);
+    assert_eq!(reserved, edited);
+}
+
+#[test]
+fn minimal_padding_is_smaller() {
+    let path = scratch_copy("silence.mp3", "id3v2_padding_minimal");
+    let reserved = save_title(&path, "Title", taglib::PaddingPolicy::Reserve(100));
+    let minimal = save_title(&path, "Title 2", taglib::PaddingPolicy::Minimal);
+    // the longer title takes one byte of the padding, and all but one of the rest goes
+    assert_eq!(minimal, reserved - 99 + 1);
+}
+
+#[test]
+fn read_property_by_key() {
+    let path = scratch_copy("silence.flac", "read_property_by_key");
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.tag_mut().set_artist("Artist").unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.tag().property("ARTIST").unwrap(), Some("Artist".to_owned()));
+    assert_eq!(file.tag().property("ALBUMARTIST").unwrap(), None);
+}
+
+#[test]
+fn property_key_with_nul() {
+    let file = TagLibFile::new("tests/fixtures/silence.flac").unwrap();
+    match file.tag().property("ALBUM\0ARTIST") {
+        Err(taglib::PropertyError::InvalidKey(_)) => (),
+        other => panic!("expected an invalid key error, got {:?}", other),
+    }
+}
+
+#[test]
+fn list_property_keys() {
+    let path = scratch_copy("silence.flac", "list_property_keys");
+    {
+        let file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.property_keys().unwrap(), Vec::<String>::new());
+    }
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.set_title("Title").unwrap();
+        file.set_artist("Artist").unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.property_keys().unwrap(), vec!["ARTIST", "TITLE"]);
+}
+
+#[test]
+fn read_multiple_property_values() {
+    let file = TagLibFile::new("tests/fixtures/two_artists.flac").unwrap();
+    assert_eq!(file.property_values("ARTIST").unwrap(), vec!["First Artist", "Second Artist"]);
+    assert_eq!(file.property("ARTIST").unwrap(), Some("First Artist".to_owned()));
+    assert_eq!(file.property_values("GENRE").unwrap(), Vec::<String>::new());
+}
+
+fn append_artists(fixture: &str, name: &str) -> Vec<String> {
+    let path = scratch_copy(fixture, name);
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.set_property("ARTIST", "Replaced").unwrap();
+        file.set_property("ARTIST", "First Artist").unwrap();
+        file.append_property("ARTIST", "Second Artist").unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    file.property_values("ARTIST").unwrap()
+}
+
+#[test]
+fn append_property_flac() {
+    assert_eq!(append_artists("silence.flac", "append_property_flac"), vec!["First Artist", "Second Artist"]);
+}
+
+#[test]
+fn append_property_mp3() {
+    assert_eq!(append_artists("silence.mp3", "append_property_mp3"), vec!["First Artist", "Second Artist"]);
+}
+
+#[test]
+fn set_property_with_nul() {
+    let path = scratch_copy("silence.flac", "set_property_with_nul");
+    let mut file = TagLibFile::new(&path).unwrap();
+    match file.set_property("ARTIST", "First\0Second") {
+        Err(taglib::PropertyError::InteriorNul(_)) => (),
+        other => panic!("expected an interior nul error, got {:?}", other),
+    }
+}
+
+#[test]
+fn remove_property() {
+    let path = scratch_copy("two_artists.flac", "remove_property");
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.remove_property("ARTIST").unwrap();
+        file.remove_property("CDDB").unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.property_keys().unwrap(), vec!["TITLE"]);
+}
+
+#[test]
+fn properties_snapshot() {
+    let file = TagLibFile::new("tests/fixtures/two_artists.flac").unwrap();
+    let mut expected = std::collections::BTreeMap::new();
+    expected.insert("ARTIST".to_owned(), vec!["First Artist".to_owned(), "Second Artist".to_owned()]);
+    expected.insert("TITLE".to_owned(), vec!["Two Artists".to_owned()]);
+    assert_eq!(file.properties().unwrap(), expected);
+}
+
+#[test]
+fn restore_properties_snapshot() {
+    let snapshot = TagLibFile::new("tests/fixtures/two_artists.flac").unwrap().properties().unwrap();
+    let path = scratch_copy("silence.flac", "restore_properties_snapshot");
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.set_property("GENRE", "Ambient").unwrap();
+        file.set_properties(&snapshot).unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.properties().unwrap(), snapshot);
+}
+
+fn album_artist_round_trip(fixture: &str, name: &str) {
+    let path = scratch_copy(fixture, name);
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.album_artist().unwrap(), None);
+        file.set_album_artist("Various Artists").unwrap();
+        file.save().unwrap();
+    }
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.album_artist().unwrap(), Some("Various Artists".to_owned()));
+        file.set_album_artist(None).unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.album_artist().unwrap(), None);
+}
+
+#[test]
+fn album_artist_mp3() {
+    album_artist_round_trip("silence.mp3", "album_artist_mp3");
+}
+
+#[test]
+fn album_artist_m4a() {
+    album_artist_round_trip("silence.m4a", "album_artist_m4a");
+}
+
+#[test]
+fn album_artist_flac() {
+    album_artist_round_trip("silence.flac", "album_artist_flac");
+}
+
+#[test]
+fn composer_through_tag_edit() {
+    let path = scratch_copy("silence.flac", "composer_through_tag_edit");
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.edit().title("Title").composer("Composer").save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.composer().unwrap(), Some("Composer".to_owned()));
+    let (data, _) = file.tag().to_tag_data();
+    assert_eq!(data.composer, Some("Composer".to_owned()));
+}
+
+#[test]
+fn diff_composer() {
+    let current = TagData::default();
+    let desired = TagData {
+        composer: Some("Composer".to_owned()),
+        ..TagData::default()
+    };
+    let changes = current.diff(&desired, DiffOptions::default());
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].field, TagField::Composer);
+}
+
+fn disc_round_trip(fixture: &str, name: &str) -> PathBuf {
+    let path = scratch_copy(fixture, name);
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.disc(), None);
+        file.set_disc(2, 3).unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.disc(), Some((2, Some(3))));
+    path
+}
+
+#[test]
+fn disc_mp3() {
+    let path = disc_round_trip("silence.mp3", "disc_mp3");
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.property("DISCNUMBER").unwrap(), Some("2/3".to_owned()));
+}
+
+#[test]
+fn disc_m4a() {
+    disc_round_trip("silence.m4a", "disc_m4a");
+}
+
+#[test]
+fn disc_flac() {
+    let path = disc_round_trip("silence.flac", "disc_flac");
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.property("DISCNUMBER").unwrap(), Some("2".to_owned()));
+    assert_eq!(file.property("DISCTOTAL").unwrap(), Some("3".to_owned()));
+}
+
+#[test]
+fn unparseable_disc_numbers() {
+    let path = scratch_copy("silence.flac", "unparseable_disc_numbers");
+    let mut file = TagLibFile::new(&path).unwrap();
+    for value in ["A", "3/", "/3", "1/B"].iter() {
+        file.set_property("DISCNUMBER", value).unwrap();
+        assert_eq!(file.disc(), None, "{}", value);
+    }
+    file.set_property("DISCNUMBER", " 1 / 2 ").unwrap();
+    assert_eq!(file.disc(), Some((1, Some(2))));
+    file.set_disc(4, None).unwrap();
+    assert_eq!(file.disc(), Some((4, None)));
+}
+
+fn track_total_round_trip(fixture: &str, name: &str) {
+    let path = scratch_copy(fixture, name);
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.track_total(), None);
+        file.set_track_with_total(5, Some(12)).unwrap();
+        file.save().unwrap();
+    }
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.track(), Some(5));
+        assert_eq!(file.track_total(), Some(12));
+        // a plain track number keeps the total
+        file.set_track(6).unwrap();
+        file.save().unwrap();
+    }
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.track(), Some(6));
+        assert_eq!(file.track_total(), Some(12));
+        file.set_track_with_total(6, None).unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.track(), Some(6));
+    assert_eq!(file.track_total(), None);
+}
+
+#[test]
+fn track_total_mp3() {
+    track_total_round_trip("silence.mp3", "track_total_mp3");
+}
+
+#[test]
+fn track_total_flac() {
+    track_total_round_trip("silence.flac", "track_total_flac");
+}
+
+#[test]
+fn track_total_m4a() {
+    track_total_round_trip("silence.m4a", "track_total_m4a");
+}
+
+fn compilation_round_trip(fixture: &str, name: &str) {
+    let path = scratch_copy(fixture, name);
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.is_compilation(), None);
+        file.set_compilation(true).unwrap();
+        file.save().unwrap();
+    }
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.is_compilation(), Some(true));
+        file.set_compilation(false).unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.is_compilation(), None);
+}
+
+#[test]
+fn compilation_mp3() {
+    compilation_round_trip("silence.mp3", "compilation_mp3");
+}
+
+#[test]
+fn compilation_m4a() {
+    compilation_round_trip("silence.m4a", "compilation_m4a");
+}
+
+#[test]
+fn compilation_flac() {
+    compilation_round_trip("silence.flac", "compilation_flac");
+}
+
+#[test]
+fn lenient_compilation_values() {
+    let path = scratch_copy("silence.flac", "lenient_compilation_values");
+    let mut file = TagLibFile::new(&path).unwrap();
+    let values = [("1", Some(true)), ("TRUE", Some(true)), ("0", Some(false)), ("false", Some(false)), ("yes please", None)];
+    for &(value, expected) in values.iter() {
+        file.set_property("COMPILATION", value).unwrap();
+        assert_eq!(file.is_compilation(), expected, "{}", value);
+    }
+}
+
+fn lyrics_round_trip(fixture: &str, name: &str) -> PathBuf {
+    let verse = "Line one of the verse\nLine two, with a comma\r\nLine three\n\n";
+    let lyrics: String = verse.repeat(1000);
+    assert!(lyrics.len() > 40_000);
+    let path = scratch_copy(fixture, name);
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.lyrics().unwrap(), None);
+        file.set_lyrics(lyrics.as_str()).unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.lyrics().unwrap(), Some(lyrics));
+    path
+}
+
+#[test]
+fn lyrics_mp3() {
+    let path = lyrics_round_trip("silence.mp3", "lyrics_mp3");
+    let file = TagLibFile::new(&path).unwrap();
+    assert!(file.lyrics_language().is_some());
+}
+
+#[test]
+fn lyrics_m4a() {
+    lyrics_round_trip("silence.m4a", "lyrics_m4a");
+}
+
+#[test]
+fn lyrics_flac() {
+    let path = lyrics_round_trip("silence.flac", "lyrics_flac");
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.lyrics_language(), None);
+}
+
+#[test]
+fn unsynced_lyrics_key() {
+    let path = scratch_copy("silence.flac", "unsynced_lyrics_key");
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_property("UNSYNCEDLYRICS", "La la la").unwrap();
+    assert_eq!(file.lyrics().unwrap(), Some("La la la".to_owned()));
+    file.set_lyrics("Do re mi").unwrap();
+    assert_eq!(file.property_keys().unwrap(), vec!["LYRICS"]);
+}
+
+fn example_musicbrainz_ids() -> taglib::MusicBrainzIds {
+    taglib::MusicBrainzIds {
+        recording_id: Some("b1a9c0e9-d987-4042-ae91-78d6a3267d69".to_owned()),
+        release_id: Some("1dc4c347-a1db-32aa-b14f-bc9cc507b843".to_owned()),
+        release_group_id: Some("f5093c06-23e3-404f-aeaa-40f72885ee3a".to_owned()),
+        artist_id: Some("83d91898-7763-47d7-b03b-b92132375c47".to_owned()),
+        track_id: None,
+    }
+}
+
+fn musicbrainz_round_trip(fixture: &str, name: &str) {
+    let path = scratch_copy(fixture, name);
+    let ids = example_musicbrainz_ids();
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.musicbrainz_ids().unwrap(), taglib::MusicBrainzIds::default());
+        file.set_musicbrainz_ids(&ids).unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.musicbrainz_ids().unwrap(), ids);
+}
+
+#[test]
+fn musicbrainz_ids_mp3() {
+    musicbrainz_round_trip("silence.mp3", "musicbrainz_ids_mp3");
+}
+
+#[test]
+fn musicbrainz_ids_m4a() {
+    musicbrainz_round_trip("silence.m4a", "musicbrainz_ids_m4a");
+}
+
+#[test]
+fn musicbrainz_ids_flac() {
+    musicbrainz_round_trip("silence.flac", "musicbrainz_ids_flac");
+}
+
+#[test]
+fn malformed_musicbrainz_id() {
+    let path = scratch_copy("silence.flac", "malformed_musicbrainz_id");
+    let mut file = TagLibFile::new(&path).unwrap();
+    let ids = taglib::MusicBrainzIds {
+        track_id: Some("not-a-uuid".to_owned()),
+        ..example_musicbrainz_ids()
+    };
+    let error = file.set_musicbrainz_ids(&ids).unwrap_err();
+    assert_eq!(error.field, TagField::MusicBrainzIds);
+    match error.kind {
+        taglib::SetErrorKind::Malformed(value) => assert_eq!(value, "not-a-uuid"),
+        other => panic!("unexpected error {:?}", other),
+    }
+    // nothing was written
+    assert_eq!(file.musicbrainz_ids().unwrap(), taglib::MusicBrainzIds::default());
+    file.set_musicbrainz_ids_unchecked(&ids).unwrap();
+    assert_eq!(file.musicbrainz_ids().unwrap(), ids);
+}
+
+fn replay_gain_round_trip(fixture: &str, name: &str) -> PathBuf {
+    let path = scratch_copy(fixture, name);
+    let replay_gain = taglib::ReplayGain {
+        track_gain_db: Some(-7.23),
+        track_peak: Some(0.988553),
+        album_gain_db: Some(1.5),
+        album_peak: None,
+    };
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.replay_gain().unwrap(), taglib::ReplayGain::default());
+        file.set_replay_gain(&replay_gain).unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.replay_gain().unwrap(), replay_gain);
+    path
+}
+
+#[test]
+fn replay_gain_mp3() {
+    replay_gain_round_trip("silence.mp3", "replay_gain_mp3");
+}
+
+#[test]
+fn replay_gain_flac() {
+    let path = replay_gain_round_trip("silence.flac", "replay_gain_flac");
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.property("REPLAYGAIN_TRACK_GAIN").unwrap(), Some("-7.23 dB".to_owned()));
+    assert_eq!(file.property("REPLAYGAIN_ALBUM_GAIN").unwrap(), Some("1.50 dB".to_owned()));
+    assert_eq!(file.property("REPLAYGAIN_TRACK_PEAK").unwrap(), Some("0.988553".to_owned()));
+}
+
+#[test]
+fn lenient_replay_gain_values() {
+    let path = scratch_copy("silence.flac", "lenient_replay_gain_values");
+    let mut file = TagLibFile::new(&path).unwrap();
+    for &(value, expected) in [("+3.10 dB", 3.1), ("-1,25 DB", -1.25), ("0.5db", 0.5), ("  -2 ", -2.0)].iter() {
+        file.set_property("REPLAYGAIN_TRACK_GAIN", value).unwrap();
+        assert_eq!(file.replay_gain().unwrap().track_gain_db, Some(expected), "{}", value);
+    }
+    file.set_property("REPLAYGAIN_ALBUM_PEAK", "loud").unwrap();
+    match file.replay_gain() {
+        Err(taglib::ReplayGainError::Parse { key, value }) => {
+            assert_eq!(key, "REPLAYGAIN_ALBUM_PEAK");
+            assert_eq!(value, "loud");
+        }
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+}
+
+fn sort_fields_round_trip(fixture: &str, name: &str) {
+    let path = scratch_copy(fixture, name);
+    let data = TagData {
+        artist: Some("The Beatles".to_owned()),
+        artist_sort: Some("Beatles, The".to_owned()),
+        album_artist_sort: Some("Beatles, The".to_owned()),
+        album_sort: Some("White Album, The".to_owned()),
+        title_sort: Some("Long and Winding Road, The".to_owned()),
+        ..TagData::default()
+    };
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.tag_mut().apply(&data).unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    let (read, errors) = file.tag().to_tag_data();
+    assert!(errors.is_empty());
+    assert_eq!(read, data);
+    assert_eq!(file.artist_sort().unwrap(), Some("Beatles, The".to_owned()));
+}
+
+#[test]
+fn sort_fields_mp3() {
+    sort_fields_round_trip("silence.mp3", "sort_fields_mp3");
+}
+
+#[test]
+fn sort_fields_m4a() {
+    sort_fields_round_trip("silence.m4a", "sort_fields_m4a");
+}
+
+#[test]
+fn sort_fields_flac() {
+    sort_fields_round_trip("silence.flac", "sort_fields_flac");
+}
+
+fn grouping_round_trip(fixture: &str, name: &str) {
+    let path = scratch_copy(fixture, name);
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.grouping().unwrap(), None);
+        file.set_grouping("Symphony No. 9").unwrap();
+        file.set_title("II. Molto vivace").unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.grouping().unwrap(), Some("Symphony No. 9".to_owned()));
+    // the grouping is in a frame of its own, not a TXXX frame
+    assert!(!file_contains(&path, b"TXXX"));
+}
+
+#[test]
+fn grouping_mp3() {
+    grouping_round_trip("silence.mp3", "grouping_mp3");
+}
+
+#[test]
+fn grouping_m4a() {
+    grouping_round_trip("silence.m4a", "grouping_m4a");
+}
+
+fn initial_key_round_trip(fixture: &str, name: &str) {
+    let path = scratch_copy(fixture, name);
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.initial_key().unwrap(), None);
+        file.set_initial_key("F#m").unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    let key = file.initial_key().unwrap().unwrap();
+    assert_eq!(key, "F#m");
+    assert_eq!(key.parse::<taglib::MusicalKey>().unwrap().camelot(), "11A");
+}
+
+#[test]
+fn initial_key_mp3() {
+    initial_key_round_trip("silence.mp3", "initial_key_mp3");
+}
+
+#[test]
+fn initial_key_flac() {
+    initial_key_round_trip("silence.flac", "initial_key_flac");
+}
+
+#[test]
+fn musical_key_notations() {
+    let key = |s: &str| s.parse::<taglib::MusicalKey>().unwrap();
+    assert_eq!(key("8A").standard(), "Am");
+    assert_eq!(key("8B").standard(), "C");
+    assert_eq!(key("12b").standard(), "E");
+    assert_eq!(key("Am").camelot(), "8A");
+    assert_eq!(key("C").camelot(), "8B");
+    assert_eq!(key("Cm").camelot(), "5A");
+    assert_eq!(key("B").camelot(), "1B");
+    assert_eq!(key("G#m"), key("Abm"));
+    assert_eq!(key("E♭ minor"), key("2A"));
+    assert_eq!(key("Bbmin").to_string(), "Bbm");
+    for bad in ["", "H", "13A", "0B", "Cx", "8C"].iter() {
+        assert!(bad.parse::<taglib::MusicalKey>().is_err(), "{}", bad);
+    }
+}
+
+fn isrc_round_trip(fixture: &str, name: &str) {
+    let path = scratch_copy(fixture, name);
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.isrc().unwrap(), None);
+        file.set_isrc("us-s1z-99-00001").unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.isrc().unwrap(), Some("USS1Z9900001".to_owned()));
+}
+
+#[test]
+fn isrc_mp3() {
+    isrc_round_trip("silence.mp3", "isrc_mp3");
+}
+
+#[test]
+fn isrc_flac() {
+    isrc_round_trip("silence.flac", "isrc_flac");
+}
+
+#[test]
+fn malformed_isrc() {
+    let path = scratch_copy("silence.flac", "malformed_isrc");
+    let mut file = TagLibFile::new(&path).unwrap();
+    for bad in ["USS1Z990000", "1SS1Z9900001", "USS1Z99A0001", "USS1Z99000012"].iter() {
+        let error = file.set_isrc(*bad).unwrap_err();
+        assert_eq!(error.field, TagField::Isrc);
+    }
+    assert_eq!(file.isrc().unwrap(), None);
+    file.set_isrc_unchecked("legacy-code").unwrap();
+    assert_eq!(file.isrc().unwrap(), Some("legacy-code".to_owned()));
+    // a blank ISRC is no ISRC, rather than a malformed one
+    file.set_isrc(" ").unwrap();
+    assert_eq!(file.isrc().unwrap(), None);
+}
+
+fn label_round_trip(fixture: &str, name: &str) {
+    let path = scratch_copy(fixture, name);
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        let data = TagData {
+            label: Some("Blue Note".to_owned()),
+            ..TagData::default()
+        };
+        file.tag_mut().apply(&data).unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.label().unwrap(), Some("Blue Note".to_owned()));
+    assert_eq!(file.tag().to_tag_data().0.label, Some("Blue Note".to_owned()));
+}
+
+#[test]
+fn label_mp3() {
+    label_round_trip("silence.mp3", "label_mp3");
+}
+
+#[test]
+fn label_flac() {
+    label_round_trip("silence.flac", "label_flac");
+}
+
+#[test]
+fn label_from_organization() {
+    let path = scratch_copy("silence.flac", "label_from_organization");
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_property("ORGANIZATION", "Old Label").unwrap();
+    assert_eq!(file.label_with_key().unwrap(), Some(("ORGANIZATION", "Old Label".to_owned())));
+    file.set_label("New Label").unwrap();
+    assert_eq!(file.label_with_key().unwrap(), Some(("LABEL", "New Label".to_owned())));
+    file.set_label(None).unwrap();
+    assert_eq!(file.label().unwrap(), None);
+}
+
+fn copyright_round_trip(fixture: &str, name: &str, policy: StringEncodingPolicy) {
+    let copyright = "\u{a9} 1959 Columbia Records, a division of Sony Music Entertainment. All rights reserved.";
+    let path = scratch_copy(fixture, name);
+    {
+        let mut file = OpenOptions::new().string_encoding(policy).open(&path).unwrap();
+        file.set_copyright(copyright).unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.copyright().unwrap(), Some(copyright.to_owned()));
+}
+
+#[test]
+fn copyright_mp3() {
+    copyright_round_trip("silence.mp3", "copyright_mp3", StringEncodingPolicy::Unicode);
+    copyright_round_trip("silence.mp3", "copyright_mp3_latin1", StringEncodingPolicy::Latin1);
+}
+
+#[test]
+fn copyright_m4a() {
+    copyright_round_trip("silence.m4a", "copyright_m4a", StringEncodingPolicy::Unicode);
+    copyright_round_trip("silence.m4a", "copyright_m4a_latin1", StringEncodingPolicy::Latin1);
+}
+
+#[test]
+fn copyright_flac() {
+    copyright_round_trip("silence.flac", "copyright_flac", StringEncodingPolicy::Unicode);
+    copyright_round_trip("silence.flac", "copyright_flac_latin1", StringEncodingPolicy::Latin1);
+}
+
+#[test]
+fn latin1_policy_rejects_phonogram_copyright() {
+    let path = scratch_copy("silence.mp3", "latin1_policy_rejects_phonogram");
+    let mut file = OpenOptions::new()
+        .string_encoding(StringEncodingPolicy::Latin1)
+        .open(&path)
+        .unwrap();
+    let error = file.set_copyright("\u{2117} 1959 Columbia").unwrap_err();
+    assert_eq!(error.field, TagField::Copyright);
+    match error.kind {
+        taglib::SetErrorKind::NotLatin1(c) => assert_eq!(c, '\u{2117}'),
+        other => panic!("unexpected error {:?}", other),
+    }
+    assert_eq!(file.copyright().unwrap(), None);
+}
+
+#[test]
+fn read_encoder_fields() {
+    let file = TagLibFile::new("tests/fixtures/encoded.mp3").unwrap();
+    assert_eq!(file.encoded_by().unwrap(), Some("LAME 3.100".to_owned()));
+    assert_eq!(file.encoder_settings().unwrap(), Some("Lavf58.29.100".to_owned()));
+    let properties = file.properties().unwrap();
+    assert_eq!(properties["ENCODEDBY"], vec!["LAME 3.100"]);
+    assert_eq!(properties["ENCODING"], vec!["Lavf58.29.100"]);
+}
+
+#[test]
+fn write_encoder_fields() {
+    let path = scratch_copy("silence.flac", "write_encoder_fields");
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.set_property("ENCODERSETTINGS", "-8").unwrap();
+        assert_eq!(file.encoder_settings().unwrap(), Some("-8".to_owned()));
+        file.set_encoded_by("Ripping Station").unwrap();
+        file.set_encoder_settings("flac 1.4.3 -8").unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.encoded_by().unwrap(), Some("Ripping Station".to_owned()));
+    assert_eq!(file.encoder_settings().unwrap(), Some("flac 1.4.3 -8".to_owned()));
+    assert_eq!(file.property("ENCODERSETTINGS").unwrap(), None);
+}
+
+#[test]
+fn normalise_language_codes() {
+    assert_eq!(taglib::iso_639_2_code("en"), Some("eng"));
+    assert_eq!(taglib::iso_639_2_code("English"), Some("eng"));
+    assert_eq!(taglib::iso_639_2_code("en-GB"), Some("eng"));
+    assert_eq!(taglib::iso_639_2_code("deu"), Some("ger"));
+    assert_eq!(taglib::iso_639_2_code(" FR "), Some("fre"));
+    assert_eq!(taglib::iso_639_2_code("pt_BR"), Some("por"));
+    assert_eq!(taglib::iso_639_2_code("und"), Some("und"));
+    assert_eq!(taglib::iso_639_2_code("Klingon"), None);
+    assert_eq!(taglib::iso_639_2_code(""), None);
+}
+
+fn language_round_trip(fixture: &str, name: &str) {
+    let path = scratch_copy(fixture, name);
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.language().unwrap(), None);
+        file.set_language("English").unwrap();
+        assert_eq!(file.language_code(), Some("eng"));
+        file.set_language_code("de").unwrap();
+        file.save().unwrap();
+    }
+    let mut file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.language().unwrap(), Some("ger".to_owned()));
+    let error = file.set_language_code("Elvish").unwrap_err();
+    assert_eq!(error.field, TagField::Language);
+}
+
+#[test]
+fn language_mp3() {
+    language_round_trip("silence.mp3", "language_mp3");
+}
+
+#[test]
+fn language_flac() {
+    language_round_trip("silence.flac", "language_flac");
+}
+
+fn original_date_round_trip(fixture: &str, name: &str) {
+    let path = scratch_copy(fixture, name);
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.original_date().unwrap(), None);
+        file.set_year(2009).unwrap();
+        file.set_original_date("1969-09-26").unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.year(), Some(2009));
+    assert_eq!(file.original_date().unwrap(), Some("1969-09-26".to_owned()));
+    assert_eq!(file.original_year(), Some(1969));
+}
+
+#[test]
+fn original_date_mp3() {
+    original_date_round_trip("silence.mp3", "original_date_mp3");
+}
+
+#[test]
+fn original_date_flac() {
+    original_date_round_trip("silence.flac", "original_date_flac");
+}
+
+#[test]
+fn original_year_forms() {
+    let path = scratch_copy("silence.flac", "original_year_forms");
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_property("ORIGINALYEAR", "1977").unwrap();
+    assert_eq!(file.original_year(), Some(1977));
+    for &(date, year) in [("1982", 1982), ("1982-07", 1982), ("1982-07-12", 1982)].iter() {
+        file.set_original_date(date).unwrap();
+        assert_eq!(file.original_year(), Some(year), "{}", date);
+    }
+    assert_eq!(file.property("ORIGINALYEAR").unwrap(), None);
+    for bad in ["82", "1982-7", "1982-13-01", "July 1982"].iter() {
+        let error = file.set_original_date(*bad).unwrap_err();
+        assert_eq!(error.field, TagField::OriginalDate);
+    }
+    file.set_original_year(1990).unwrap();
+    assert_eq!(file.original_date().unwrap(), Some("1990".to_owned()));
+    file.set_original_year(None).unwrap();
+    assert_eq!(file.original_date().unwrap(), None);
+}
+
+// a copy of the mp3 fixture, with an ID3v2.3 tag holding a single POPM frame
+fn mp3_with_popm(name: &str, email: &str, rating: u8) -> PathBuf {
+    let path = scratch_copy("silence.mp3", name);
+    let mut body = email.as_bytes().to_vec();
+    body.push(0);
+    body.push(rating);
+    let mut tag = b"ID3\x03\x00\x00".to_vec();
+    tag.extend_from_slice(&[0, 0, 0, 10 + body.len() as u8]);
+    tag.extend_from_slice(b"POPM");
+    tag.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    tag.extend_from_slice(&[0, 0]);
+    tag.extend_from_slice(&body);
+    tag.extend_from_slice(&fs::read(&path).unwrap());
+    fs::write(&path, tag).unwrap();
+    path
+}
+
+#[test]
+fn four_star_ratings_from_other_players() {
+    let foobar = mp3_with_popm("rating_foobar2000", "foobar2000", 196);
+    let wmp = mp3_with_popm("rating_wmp", "Windows Media Player 9 Series", 196);
+    assert_eq!(TagLibFile::new(&foobar).unwrap().rating(), Some(80));
+    assert_eq!(TagLibFile::new(&wmp).unwrap().rating(), Some(80));
+}
+
+#[test]
+fn rating_conventions() {
+    let path = scratch_copy("silence.mp3", "rating_conventions");
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.rating(), None);
+        file.set_rating(60).unwrap();
+        file.set_rating_with(50, taglib::RatingConvention::MediaMonkey).unwrap();
+        file.save().unwrap();
+    }
+    let mut file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.rating(), Some(60));
+    assert_eq!(file.rating_with(taglib::RatingConvention::MediaMonkey), Some(50));
+    assert!(file_contains(&path, b"Windows Media Player 9 Series\x00\x80"));
+    assert!(file_contains(&path, b"no@email\x00\x80"));
+    let error = file.set_rating(101).unwrap_err();
+    assert_eq!(error.field, TagField::Rating);
+    file.set_rating(None).unwrap();
+    file.set_rating_with(None, taglib::RatingConvention::MediaMonkey).unwrap();
+    assert_eq!(file.rating(), None);
+}
+
+#[test]
+fn save_as_keeps_unsaved_rating() {
+    let path = scratch_copy("silence.mp3", "save_as_rating");
+    let dest = env::temp_dir().join("taglib-rs-save_as_rating_dest.mp3");
+    let _ = fs::remove_file(&dest);
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_rating(60).unwrap();
+    drop(file.save_as(&dest).unwrap());
+    assert_eq!(TagLibFile::new(&dest).unwrap().rating(), Some(60));
+}
+
+#[test]
+fn rating_flac() {
+    let path = scratch_copy("silence.flac", "rating_flac");
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.set_rating(80).unwrap();
+        file.save().unwrap();
+    }
+    let mut file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.rating(), Some(80));
+    assert_eq!(file.property("RATING").unwrap(), Some("80".to_owned()));
+    // foobar2000 writes stars
+    file.set_property("RATING", "4").unwrap();
+    assert_eq!(file.rating(), Some(80));
+}
+
+fn mood_round_trip(fixture: &str, name: &str) {
+    let path = scratch_copy(fixture, name);
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.mood().unwrap(), None);
+        assert!(file.moods().unwrap().is_empty());
+        file.set_moods(&["Mellow", "", "Wistful"]).unwrap();
+        file.save().unwrap();
+    }
+    let mut file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.mood().unwrap(), Some("Mellow".to_owned()));
+    assert_eq!(file.moods().unwrap(), vec!["Mellow".to_owned(), "Wistful".to_owned()]);
+    file.set_mood("Upbeat").unwrap();
+    assert_eq!(file.moods().unwrap(), vec!["Upbeat".to_owned()]);
+    file.set_moods(&[]).unwrap();
+    assert_eq!(file.mood().unwrap(), None);
+}
+
+#[test]
+fn mood_mp3() {
+    mood_round_trip("silence.mp3", "mood_mp3");
+}
+
+#[test]
+fn mood_flac() {
+    mood_round_trip("silence.flac", "mood_flac");
+}
+
+#[test]
+fn mood_from_id3v2_3_user_text() {
+    // ID3v2.3 has no TMOO, so the mood is in a TXXX frame
+    let file = TagLibFile::new("tests/fixtures/mood_v23.mp3").unwrap();
+    assert_eq!(file.mood().unwrap(), Some("Calm".to_owned()));
+}
+
+fn conductor_and_remixer_round_trip(fixture: &str, name: &str) {
+    let path = scratch_copy(fixture, name);
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.conductor().unwrap(), None);
+        assert_eq!(file.remixer().unwrap(), None);
+        file.edit().conductor("Herbert von Karajan").remixer("Aphex Twin").save().unwrap();
+    }
+    let mut file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.conductor().unwrap(), Some("Herbert von Karajan".to_owned()));
+    assert_eq!(file.remixer().unwrap(), Some("Aphex Twin".to_owned()));
+    let (data, _) = file.tag().to_tag_data();
+    assert_eq!(data.conductor, Some("Herbert von Karajan".to_owned()));
+    assert_eq!(data.remixer, Some("Aphex Twin".to_owned()));
+    file.set_remixer(None).unwrap();
+    assert_eq!(file.remixer().unwrap(), None);
+}
+
+#[test]
+fn conductor_and_remixer_mp3() {
+    conductor_and_remixer_round_trip("silence.mp3", "conductor_and_remixer_mp3");
+}
+
+#[test]
+fn conductor_and_remixer_flac() {
+    conductor_and_remixer_round_trip("silence.flac", "conductor_and_remixer_flac");
+}
+
+#[test]
+fn diff_conductor_and_remixer() {
+    let current = TagData {
+        conductor: Some("Conductor".to_owned()),
+        ..TagData::default()
+    };
+    let desired = TagData {
+        conductor: Some("Conductor".to_owned()),
+        remixer: Some("Remixer".to_owned()),
+        ..TagData::default()
+    };
+    let changes = current.diff(&desired, DiffOptions::default());
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].field, TagField::Remixer);
+}
+
+fn genres_round_trip(fixture: &str, name: &str) {
+    let path = scratch_copy(fixture, name);
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert!(file.genres().unwrap().is_empty());
+        file.set_genres(&["Jazz", "Bossa Nova"]).unwrap();
+        file.save().unwrap();
+    }
+    let mut file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.genres().unwrap(), vec!["Jazz".to_owned(), "Bossa Nova".to_owned()]);
+    // the single genre methods see the first one
+    assert_eq!(file.genre().unwrap(), "Jazz");
+    assert_eq!(file.genre_opt().unwrap(), Some("Jazz".to_owned()));
+    assert_eq!(file.genre_lossy(), "Jazz");
+    file.set_genre("Samba").unwrap();
+    assert_eq!(file.genres().unwrap(), vec!["Samba".to_owned()]);
+    file.set_genres(&[]).unwrap();
+    assert_eq!(file.genre_opt().unwrap(), None);
+}
+
+#[test]
+fn genres_mp3() {
+    genres_round_trip("silence.mp3", "genres_mp3");
+}
+
+#[test]
+fn genres_flac() {
+    genres_round_trip("silence.flac", "genres_flac");
+}
+
+#[test]
+fn genres_split_joined() {
+    let path = scratch_copy("silence.flac", "genres_split_joined");
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_genres(&["Jazz;Bossa Nova", "Latin / Samba"]).unwrap();
+    assert_eq!(file.genres().unwrap(), vec!["Jazz;Bossa Nova".to_owned(), "Latin / Samba".to_owned()]);
+    assert_eq!(
+        file.genres_with(true).unwrap(),
+        vec!["Jazz".to_owned(), "Bossa Nova".to_owned(), "Latin".to_owned(), "Samba".to_owned()]
+    );
+}
+
+#[test]
+fn artists_flac() {
+    let path = scratch_copy("two_artists.flac", "artists_flac");
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.artists().unwrap(), vec!["First Artist".to_owned(), "Second Artist".to_owned()]);
+        assert_eq!(file.artist().unwrap(), "First Artist");
+        assert_eq!(file.artist_bytes(), b"First Artist");
+        assert_eq!(&*file.tag().artist_ref().unwrap(), "First Artist");
+        file.set_artists(&["Earth, Wind & Fire", "The Emotions"]).unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.artists_with(true).unwrap(), vec!["Earth, Wind & Fire".to_owned(), "The Emotions".to_owned()]);
+    assert_eq!(file.artist_opt().unwrap(), Some("Earth, Wind & Fire".to_owned()));
+}
+
+#[test]
+fn artists_mp3() {
+    let path = scratch_copy("silence.mp3", "artists_mp3");
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert!(file.artists().unwrap().is_empty());
+        file.set_artists(&["First Artist", "Second Artist"]).unwrap();
+        file.save().unwrap();
+    }
+    let mut file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.artists().unwrap(), vec!["First Artist".to_owned(), "Second Artist".to_owned()]);
+    assert_eq!(file.artist_lossy(), "First Artist");
+    file.set_artist("Only Artist").unwrap();
+    assert_eq!(file.artists().unwrap(), vec!["Only Artist".to_owned()]);
+}
+
+#[test]
+fn artists_joined_in_id3v2_3() {
+    // ID3v2.3 has no multiple values, so the artists are joined into one string
+    let file = TagLibFile::new("tests/fixtures/two_artists_v23.mp3").unwrap();
+    assert_eq!(file.artists().unwrap(), vec!["First Artist/Second Artist feat. Third Artist".to_owned()]);
+    assert_eq!(
+        file.artists_with(true).unwrap(),
+        vec!["First Artist".to_owned(), "Second Artist".to_owned(), "Third Artist".to_owned()]
+    );
+}
+
+#[test]
+fn artists_featuring_in_brackets() {
+    let path = scratch_copy("silence.flac", "artists_featuring_in_brackets");
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_artists(&["Daft Punk (Feat. Pharrell Williams)", "AC; DC"]).unwrap();
+    assert_eq!(
+        file.artists_with(true).unwrap(),
+        vec!["Daft Punk".to_owned(), "Pharrell Williams".to_owned(), "AC".to_owned(), "DC".to_owned()]
+    );
+}
+
+#[test]
+fn property_as_parses() {
+    let path = scratch_copy("silence.flac", "property_as_parses");
+    let mut file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.property_as::<u32>("TRACKTOTAL").unwrap(), None);
+    file.set_property_display("TRACKTOTAL", 12).unwrap();
+    file.set_property_display("BPM", 128.5).unwrap();
+    file.set_property("DISCNUMBER", " 2 ").unwrap();
+    assert_eq!(file.property_as::<u32>("TRACKTOTAL").unwrap(), Some(12));
+    assert_eq!(file.property_as::<f64>("BPM").unwrap(), Some(128.5));
+    assert_eq!(file.property_as::<u32>("DISCNUMBER").unwrap(), Some(2));
+    match file.property_as::<u32>("BPM").unwrap_err() {
+        taglib::PropertyParseError::Parse { key, value } => {
+            assert_eq!(key, "BPM");
+            assert_eq!(value, "128.5");
+        }
+        err => panic!("unexpected error {:?}", err),
+    }
+}
+
+fn user_text_round_trip(fixture: &str, name: &str, description: &str) {
+    let path = scratch_copy(fixture, name);
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.user_text(description).unwrap(), None);
+        file.set_user_text(description, "Première version").unwrap();
+        file.set_user_text("Waveform: Checksum", "0xdeadbeef").unwrap();
+        file.save().unwrap();
+    }
+    let mut file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.user_text(description).unwrap(), Some("Première version".to_owned()));
+    assert_eq!(file.user_text("Waveform: Checksum").unwrap(), Some("0xdeadbeef".to_owned()));
+    file.remove_user_text(description).unwrap();
+    assert_eq!(file.user_text(description).unwrap(), None);
+    assert_eq!(file.user_text("Waveform: Checksum").unwrap(), Some("0xdeadbeef".to_owned()));
+}
+
+#[test]
+fn user_text_mp3() {
+    user_text_round_trip("silence.mp3", "user_text_mp3", "Analysis Version ü");
+    let path = env::temp_dir().join("taglib-rs-user_text_mp3.mp3");
+    // the description keeps its case
+    assert!(file_contains(&path, b"Waveform: Checksum\x00"));
+}
+
+#[test]
+fn user_text_m4a() {
+    user_text_round_trip("silence.m4a", "user_text_m4a", "Analysis Version ü");
+}
+
+#[test]
+fn user_text_flac() {
+    user_text_round_trip("silence.flac", "user_text_flac", "Analysis Version");
+    let path = scratch_copy("silence.flac", "user_text_flac_unicode");
+    let mut file = TagLibFile::new(&path).unwrap();
+    // Vorbis comment names are ASCII
+    match file.set_user_text("Analysis Version ü", "1").unwrap_err() {
+        taglib::PropertyError::Unsupported(description) => assert_eq!(description, "Analysis Version ü"),
+        err => panic!("unexpected error {:?}", err),
+    }
+}
+
+#[test]
+fn partial_date_parses() {
+    let date: PartialDate = "2009-09-26".parse().unwrap();
+    assert_eq!((date.year(), date.month(), date.day()), (2009, Some(9), Some(26)));
+    assert_eq!(" 2009 ".parse(), Ok(PartialDate::new(2009, None, None).unwrap()));
+    assert_eq!("2009-09-26T07:00:00Z".parse(), Ok(date));
+    assert_eq!("2009-09-26 07:00".parse(), Ok(date));
+    assert_eq!(PartialDate::new(2009, Some(9), None).unwrap().to_string(), "2009-09");
+    assert_eq!(date.to_string(), "2009-09-26");
+    assert!("2009-13".parse::<PartialDate>().is_err());
+    assert!("September 2009".parse::<PartialDate>().is_err());
+    assert_eq!(PartialDate::new(2009, None, Some(26)), None);
+}
+
+fn date_round_trip(fixture: &str, name: &str) {
+    let path = scratch_copy(fixture, name);
+    let date = PartialDate::new(2009, Some(9), Some(26)).unwrap();
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.date(), None);
+        file.set_date(date).unwrap();
+        file.save().unwrap();
+    }
+    let mut file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.date(), Some(date));
+    assert_eq!(file.year(), Some(2009));
+    file.set_year(2010).unwrap();
+    assert_eq!(file.date(), PartialDate::new(2010, None, None));
+    file.set_date(None).unwrap();
+    assert_eq!(file.date(), None);
+    assert_eq!(file.year(), None);
+}
+
+#[test]
+fn date_mp3() {
+    date_round_trip("silence.mp3", "date_mp3");
+}
+
+#[test]
+fn date_flac() {
+    date_round_trip("silence.flac", "date_flac");
+}
+
+#[test]
+fn date_m4a() {
+    date_round_trip("silence.m4a", "date_m4a");
+}
+
+#[test]
+fn date_from_timestamp() {
+    let path = scratch_copy("silence.m4a", "date_from_timestamp");
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_property("DATE", "2009-09-26T07:00:00Z").unwrap();
+    assert_eq!(file.date(), PartialDate::new(2009, Some(9), Some(26)));
+    assert_eq!(file.year(), Some(2009));
+}
+
+fn example_podcast_info() -> taglib::PodcastInfo {
+    taglib::PodcastInfo {
+        podcast: true,
+        category: Some("Technology".to_owned()),
+        description: Some("An episode about tags".to_owned()),
+        episode_id: Some("https://example.com/episodes/42".to_owned()),
+    }
+}
+
+fn podcast_round_trip(fixture: &str, name: &str) -> PathBuf {
+    let path = scratch_copy(fixture, name);
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.podcast_info().unwrap(), taglib::PodcastInfo::default());
+        file.set_podcast_info(&example_podcast_info()).unwrap();
+        file.save().unwrap();
+    }
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.podcast_info().unwrap(), example_podcast_info());
+        file.set_podcast_info(&taglib::PodcastInfo {
+            podcast: false,
+            ..example_podcast_info()
+        })
+        .unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    let info = file.podcast_info().unwrap();
+    assert!(!info.podcast);
+    assert_eq!(info.category, Some("Technology".to_owned()));
+    path
+}
+
+#[test]
+fn podcast_m4a() {
+    let path = podcast_round_trip("silence.m4a", "podcast_m4a");
+    assert!(file_contains(&path, b"catg"));
+    assert!(file_contains(&path, b"egid"));
+    assert!(!file_contains(&path, b"pcst"));
+}
+
+#[test]
+fn podcast_mp3() {
+    let path = podcast_round_trip("silence.mp3", "podcast_mp3");
+    assert!(file_contains(&path, b"TCAT"));
+    assert!(file_contains(&path, b"TGID"));
+}
+
+#[test]
+fn podcast_flac() {
+    podcast_round_trip("silence.flac", "podcast_flac");
+}
+
+#[test]
+fn podcast_flag_m4a() {
+    let path = scratch_copy("silence.m4a", "podcast_flag_m4a");
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.set_podcast_info(&example_podcast_info()).unwrap();
+        file.save().unwrap();
+    }
+    // the flag is a pcst atom holding a single byte, 1
+    assert!(file_contains(&path, b"pcst"));
+    assert!(TagLibFile::new(&path).unwrap().podcast_info().unwrap().podcast);
+}
+
+#[test]
+fn atomic_save_keeps_unsaved_podcast_and_classical_fields_m4a() {
+    let path = scratch_copy("silence.m4a", "atomic_save_podcast_m4a");
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_podcast_info(&example_podcast_info()).unwrap();
+    file.set_movement_name("II. Molto vivace").unwrap();
+    file.set_movement(2, 4).unwrap();
+    file.save_atomic().unwrap();
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.podcast_info().unwrap(), example_podcast_info());
+    assert_eq!(file.movement_name().unwrap(), Some("II. Molto vivace".to_owned()));
+    assert_eq!(file.movement(), Some((2, Some(4))));
+}
+
+#[test]
+fn gapless_info_mp3() {
+    let file = TagLibFile::new("tests/fixtures/gapless.mp3").unwrap();
+    let info = file.gapless_info().unwrap().unwrap();
+    assert_eq!(info.encoder_delay, 0x840);
+    assert_eq!(info.encoder_padding, 0x1ca);
+    assert_eq!(info.original_length, 0x3f31f6);
+}
+
+#[test]
+fn gapless_info_m4a() {
+    let path = scratch_copy("silence.m4a", "gapless_info_m4a");
+    let mut file = TagLibFile::new(&path).unwrap();
+    assert!(file.gapless_info().unwrap().is_none());
+    file.set_user_text("iTunSMPB", " 00000000 00000840 00000374 0000000000A6A4CC 00000000 00000000").unwrap();
+    let info = file.gapless_info().unwrap().unwrap();
+    assert_eq!((info.encoder_delay, info.encoder_padding, info.original_length), (2112, 884, 10921164));
+}
+
+#[test]
+fn gapless_info_malformed() {
+    let path = scratch_copy("silence.flac", "gapless_info_malformed");
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_user_text("iTunSMPB", "00000000 nonsense").unwrap();
+    match file.gapless_info().unwrap_err() {
+        taglib::GaplessInfoError::Parse(value) => assert_eq!(value, "00000000 nonsense"),
+        err => panic!("unexpected error {:?}", err),
+    }
+}
+
+fn example_classical_info() -> taglib::ClassicalInfo {
+    taglib::ClassicalInfo {
+        work: Some("Symphony No. 9 in D minor, Op. 125".to_owned()),
+        movement_name: Some("Molto vivace".to_owned()),
+        movement_number: Some(2),
+        movement_total: Some(4),
+    }
+}
+
+fn classical_round_trip(fixture: &str, name: &str) -> PathBuf {
+    let path = scratch_copy(fixture, name);
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.classical_info().unwrap(), taglib::ClassicalInfo::default());
+        file.set_classical_info(&example_classical_info()).unwrap();
+        file.save().unwrap();
+    }
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.classical_info().unwrap(), example_classical_info());
+        assert_eq!(file.movement(), Some((2, Some(4))));
+        file.set_movement(3, None).unwrap();
+        file.set_work(None).unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.movement(), Some((3, None)));
+    assert_eq!(file.work().unwrap(), None);
+    assert_eq!(file.movement_name().unwrap(), Some("Molto vivace".to_owned()));
+    path
+}
+
+#[test]
+fn classical_m4a() {
+    let path = scratch_copy("silence.m4a", "classical_m4a_atoms");
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.set_classical_info(&example_classical_info()).unwrap();
+        file.save().unwrap();
+    }
+    // Apple's atoms, rather than freeform ones
+    assert!(file_contains(&path, b"\xa9wrk"));
+    assert!(file_contains(&path, b"\xa9mvn"));
+    assert!(file_contains(&path, b"\xa9mvi"));
+    assert!(file_contains(&path, b"\xa9mvc"));
+    assert!(!file_contains(&path, b"com.apple.iTunes"));
+    classical_round_trip("silence.m4a", "classical_m4a");
+}
+
+#[test]
+fn classical_mp3() {
+    let path = classical_round_trip("silence.mp3", "classical_mp3");
+    assert!(file_contains(&path, b"MVNM"));
+    assert!(file_contains(&path, b"MVIN"));
+}
+
+#[test]
+fn classical_flac() {
+    let path = classical_round_trip("silence.flac", "classical_flac");
+    assert!(file_contains(&path, b"MOVEMENT=3"));
+}
+
+fn bpm_f64_round_trip(fixture: &str, name: &str) {
+    let path = scratch_copy(fixture, name);
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        assert_eq!(file.bpm_f64(), None);
+        file.set_bpm_f64(127.96).unwrap();
+        file.save().unwrap();
+    }
+    let mut file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.bpm_f64(), Some(127.96));
+    assert_eq!(file.property("BPM").unwrap(), Some("127.96".to_owned()));
+    file.set_bpm_f64(128.0).unwrap();
+    assert_eq!(file.property("BPM").unwrap(), Some("128".to_owned()));
+    assert_eq!(file.bpm(), Some(128));
+    // as European tools write it
+    file.set_property("BPM", " 127,5 ").unwrap();
+    assert_eq!(file.bpm_f64(), Some(127.5));
+    let error = file.set_bpm_f64(f64::NAN).unwrap_err();
+    assert_eq!(error.field, TagField::Bpm);
+    file.set_bpm_f64(None).unwrap();
+    assert_eq!(file.bpm_f64(), None);
+    // too small to be written as anything but 0
+    file.set_bpm_f64(127.96).unwrap();
+    file.set_bpm_f64(0.001).unwrap();
+    assert_eq!(file.property("BPM").unwrap(), None);
+}
+
+#[test]
+fn bpm_f64_mp3() {
+    bpm_f64_round_trip("silence.mp3", "bpm_f64_mp3");
+}
+
+#[test]
+fn bpm_f64_flac() {
+    bpm_f64_round_trip("silence.flac", "bpm_f64_flac");
+}
+
+fn fixture_path(fixture: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join(fixture)
+}
+
+fn fixture_bytes(fixture: &str) -> Vec<u8> {
+    fs::read(fixture_path(fixture)).unwrap()
+}
+
+fn cover_art_of(fixture: &str) -> taglib::Picture {
+    let file = TagLibFile::new(format!("tests/fixtures/{}", fixture)).unwrap();
+    file.cover_art().unwrap().expect("fixture has a cover")
+}
+
+#[test]
+fn cover_art_mp3() {
+    let cover = cover_art_of("cover.mp3");
+    assert_eq!(cover.data, fixture_bytes("cover.png"));
+    assert_eq!(cover.mime_type, "image/png");
+    assert_eq!(cover.picture_type, PictureType::FrontCover);
+    assert_eq!(cover.description, Some("Front".to_owned()));
+}
+
+#[test]
+fn cover_art_flac() {
+    let cover = cover_art_of("cover.flac");
+    assert_eq!(cover.data, fixture_bytes("cover.png"));
+    assert_eq!(cover.mime_type, "image/png");
+    assert_eq!(cover.picture_type, PictureType::FrontCover);
+    assert_eq!(cover.description, Some("Front".to_owned()));
+}
+
+#[test]
+fn cover_art_m4a() {
+    let cover = cover_art_of("cover.m4a");
+    assert_eq!(cover.data, fixture_bytes("cover.png"));
+    assert_eq!(cover.mime_type, "image/png");
+    assert_eq!(cover.picture_type, PictureType::FrontCover);
+    assert_eq!(cover.description, None);
+}
+
+#[test]
+fn cover_art_none() {
+    let file = TagLibFile::new("tests/fixtures/silence.mp3").unwrap();
+    assert_eq!(file.cover_art().unwrap(), None);
+}
+
+#[test]
+fn picture_type_numbers() {
+    assert_eq!(PictureType::from_id3v2(3), PictureType::FrontCover);
+    assert_eq!(PictureType::from_id3v2(20), PictureType::PublisherLogo);
+    assert_eq!(PictureType::from_id3v2(200), PictureType::Other);
+    assert_eq!(PictureType::BackCover.id3v2_number(), 4);
+}
+
+fn png_cover() -> Picture {
+    Picture {
+        data: fixture_bytes("cover.png"),
+        mime_type: "image/png".to_owned(),
+        picture_type: PictureType::FrontCover,
+        description: Some("Front".to_owned()),
+        dimensions: None,
+    }
+}
+
+// the data only has to look like a JPEG, so that taglib keeps it as it is
+fn large_jpeg_cover(size: usize) -> Picture {
+    let mut data = vec![0xFF, 0xD8, 0xFF, 0xE0];
+    data.extend((0..size).map(|i| (i % 251) as u8));
+    data.extend(&[0xFF, 0xD9]);
+    Picture {
+        data,
+        mime_type: "image/jpeg".to_owned(),
+        picture_type: PictureType::FrontCover,
+        description: None,
+        dimensions: None,
+    }
+}
+
+fn set_cover_art_round_trip(fixture: &str, name: &str) {
+    let path = scratch_copy(fixture, name);
+    let cover = png_cover();
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.set_cover_art(&cover).unwrap();
+        assert!(file.is_modified());
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    let read = file.cover_art().unwrap().unwrap();
+    assert_eq!(read.data, cover.data);
+    assert_eq!(read.mime_type, "image/png");
+    assert_eq!(read.picture_type, PictureType::FrontCover);
+}
+
+#[test]
+fn set_cover_art_mp3() {
+    set_cover_art_round_trip("silence.mp3", "set_cover_art_mp3");
+}
+
+#[test]
+fn set_cover_art_flac() {
+    set_cover_art_round_trip("silence.flac", "set_cover_art_flac");
+}
+
+#[test]
+fn set_cover_art_m4a() {
+    set_cover_art_round_trip("cover.m4a", "set_cover_art_m4a");
+}
+
+fn count_occurrences(path: &PathBuf, needle: &[u8]) -> usize {
+    fs::read(path).unwrap().windows(needle.len()).filter(|window| *window == needle).count()
+}
+
+#[test]
+fn set_cover_art_replaces_front_cover() {
+    let path = scratch_copy("cover.mp3", "set_cover_art_replaces");
+    let cover = large_jpeg_cover(1000);
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.set_cover_art(&cover).unwrap();
+        file.save().unwrap();
+    }
+    assert_eq!(count_occurrences(&path, b"APIC"), 1);
+    let file = TagLibFile::new(&path).unwrap();
+    let read = file.cover_art().unwrap().unwrap();
+    assert_eq!(read.data, cover.data);
+    assert_eq!(read.mime_type, "image/jpeg");
+    assert_eq!(read.description, None);
+}
+
+#[test]
+fn set_cover_art_large_repeatedly() {
+    let path = scratch_copy("silence.flac", "set_cover_art_large");
+    let cover = large_jpeg_cover(4 * 1024 * 1024);
+    for _ in 0..3 {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.set_cover_art(&cover).unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.cover_art().unwrap().unwrap().data, cover.data);
+    // replaced each time rather than added
+    assert!(fs::metadata(&path).unwrap().len() < 2 * cover.data.len() as u64);
+}
+
+#[test]
+fn set_cover_art_rejects_nul() {
+    let mut file = TagLibFile::new("tests/fixtures/silence.mp3").unwrap();
+    let mut cover = png_cover();
+    cover.mime_type = "image/png\0".to_owned();
+    assert!(file.set_cover_art(&cover).is_err());
+    assert!(!file.is_modified());
+}
+
+#[test]
+fn image_mime_types() {
+    assert_eq!(image_mime_type(&fixture_bytes("cover.jpg")), Some("image/jpeg"));
+    assert_eq!(image_mime_type(&fixture_bytes("cover.png")), Some("image/png"));
+    assert_eq!(image_mime_type(b"GIF89a\x01\x00"), Some("image/gif"));
+    assert_eq!(image_mime_type(b"RIFF\x10\x00\x00\x00WEBPVP8 "), Some("image/webp"));
+    assert_eq!(image_mime_type(b"RIFF\x10\x00\x00\x00WAVEfmt "), None);
+    assert_eq!(image_mime_type(b""), None);
+}
+
+#[test]
+fn set_cover_art_from_path_jpeg() {
+    let path = scratch_copy("cover.mp3", "set_cover_art_from_path");
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.set_cover_art_from_path(fixture_path("cover.jpg")).unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    let cover = file.cover_art().unwrap().unwrap();
+    assert_eq!(cover.data, fixture_bytes("cover.jpg"));
+    assert_eq!(cover.mime_type, "image/jpeg");
+    assert_eq!(cover.picture_type, PictureType::FrontCover);
+    assert_eq!(count_occurrences(&path, b"APIC"), 1);
+}
+
+#[test]
+fn set_cover_art_from_path_errors() {
+    let mut file = TagLibFile::new("tests/fixtures/silence.mp3").unwrap();
+    match file.set_cover_art_from_path(fixture_path("missing.jpg")) {
+        Err(taglib::PictureError::Io(path, _)) => assert_eq!(path, fixture_path("missing.jpg")),
+        other => panic!("expected an io error, got {:?}", other),
+    }
+    match file.set_cover_art_from_path(fixture_path("silence.mp3")) {
+        Err(taglib::PictureError::UnknownImageFormat(_)) => {}
+        other => panic!("expected an unknown format, got {:?}", other),
+    }
+    match file.set_cover_art_from_path_with(fixture_path("cover.jpg"), Some(100)) {
+        Err(taglib::PictureError::ImageTooLarge { size, limit, .. }) => assert_eq!((size, limit), (125, 100)),
+        other => panic!("expected the image to be too large, got {:?}", other),
+    }
+    assert!(!file.is_modified());
+    file.set_cover_art_from_path_with(fixture_path("cover.jpg"), Some(125)).unwrap();
+    assert!(file.is_modified());
+}
+
+fn remove_pictures_shrinks(fixture: &str, name: &str) {
+    let path = scratch_copy(fixture, name);
+    let cover = large_jpeg_cover(200 * 1024);
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.set_cover_art(&cover).unwrap();
+        let mut back = png_cover();
+        back.picture_type = PictureType::BackCover;
+        file.set_cover_art(&back).unwrap();
+        file.save().unwrap();
+    }
+    let with_pictures = fs::metadata(&path).unwrap().len();
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.remove_pictures().unwrap();
+        file.save().unwrap();
+    }
+    let without_pictures = fs::metadata(&path).unwrap().len();
+    // allowing for the padding that taglib leaves
+    assert!(with_pictures - without_pictures > cover.data.len() as u64 - 8192);
+    let mut file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.cover_art().unwrap(), None);
+    file.remove_pictures().unwrap();
+    assert!(!file.is_modified());
+}
+
+#[test]
+fn remove_pictures_mp3() {
+    remove_pictures_shrinks("silence.mp3", "remove_pictures_mp3");
+}
+
+#[test]
+fn remove_pictures_flac() {
+    remove_pictures_shrinks("silence.flac", "remove_pictures_flac");
+}
+
+#[test]
+fn remove_pictures_m4a() {
+    let path = scratch_copy("cover.m4a", "remove_pictures_m4a");
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.remove_pictures().unwrap();
+        assert!(file.is_modified());
+        file.save().unwrap();
+    }
+    assert!(!file_contains(&path, b"covr"));
+    assert_eq!(TagLibFile::new(&path).unwrap().cover_art().unwrap(), None);
+}
+
+#[test]
+fn pictures_fixture() {
+    let file = TagLibFile::new("tests/fixtures/cover.mp3").unwrap();
+    let pictures = file.pictures().unwrap();
+    assert_eq!(pictures.len(), 1);
+    assert_eq!(pictures[0], cover_art_of("cover.mp3"));
+    let file = TagLibFile::new("tests/fixtures/silence.mp3").unwrap();
+    assert_eq!(file.pictures().unwrap(), Vec::new());
+}
+
+fn pictures_in_order(fixture: &str, name: &str) {
+    let path = scratch_copy(fixture, name);
+    let front = png_cover();
+    let mut back = large_jpeg_cover(100);
+    back.picture_type = PictureType::BackCover;
+    back.description = Some("Back".to_owned());
+    let mut artist = png_cover();
+    artist.picture_type = PictureType::Artist;
+    artist.description = Some("Band photo".to_owned());
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        for picture in &[&front, &back, &artist] {
+            file.set_cover_art(picture).unwrap();
+        }
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.pictures().unwrap(), vec![front, back, artist]);
+}
+
+#[test]
+fn pictures_in_order_mp3() {
+    pictures_in_order("silence.mp3", "pictures_in_order_mp3");
+}
+
+#[test]
+fn pictures_in_order_flac() {
+    pictures_in_order("silence.flac", "pictures_in_order_flac");
+}
+
+fn scratch_path(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("taglib-rs-{}", name));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn save_cover_to_adds_extension() {
+    let dest = scratch_path("save_cover_to.png");
+    let file = TagLibFile::new("tests/fixtures/cover.flac").unwrap();
+    let written = file.save_cover_to(dest.with_extension(""), false).unwrap();
+    assert_eq!(written, dest);
+    assert_eq!(fs::read(&written).unwrap(), fixture_bytes("cover.png"));
+    // refusing to overwrite it, unless asked to
+    match file.save_cover_to(&dest, false) {
+        Err(taglib::PictureError::AlreadyExists(path)) => assert_eq!(path, dest),
+        other => panic!("expected the file to exist already, got {:?}", other),
+    }
+    fs::write(&dest, b"stale").unwrap();
+    assert_eq!(file.save_cover_to(&dest, true).unwrap(), dest);
+    assert_eq!(fs::read(&dest).unwrap(), fixture_bytes("cover.png"));
+}
+
+#[test]
+fn save_cover_to_checks_extension() {
+    let file = TagLibFile::new("tests/fixtures/cover.mp3").unwrap();
+    let dest = scratch_path("save_cover_to_checks.jpg");
+    match file.save_cover_to(&dest, false) {
+        Err(taglib::PictureError::WrongExtension(path, extension)) => {
+            assert_eq!(path, dest);
+            assert_eq!(extension, "png");
+        }
+        other => panic!("expected the wrong extension, got {:?}", other),
+    }
+    assert!(!dest.exists());
+    let dest = scratch_path("save_cover_to_checks.PNG");
+    assert_eq!(file.save_cover_to(&dest, false).unwrap(), dest);
+}
+
+#[test]
+fn save_cover_to_without_artwork() {
+    let file = TagLibFile::new("tests/fixtures/silence.mp3").unwrap();
+    let dest = scratch_path("save_cover_to_without_artwork");
+    match file.save_cover_to(&dest, false) {
+        Err(taglib::PictureError::NoArtwork) => {}
+        other => panic!("expected no artwork, got {:?}", other),
+    }
+    assert!(!dest.exists());
+    assert!(!dest.with_extension("jpg").exists());
+}
+
+fn dimensions(colour_depth: u32) -> Option<PictureDimensions> {
+    Some(PictureDimensions {
+        width: 1,
+        height: 1,
+        colour_depth,
+        colours: 0,
+    })
+}
+
+#[test]
+fn flac_pictures_with_dimensions() {
+    let file = TagLibFile::new("tests/fixtures/pictures.flac").unwrap();
+    let pictures = file.pictures().unwrap();
+    let summary: Vec<_> = pictures
+        .iter()
+        .map(|p| (p.picture_type, p.mime_type.as_str(), p.description.as_deref(), p.dimensions))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (PictureType::FrontCover, "image/png", Some("Front"), dimensions(24)),
+            (PictureType::BackCover, "image/jpeg", Some("Back"), dimensions(8)),
+            (PictureType::LeafletPage, "image/png", Some("Booklet page 1"), dimensions(32)),
+            (PictureType::LeafletPage, "image/jpeg", Some("Booklet page 2"), dimensions(8)),
+        ]
+    );
+    assert_eq!(pictures[1].data, fixture_bytes("cover.jpg"));
+    // other formats don't keep dimensions
+    assert_eq!(cover_art_of("cover.mp3").dimensions, None);
+}
+
+#[test]
+fn flac_remove_picture() {
+    let path = scratch_copy("pictures.flac", "flac_remove_picture");
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.remove_picture(PictureType::LeafletPage).unwrap();
+        file.save().unwrap();
+    }
+    let mut file = TagLibFile::new(&path).unwrap();
+    let types: Vec<_> = file.pictures().unwrap().iter().map(|p| p.picture_type).collect();
+    assert_eq!(types, vec![PictureType::FrontCover, PictureType::BackCover]);
+    assert_eq!(file.cover_art().unwrap().unwrap().dimensions, dimensions(24));
+    file.remove_picture(PictureType::Media).unwrap();
+    assert!(!file.is_modified());
+}
+
+#[test]
+fn flac_add_picture_with_type() {
+    let path = scratch_copy("pictures.flac", "flac_add_picture_with_type");
+    let mut page = large_jpeg_cover(100);
+    page.picture_type = PictureType::LeafletPage;
+    page.description = Some("Booklet page 3".to_owned());
+    page.dimensions = Some(PictureDimensions {
+        width: 600,
+        height: 400,
+        colour_depth: 8,
+        colours: 256,
+    });
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.add_picture(&page).unwrap();
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    let pictures = file.pictures().unwrap();
+    assert_eq!(pictures.len(), 5);
+    assert_eq!(pictures[4], page);
+    assert_eq!(pictures[0].picture_type, PictureType::FrontCover);
+}
+
+#[test]
+fn m4a_pictures() {
+    let path = scratch_copy("cover.m4a", "m4a_pictures");
+    let mut jpeg = large_jpeg_cover(100);
+    jpeg.mime_type = "IMAGE/JPG".to_owned();
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.add_picture(&jpeg).unwrap();
+        let mut webp = png_cover();
+        webp.mime_type = "image/webp".to_owned();
+        match file.add_picture(&webp) {
+            Err(taglib::PictureError::UnsupportedMimeType(mime_type)) => assert_eq!(mime_type, "image/webp"),
+            other => panic!("expected an unsupported MIME type, got {:?}", other),
+        }
+        file.save().unwrap();
+    }
+    let file = TagLibFile::new(&path).unwrap();
+    let pictures = file.pictures().unwrap();
+    let mime_types: Vec<_> = pictures.iter().map(|p| p.mime_type.as_str()).collect();
+    assert_eq!(mime_types, vec!["image/png", "image/jpeg"]);
+    assert_eq!(pictures[0].data, fixture_bytes("cover.png"));
+    assert_eq!(pictures[1].data, jpeg.data);
+    // the old cover stays first, so it's still the cover
+    assert_eq!(file.cover_art().unwrap().unwrap().data, fixture_bytes("cover.png"));
+}
+
+#[test]
+fn m4a_png_cover_flavour() {
+    let path = scratch_copy("silence.m4a", "m4a_png_cover_flavour");
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.set_cover_art(&png_cover()).unwrap();
+        file.save().unwrap();
+    }
+    // a data atom of type 14, for PNG, rather than 13 for JPEG
+    assert!(file_contains(&path, b"data\x00\x00\x00\x0e\x00\x00\x00\x00\x89PNG"));
+    assert_eq!(TagLibFile::new(&path).unwrap().cover_art().unwrap().unwrap().mime_type, "image/png");
+}
+
+// a PNG header claiming to be 3000x2000, which is all that's read of it
+fn huge_png_header() -> Vec<u8> {
+    let mut data = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR".to_vec();
+    data.extend(&3000u32.to_be_bytes());
+    data.extend(&2000u32.to_be_bytes());
+    data.extend(&[8, 2, 0, 0, 0]);
+    data
+}
+
+#[test]
+fn image_dimensions_from_headers() {
+    assert_eq!(image_dimensions(&fixture_bytes("cover.png")), Some((1, 1)));
+    assert_eq!(image_dimensions(&fixture_bytes("cover.jpg")), Some((1, 1)));
+    assert_eq!(image_dimensions(&huge_png_header()), Some((3000, 2000)));
+    assert_eq!(image_dimensions(b"GIF89a\x40\x01\xf0\x00"), Some((320, 240)));
+    assert_eq!(image_dimensions(b"RIFF\x10\x00\x00\x00WEBPVP8 "), None);
+    assert_eq!(image_dimensions(&[0xFF, 0xD8, 0xFF]), None);
+}
+
+fn constraint_violation(picture: &Picture, constraints: &PictureConstraints) -> ConstraintViolation {
+    let mut file = TagLibFile::new("tests/fixtures/silence.mp3").unwrap();
+    let violation = match file.set_cover_art_with(picture, constraints) {
+        Err(taglib::PictureError::ConstraintViolated(violation)) => violation,
+        other => panic!("expected a constraint to fail, got {:?}", other),
+    };
+    assert!(!file.is_modified());
+    violation
+}
+
+#[test]
+fn picture_constraints_reject() {
+    let cover = png_cover();
+    let small = PictureConstraints {
+        max_bytes: Some(50),
+        ..Default::default()
+    };
+    assert_eq!(
+        constraint_violation(&cover, &small),
+        ConstraintViolation::TooManyBytes { size: 69, limit: 50 }
+    );
+    let jpeg_only = PictureConstraints {
+        allowed_mime: Some(&["image/jpeg"]),
+        ..Default::default()
+    };
+    assert_eq!(
+        constraint_violation(&cover, &jpeg_only),
+        ConstraintViolation::MimeTypeNotAllowed("image/png".to_owned())
+    );
+    let mut huge = png_cover();
+    huge.data = huge_png_header();
+    let limited = PictureConstraints {
+        max_dimensions: Some((1000, 1000)),
+        ..Default::default()
+    };
+    let violation = constraint_violation(&huge, &limited);
+    assert_eq!(
+        violation,
+        ConstraintViolation::TooLarge {
+            dimensions: (3000, 2000),
+            limit: (1000, 1000)
+        }
+    );
+    assert_eq!(violation.to_string(), "picture is 3000x2000, larger than the limit of 1000x1000");
+    let mut webp = png_cover();
+    webp.data = b"RIFF\x10\x00\x00\x00WEBPVP8 ".to_vec();
+    assert_eq!(constraint_violation(&webp, &limited), ConstraintViolation::UnknownDimensions);
+}
+
+#[test]
+fn picture_constraints_accept() {
+    let path = scratch_copy("silence.mp3", "picture_constraints_accept");
+    let constraints = PictureConstraints {
+        max_bytes: Some(1024),
+        max_dimensions: Some((500, 500)),
+        allowed_mime: Some(&["image/jpeg", "IMAGE/PNG"]),
+    };
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.set_cover_art_with(&png_cover(), &constraints).unwrap();
+        file.set_cover_art_with(&png_cover(), &PictureConstraints::default()).unwrap();
+        file.save().unwrap();
+    }
+    assert_eq!(TagLibFile::new(&path).unwrap().pictures().unwrap(), vec![png_cover()]);
+}
+
+fn replace_picture_keeps_others(fixture: &str, name: &str) {
+    let path = scratch_copy(fixture, name);
+    let mut back = large_jpeg_cover(300);
+    back.picture_type = PictureType::BackCover;
+    let mut booklet = large_jpeg_cover(500);
+    booklet.picture_type = PictureType::LeafletPage;
+    booklet.description = Some("Booklet".to_owned());
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        for picture in &[&back, &png_cover(), &booklet] {
+            file.add_picture(picture).unwrap();
+        }
+        file.save().unwrap();
+    }
+    let mut front = large_jpeg_cover(2000);
+    front.picture_type = PictureType::Other;
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.replace_picture(PictureType::FrontCover, &front).unwrap();
+        file.save().unwrap();
+    }
+    front.picture_type = PictureType::FrontCover;
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.pictures().unwrap(), vec![back.clone(), front, booklet.clone()]);
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.remove_picture(PictureType::FrontCover).unwrap();
+        file.save().unwrap();
+    }
+    assert_eq!(TagLibFile::new(&path).unwrap().pictures().unwrap(), vec![back, booklet]);
+}
+
+#[test]
+fn replace_picture_mp3() {
+    replace_picture_keeps_others("silence.mp3", "replace_picture_mp3");
+}
+
+#[test]
+fn replace_picture_flac() {
+    replace_picture_keeps_others("silence.flac", "replace_picture_flac");
+}
+
+#[test]
+fn replace_picture_checks_first() {
+    let path = scratch_copy("pictures.flac", "replace_picture_checks_first");
+    let mut file = TagLibFile::new(&path).unwrap();
+    let before = file.pictures().unwrap();
+    let mut bad = png_cover();
+    bad.description = Some("nul\0".to_owned());
+    assert!(file.replace_picture(PictureType::BackCover, &bad).is_err());
+    assert!(!file.is_modified());
+    assert_eq!(file.pictures().unwrap(), before);
+}
+
+fn tag_containers_of(fixture: &str) -> Vec<TagContainer> {
+    TagLibFile::new(format!("tests/fixtures/{}", fixture)).unwrap().tag_containers()
+}
+
+#[test]
+fn tag_containers() {
+    assert_eq!(
+        tag_containers_of("three_tags.mp3"),
+        vec![TagContainer::Id3v1, TagContainer::Id3v2, TagContainer::Ape]
+    );
+    assert_eq!(tag_containers_of("cover.mp3"), vec![TagContainer::Id3v2]);
+    assert_eq!(tag_containers_of("silence.mp3"), Vec::new());
+    assert_eq!(tag_containers_of("two_artists.flac"), vec![TagContainer::Xiph]);
+    assert_eq!(tag_containers_of("cover.m4a"), vec![TagContainer::Mp4Ilst]);
+}
+
+#[test]
+fn tag_containers_after_save() {
+    let path = scratch_copy("silence.mp3", "tag_containers_after_save");
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_title("Title").unwrap();
+    file.save().unwrap();
+    assert!(file.tag_containers().contains(&TagContainer::Id3v2));
+}
+
+fn save_mpeg_tags(name: &str, tags: &[TagContainer], others: taglib::OtherTags) -> TagLibFile {
+    let path = scratch_copy("three_tags.mp3", name);
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_title("Tïtle ✓").unwrap();
+    file.save_with(taglib::SaveOptions::new().mpeg_tags(tags, others)).unwrap();
+    TagLibFile::new(&path).unwrap()
+}
+
+#[test]
+fn mpeg_tags_strip_others() {
+    let file = save_mpeg_tags("mpeg_tags_strip_others", &[TagContainer::Id3v2], taglib::OtherTags::Strip);
+    assert_eq!(file.tag_containers(), vec![TagContainer::Id3v2]);
+    assert_eq!(file.title().unwrap(), "Tïtle ✓");
+}
+
+#[test]
+fn mpeg_tags_preserve_others() {
+    let file = save_mpeg_tags("mpeg_tags_preserve_others", &[TagContainer::Id3v2], taglib::OtherTags::Preserve);
+    assert_eq!(
+        file.tag_containers(),
+        vec![TagContainer::Id3v1, TagContainer::Id3v2, TagContainer::Ape]
+    );
+    // the ID3v1 tag wasn't written, so still has the old title
+    assert!(file_contains(&PathBuf::from(file.path()), b"TAGID3v1 Title"));
+}
+
+#[test]
+fn mpeg_tags_atomic() {
+    let path = scratch_copy("three_tags.mp3", "mpeg_tags_atomic");
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_title("Title").unwrap();
+    let options = taglib::SaveOptions::new()
+        .atomic(true)
+        .mpeg_tags(&[TagContainer::Id3v2, TagContainer::Ape], taglib::OtherTags::Strip)
+        .clone();
+    file.save_with(&options).unwrap();
+    assert_eq!(file.tag_containers(), vec![TagContainer::Id3v2, TagContainer::Ape]);
+}
+
+#[test]
+fn mpeg_tags_ignored_for_other_formats() {
+    let path = scratch_copy("two_artists.flac", "mpeg_tags_ignored");
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_title("Title").unwrap();
+    file.save_with(taglib::SaveOptions::new().mpeg_tags(&[TagContainer::Id3v1], taglib::OtherTags::Strip))
+        .unwrap();
+    assert_eq!(file.tag_containers(), vec![TagContainer::Xiph]);
+}
+
+#[test]
+#[cfg(unix)]
+fn mpeg_tags_through_std_file() {
+    let path = scratch_copy("three_tags.mp3", "mpeg_tags_through_std_file");
+    let std_file = fs::OpenOptions::new().read(true).write(true).open(&path).unwrap();
+    let mut file = TagLibFile::from_file(std_file, taglib::FileType::Mpeg).unwrap();
+    file.set_title("Title").unwrap();
+    file.save_with(taglib::SaveOptions::new().mpeg_tags(&[TagContainer::Id3v2], taglib::OtherTags::Strip))
+        .unwrap();
+    drop(file);
+    assert_eq!(TagLibFile::new(&path).unwrap().tag_containers(), vec![TagContainer::Id3v2]);
+}
+
+// the frames of a FLAC file, after its metadata blocks
+fn flac_audio(path: &PathBuf) -> Vec<u8> {
+    let data = fs::read(path).unwrap();
+    let mut at = 4;
+    loop {
+        let last = data[at] & 0x80 != 0;
+        let length = usize::from(data[at + 1]) << 16 | usize::from(data[at + 2]) << 8 | usize::from(data[at + 3]);
+        at += 4 + length;
+        if last {
+            return data[at..].to_vec();
+        }
+    }
+}
+
+fn assert_stripped(file: &TagLibFile) {
+    assert_eq!(file.tag_containers(), Vec::new());
+    assert_eq!(file.title_opt().unwrap(), None);
+    assert_eq!(file.artist_opt().unwrap(), None);
+    assert_eq!(file.pictures().unwrap(), Vec::new());
+    assert!(!file.is_modified());
+}
+
+#[test]
+fn strip_all_tags_mp3() {
+    let path = scratch_copy("three_tags.mp3", "strip_all_tags_mp3");
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.strip_all_tags().unwrap();
+    assert_stripped(&file);
+    assert_stripped(&TagLibFile::new(&path).unwrap());
+    // leaving exactly the audio that the tags were wrapped around
+    assert_eq!(fs::read(&path).unwrap(), fixture_bytes("silence.mp3"));
+    // the reopened file can be tagged again
+    file.set_title("Title").unwrap();
+    file.save().unwrap();
+    assert_eq!(TagLibFile::new(&path).unwrap().title().unwrap(), "Title");
+}
+
+#[test]
+fn strip_all_tags_flac() {
+    let path = scratch_copy("pictures.flac", "strip_all_tags_flac");
+    {
+        let mut file = TagLibFile::new(&path).unwrap();
+        file.set_title("Title").unwrap();
+        file.save().unwrap();
+    }
+    let audio = flac_audio(&path);
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.strip_all_tags().unwrap();
+    assert_stripped(&file);
+    assert_stripped(&TagLibFile::new(&path).unwrap());
+    assert_eq!(flac_audio(&path), audio);
+    assert!(fs::metadata(&path).unwrap().len() < fs::metadata(fixture_path("pictures.flac")).unwrap().len());
+}
+
+#[test]
+fn strip_all_tags_m4a() {
+    let path = scratch_copy("cover.m4a", "strip_all_tags_m4a");
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.strip_all_tags().unwrap();
+    assert_stripped(&file);
+    assert_stripped(&TagLibFile::new(&path).unwrap());
+}
+
+#[test]
+fn strip_all_tags_read_only() {
+    let mut file = TagLibFile::open_read_only("tests/fixtures/three_tags.mp3").unwrap();
+    assert!(file.strip_all_tags().is_err());
+    assert_eq!(file.tag_containers().len(), 3);
+}
+
+#[test]
+fn strip_legacy_tags() {
+    let path = scratch_copy("three_tags.mp3", "strip_legacy_tags");
+    let id3v2_length = 10 + 2 * 10 + "\x03ID3v2 Title".len() + "\x03ID3v2 Artist".len();
+    let mut file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.title().unwrap(), "ID3v2 Title");
+    file.strip_legacy_tags().unwrap();
+    assert_eq!(file.tag_containers(), vec![TagContainer::Id3v2]);
+    assert_eq!(file.title().unwrap(), "ID3v2 Title");
+    assert_eq!(file.artist().unwrap(), "ID3v2 Artist");
+    assert!(!file.is_modified());
+    // the ID3v2 tag is byte for byte what it was, followed by just the audio
+    let mut expected = fixture_bytes("three_tags.mp3")[..id3v2_length].to_vec();
+    expected.extend(fixture_bytes("silence.mp3"));
+    assert_eq!(fs::read(&path).unwrap(), expected);
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.tag_containers(), vec![TagContainer::Id3v2]);
+    assert_eq!(file.title().unwrap(), "ID3v2 Title");
+}
+
+#[test]
+fn strip_legacy_tags_keeps_unsaved_changes() {
+    let path = scratch_copy("three_tags.mp3", "strip_legacy_tags_unsaved");
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_album("Album").unwrap();
+    file.strip_legacy_tags().unwrap();
+    assert!(file.is_modified());
+    file.save().unwrap();
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.album().unwrap(), "Album");
+    assert_eq!(file.tag_containers(), vec![TagContainer::Id3v2]);
+}
+
+#[test]
+fn strip_legacy_tags_unsupported() {
+    let path = scratch_copy("two_artists.flac", "strip_legacy_tags_unsupported");
+    let mut file = TagLibFile::new(&path).unwrap();
+    match file.strip_legacy_tags() {
+        Err(taglib::FileError::Unsupported(error_path)) => assert_eq!(error_path, path),
+        other => panic!("expected an unsupported format, got {:?}", other),
+    }
+    let mut file = TagLibFile::open_read_only("tests/fixtures/three_tags.mp3").unwrap();
+    assert!(file.strip_legacy_tags().is_err());
+}
+
+fn save_id3v2_version(name: &str, version: Id3v2Version) -> PathBuf {
+    let path = scratch_copy("silence.mp3", name);
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_title("Title").unwrap();
+    file.set_artists(&["First Artist", "Second Artist"]).unwrap();
+    file.set_year(1999).unwrap();
+    file.set_mood("Calm").unwrap();
+    file.save_with(taglib::SaveOptions::new().id3v2_version(version)).unwrap();
+    path
+}
+
+#[test]
+fn id3v2_version_v3() {
+    let path = save_id3v2_version("id3v2_version_v3", Id3v2Version::V3);
+    // the major version follows the "ID3" magic
+    assert_eq!(&fs::read(&path).unwrap()[..4], b"ID3\x03");
+    assert!(file_contains(&path, b"TYER"));
+    assert!(!file_contains(&path, b"TDRC"));
+    assert!(!file_contains(&path, b"TMOO"));
+    assert!(file_contains(&path, b"TXXX"));
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.title().unwrap(), "Title");
+    assert_eq!(file.year(), Some(1999));
+    assert_eq!(file.mood().unwrap(), Some("Calm".to_owned()));
+    assert_eq!(file.artists().unwrap(), vec!["First Artist/Second Artist".to_owned()]);
+    assert_eq!(file.artists_with(true).unwrap(), vec!["First Artist".to_owned(), "Second Artist".to_owned()]);
+}
+
+#[test]
+fn id3v2_version_v4() {
+    let path = save_id3v2_version("id3v2_version_v4", Id3v2Version::V4);
+    assert_eq!(&fs::read(&path).unwrap()[..4], b"ID3\x04");
+    assert!(file_contains(&path, b"TDRC"));
+    assert!(file_contains(&path, b"TMOO"));
+    let file = TagLibFile::new(&path).unwrap();
+    assert_eq!(file.artists().unwrap(), vec!["First Artist".to_owned(), "Second Artist".to_owned()]);
+}
+
+#[test]
+fn id3v2_version_with_mpeg_tags() {
+    let path = scratch_copy("three_tags.mp3", "id3v2_version_with_mpeg_tags");
+    let mut file = TagLibFile::new(&path).unwrap();
+    file.set_album("Album").unwrap();
+    let options = taglib::SaveOptions::new()
+        .id3v2_version(Id3v2Version::V3)
+        .mpeg_tags(&[TagContainer::Id3v2], taglib::OtherTags::Strip)
+        .clone();
+    file.save_with(&options).unwrap();
+    assert_eq!(&fs::read(&path).unwrap()[..4], b"ID3\x03");
+    assert_eq!(file.tag_containers(), vec![TagContainer::Id3v2]);
+    assert_eq!(TagLibFile::new(&path).unwrap().album().unwrap(), "Album");
+}
+
+#[test]
+fn vendor_string() {
+    let vendor_of = |fixture: &str| TagLibFile::new(format!("tests/fixtures/{}", fixture)).unwrap().vendor_string();
+    assert_eq!(vendor_of("vendor.ogg"), Some("Lavf58.29.100".to_owned()));
+    assert_eq!(vendor_of("two_artists.flac"), Some("reference libFLAC 1.3.2 20170101".to_owned()));
+    assert_eq!(vendor_of("silence.mp3"), None);
+    assert_eq!(vendor_of("cover.m4a"), None);
+    let file = TagLibFile::new("tests/fixtures/vendor.ogg").unwrap();
+    assert_eq!(file.title().unwrap(), "Vendor test");
+}